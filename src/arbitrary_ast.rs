@@ -0,0 +1,386 @@
+//! This module implements `Arbitrary` for the syntax tree, when the `arbitrary` feature is
+//! enabled. It is meant for property tests and fuzzers.
+//!
+//! Only trees that could have come from valid source text are generated. For example,
+//! identifiers are never reserved words, number literals are never negative, assignment
+//! targets are identifiers or member expressions, and spread elements only show up in
+//! array literals and argument lists. Context dependent expressions like `super`,
+//! `new.target` and `yield` are not generated, and neither are legacy octal numbers like
+//! `017`, since expressions are generated without knowing if the code is strict.
+//!
+//! Trees are at most `MAX_DEPTH` nodes deep, as measured by `Stats::max_depth`.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use ast::*;
+use parser::is_reserved_word;
+use span::Span;
+
+const MAX_DEPTH: usize = 8;
+
+const MAX_LENGTH: usize = 4;
+
+const ID_START: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_$";
+
+const ID_CONTINUE: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_$0123456789";
+
+impl<'a> Arbitrary<'a> for Program {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Program> {
+        Ok(Program {
+            source_type: u.arbitrary()?,
+            // there are no statements to generate yet
+            body: Vec::new(),
+            // there is no source text for these to describe
+            span: Span::default(),
+            hashbang: None,
+            comments: Vec::new(),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for SourceType {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<SourceType> {
+        Ok(if u.arbitrary()? {
+            SourceType::Module
+        } else {
+            SourceType::Script
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Expression {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Expression> {
+        expression(u, MAX_DEPTH)
+    }
+}
+
+impl<'a> Arbitrary<'a> for JsString {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<JsString> {
+        // most strings are well formed, but some have lone surrogates
+        Ok(if u.ratio(1, 4)? {
+            JsString::from_utf16(&Vec::<u16>::arbitrary(u)?)
+        } else {
+            JsString::from(String::arbitrary(u)?)
+        })
+    }
+}
+
+/// This generates an expression that is at most `depth` nodes deep.
+fn expression(u: &mut Unstructured, depth: usize) -> Result<Expression> {
+    if depth <= 1 || u.is_empty() {
+        return leaf(u);
+    }
+    let depth = depth - 1;
+    Ok(match u.int_in_range(0..=12)? {
+        0 => leaf(u)?,
+        1 => Expression::ArrayLiteral(elements(u, depth)?),
+        2 => {
+            let mut properties = Vec::new();
+            // the property is one level, and the key and value are another
+            if depth >= 2 {
+                for _ in 0..u.int_in_range(0..=MAX_LENGTH)? {
+                    properties.push(Property {
+                        key: property_key(u)?,
+                        value: expression(u, depth - 1)?,
+                        kind: PropertyKind::Init,
+                    });
+                }
+            }
+            Expression::ObjectLiteral(properties)
+        }
+        3 => {
+            let mut elements = vec![template_element(u)?];
+            for _ in 0..u.int_in_range(0..=MAX_LENGTH)? {
+                elements.push(TemplateLiteralElement::Expression(expression(u, depth)?));
+                elements.push(template_element(u)?);
+            }
+            Expression::TemplateLiteral(elements)
+        }
+        4 => member(u, depth)?,
+        5 => Expression::Call(Box::new(Call {
+            callee: expression(u, depth)?,
+            arguments: elements(u, depth)?,
+        })),
+        6 => Expression::New(Box::new(New {
+            callee: expression(u, depth)?,
+            arguments: elements(u, depth)?,
+        })),
+        7 => Expression::Update {
+            operator: u
+                .choose(&[UpdateOperator::Increment, UpdateOperator::Decrement])?
+                .clone(),
+            argument: Box::new(assignment_target(u, depth)?),
+            prefix: u.arbitrary()?,
+        },
+        8 => {
+            let operator = u.choose(UNARY_OPERATORS)?.clone();
+            // deleting an identifier is an error in strict mode code
+            let argument = if operator == UnaryOperator::Delete {
+                if depth >= 2 {
+                    member(u, depth - 1)?
+                } else {
+                    Expression::This
+                }
+            } else {
+                expression(u, depth)?
+            };
+            Expression::Unary {
+                operator,
+                argument: Box::new(argument),
+            }
+        }
+        9 => Expression::Binary {
+            operator: u.choose(BINARY_OPERATORS)?.clone(),
+            lhs: Box::new(expression(u, depth)?),
+            rhs: Box::new(expression(u, depth)?),
+        },
+        10 => Expression::Conditional {
+            test: Box::new(expression(u, depth)?),
+            alternate: Box::new(expression(u, depth)?),
+            consequent: Box::new(expression(u, depth)?),
+        },
+        11 => Expression::Assignment {
+            operator: u.choose(ASSIGNMENT_OPERATORS)?.clone(),
+            lhs: Box::new(assignment_target(u, depth)?),
+            rhs: Box::new(expression(u, depth)?),
+        },
+        _ => {
+            let mut expressions = vec![expression(u, depth)?, expression(u, depth)?];
+            for _ in 0..u.int_in_range(0..=MAX_LENGTH - 2)? {
+                expressions.push(expression(u, depth)?);
+            }
+            Expression::Comma(expressions)
+        }
+    })
+}
+
+fn leaf(u: &mut Unstructured) -> Result<Expression> {
+    Ok(match u.int_in_range(0..=5)? {
+        0 => Expression::This,
+        1 => Expression::IdReference(identifier(u)?),
+        2 => Expression::Literal(ExpressionLiteral::NullLiteral(NullLiteral)),
+        3 => Expression::Literal(ExpressionLiteral::BooleanLiteral(u.arbitrary()?)),
+        4 => Expression::Literal(ExpressionLiteral::NumberLiteral(number(u)?)),
+        _ => Expression::Literal(ExpressionLiteral::StringLiteral(Box::new(
+            JsString::arbitrary(u)?.into(),
+        ))),
+    })
+}
+
+/// This generates the elements of an array literal or an argument list, which are allowed
+/// to be spread.
+fn elements(u: &mut Unstructured, depth: usize) -> Result<Vec<Expression>> {
+    let mut elements = Vec::new();
+    for _ in 0..u.int_in_range(0..=MAX_LENGTH)? {
+        if depth >= 2 && u.ratio(1, 4)? {
+            elements.push(Expression::Spread(Box::new(expression(u, depth - 1)?)));
+        } else {
+            elements.push(expression(u, depth)?);
+        }
+    }
+    Ok(elements)
+}
+
+/// This generates a member expression, with children that are at most `depth` nodes deep.
+/// If it is not computed, the property is always an identifier, eg. `a.b`.
+fn member(u: &mut Unstructured, depth: usize) -> Result<Expression> {
+    let computed = u.arbitrary()?;
+    let rhs = if computed {
+        expression(u, depth)?
+    } else {
+        Expression::IdReference(identifier(u)?)
+    };
+    Ok(Expression::Member {
+        lhs: Box::new(expression(u, depth)?),
+        rhs: Box::new(rhs),
+        computed,
+    })
+}
+
+fn assignment_target(u: &mut Unstructured, depth: usize) -> Result<Expression> {
+    if depth >= 2 && u.arbitrary()? {
+        member(u, depth - 1)
+    } else {
+        Ok(Expression::IdReference(identifier(u)?))
+    }
+}
+
+fn property_key(u: &mut Unstructured) -> Result<Expression> {
+    Ok(if u.arbitrary()? {
+        Expression::IdReference(identifier(u)?)
+    } else {
+        Expression::Literal(ExpressionLiteral::StringLiteral(Box::new(
+            JsString::arbitrary(u)?.into(),
+        )))
+    })
+}
+
+/// This generates a template chunk without any characters that would need escaping, so
+/// the raw and cooked strings are the same.
+fn template_element(u: &mut Unstructured) -> Result<TemplateLiteralElement> {
+    let text: String = u.arbitrary()?;
+    let text: String = text
+        .chars()
+        .filter(|&c| c != '`' && c != '\\' && c != '$' && c != '\r')
+        .collect();
+    Ok(TemplateLiteralElement::TemplateElement(TemplateElement {
+        cooked: Some(text.clone().into()),
+        raw: text,
+    }))
+}
+
+fn identifier(u: &mut Unstructured) -> Result<Id> {
+    let mut id = String::new();
+    id.push(*u.choose(ID_START)? as char);
+    for _ in 0..u.int_in_range(0..=MAX_LENGTH)? {
+        id.push(*u.choose(ID_CONTINUE)? as char);
+    }
+    if is_reserved_word(&id, true) {
+        id.push('_');
+    }
+    Ok(id.into())
+}
+
+/// Number literals are never negative, since `-1` is a unary expression.
+fn number(u: &mut Unstructured) -> Result<NumberLiteral> {
+    let n: f64 = u.arbitrary()?;
+    let value = if n.is_finite() { n.abs() } else { 0.0 };
+    // only integers can be written in the other notations
+    if value.fract() != 0.0 || value > 2f64.powi(53) {
+        return Ok(value.into());
+    }
+    let kind = *u.choose(&[
+        NumberKind::Decimal,
+        NumberKind::Binary,
+        NumberKind::Octal,
+        NumberKind::Hex,
+    ])?;
+    Ok(NumberLiteral {
+        value,
+        kind,
+        raw: None,
+    })
+}
+
+const UNARY_OPERATORS: &[UnaryOperator] = &[
+    UnaryOperator::Minus,
+    UnaryOperator::Plus,
+    UnaryOperator::Not,
+    UnaryOperator::BitwiseNot,
+    UnaryOperator::Typeof,
+    UnaryOperator::Void,
+    UnaryOperator::Delete,
+];
+
+const BINARY_OPERATORS: &[BinaryOperator] = &[
+    BinaryOperator::EqEq,
+    BinaryOperator::NotEq,
+    BinaryOperator::EqEqEq,
+    BinaryOperator::NotEqEq,
+    BinaryOperator::Lt,
+    BinaryOperator::Lte,
+    BinaryOperator::Gt,
+    BinaryOperator::Gte,
+    BinaryOperator::Shl,
+    BinaryOperator::Shr,
+    BinaryOperator::UnsignedShr,
+    BinaryOperator::Plus,
+    BinaryOperator::Minus,
+    BinaryOperator::Multiply,
+    BinaryOperator::Divide,
+    BinaryOperator::Mod,
+    BinaryOperator::BitwiseOr,
+    BinaryOperator::Or,
+    BinaryOperator::BitwiseXor,
+    BinaryOperator::BitwiseAnd,
+    BinaryOperator::And,
+    BinaryOperator::In,
+    BinaryOperator::InstanceOf,
+    BinaryOperator::Exponentiation,
+];
+
+const ASSIGNMENT_OPERATORS: &[AssignmentOperator] = &[
+    AssignmentOperator::Eq,
+    AssignmentOperator::PlusEq,
+    AssignmentOperator::MinusEq,
+    AssignmentOperator::MultiplyEq,
+    AssignmentOperator::DivideEq,
+    AssignmentOperator::ModEq,
+    AssignmentOperator::ShlEq,
+    AssignmentOperator::ShrEq,
+    AssignmentOperator::UnsignedShrEq,
+    AssignmentOperator::BitwiseOrEq,
+    AssignmentOperator::BitwiseXorEq,
+    AssignmentOperator::BitwiseAndEq,
+];
+
+#[cfg(test)]
+mod arbitrary_tests {
+    use super::*;
+
+    /// A tiny xorshift generator, so the tests do not need a dependency for random bytes.
+    fn bytes(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_depth_budget() {
+        for seed in 0..500 {
+            let data = bytes(seed, 4096);
+            let expression = Expression::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            assert!(
+                expression.stats().max_depth <= MAX_DEPTH,
+                "{:?}",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn test_sexp_round_trip() {
+        for seed in 0..500 {
+            let data = bytes(seed, 4096);
+            let expression = Expression::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            let sexp = expression.to_sexp();
+            assert_eq!(::sexp::parse_expression(&sexp), Ok(expression), "{}", sexp);
+        }
+    }
+
+    #[test]
+    fn test_valid_trees() {
+        // only the root is checked, but every kind of expression shows up as a root
+        fn check(expression: &Expression) {
+            match *expression {
+                Expression::IdReference(ref id) => assert!(!is_reserved_word(id, true)),
+                Expression::Literal(ExpressionLiteral::NumberLiteral(ref n)) => {
+                    assert!(n.value.is_finite() && n.value >= 0.0);
+                    assert!(n.kind == NumberKind::Decimal || n.value.fract() == 0.0);
+                    assert!(n.kind != NumberKind::LegacyOctal);
+                }
+                Expression::Assignment { ref lhs, .. }
+                | Expression::Update {
+                    argument: ref lhs, ..
+                } => match **lhs {
+                    Expression::IdReference(_) | Expression::Member { .. } => {}
+                    ref other => panic!("invalid assignment target {:?}", other),
+                },
+                _ => {}
+            }
+        }
+        for seed in 0..500 {
+            let data = bytes(seed, 4096);
+            check(&Expression::arbitrary(&mut Unstructured::new(&data)).unwrap());
+        }
+        assert_eq!(
+            Expression::arbitrary(&mut Unstructured::new(&[])).unwrap(),
+            Expression::This
+        );
+    }
+}
@@ -11,6 +11,11 @@
 //! module as they abstract away the types in such a way so that the user of the library
 //! feels as if they are working with source text almost directly.
 
+pub use atom::Atom;
+pub use js_string::JsString;
+use parser::is_line_terminator;
+use span::{Span, Spanned};
+
 /// NullLiteral is the syntax element for `null`.
 /// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-null-literals)
 #[derive(Debug, Clone, PartialEq)]
@@ -21,20 +26,106 @@ pub struct NullLiteral;
 pub type BooleanLiteral = bool;
 
 /// NumberLiteral is the syntax element for numbers. The parser will convert the string
-/// values into an f64 for the sake of simplicity.
+/// values into an f64 for the sake of simplicity, and remember which notation was used so
+/// the number can be written back the same way, eg. `0xFF` instead of `255`.
 /// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-numeric-literals)
-pub type NumberLiteral = f64;
+#[derive(Debug, Clone)]
+pub struct NumberLiteral {
+    /// The value of the number.
+    pub value: f64,
+    /// The notation that the number was written in.
+    pub kind: NumberKind,
+    /// The source text of the number, eg. `1_000` or `0XFF`, if the parser read it. It is
+    /// `None` for numbers that were built some other way, eg. by hand or from JSON.
+    pub raw: Option<Box<str>>,
+}
+
+/// Two number literals are equal if they have the same value and notation, even if they
+/// are spelled differently, eg. `1_000` and `1000`.
+impl PartialEq for NumberLiteral {
+    fn eq(&self, other: &NumberLiteral) -> bool {
+        self.value == other.value && self.kind == other.kind
+    }
+}
+
+/// A decimal number literal with the value.
+impl From<f64> for NumberLiteral {
+    fn from(value: f64) -> NumberLiteral {
+        NumberLiteral {
+            value,
+            kind: NumberKind::Decimal,
+            raw: None,
+        }
+    }
+}
+
+/// NumberKind is the notation of a number literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NumberKind {
+    /// eg. `10`, `1.5` or `1e3`, and also `089` in sloppy mode.
+    Decimal,
+    /// eg. `0b1010`
+    Binary,
+    /// eg. `0o17`
+    Octal,
+    /// eg. `0xFF`
+    Hex,
+    /// eg. `017`, which is only allowed in sloppy mode.
+    LegacyOctal,
+}
+
+impl NumberKind {
+    /// The base that the digits are written in.
+    pub fn radix(self) -> u32 {
+        match self {
+            NumberKind::Decimal => 10,
+            NumberKind::Binary => 2,
+            NumberKind::Octal | NumberKind::LegacyOctal => 8,
+            NumberKind::Hex => 16,
+        }
+    }
+}
 
 /// StringLiteral is a syntax element with quotes (single or double).
 /// eg. `'my string literal'` or `"my other string literal"`
+///
+/// The value is a `JsString`, so escapes of lone surrogates like `"\uD800"` are kept exactly.
 /// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-literals-string-literals)
-pub type StringLiteral = String;
+#[derive(Debug, Clone)]
+pub struct StringLiteral {
+    /// The value of the string, with the escapes replaced.
+    pub value: JsString,
+    /// The source text of the string with the quotes, eg. `'\07'` or `'\7'`, if the parser
+    /// read it. It is `None` for strings that were built some other way, eg. by hand or
+    /// from JSON.
+    pub raw: Option<Box<str>>,
+}
+
+/// Two string literals are equal if they have the same value, even if they are spelled
+/// differently, eg. `"a"` and `'\x61'`.
+impl PartialEq for StringLiteral {
+    fn eq(&self, other: &StringLiteral) -> bool {
+        self.value == other.value
+    }
+}
+
+/// A string literal with the value.
+impl<T: Into<JsString>> From<T> for StringLiteral {
+    fn from(value: T) -> StringLiteral {
+        StringLiteral {
+            value: value.into(),
+            raw: None,
+        }
+    }
+}
 
 /// Id is an identifier in the ecmascript language.
 /// eg. `var foo = {};`
 /// `foo` is the identifier.
+///
+/// Identifiers are interned, so cloning and comparing them is cheap.
 /// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-identifier-names).
-pub type Id = String;
+pub type Id = Atom;
 
 /// RegexLiteral is the syntax element of a regular expression.
 /// eg. `/abc[123]/gi`
@@ -56,7 +147,9 @@ pub struct TemplateElement {
     /// If the template element has any sort of escape sequences (eg. \u{2028})
     /// this will represent the evaluated result of that sequence.
     /// eg. if raw == "\u{41}", cooked = "A"
-    pub cooked: String,
+    /// This is `None` if the template was parsed without cooking it, see
+    /// `TemplateElement::cook`.
+    pub cooked: Option<JsString>,
     /// This will store the exact string value, before being evaluted into the unicode
     /// code points.
     pub raw: String,
@@ -67,6 +160,9 @@ pub struct TemplateElement {
 ///
 /// This represents all possible computations that can be done in the ecmascript language.
 ///
+/// The variants with a lot of fields are boxed, so that the enum stays small. This matters
+/// because expressions are stored in vectors all over the syntax tree.
+///
 /// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-ecmascript-language-expressions)
 /// [Primary Expression](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-primary-expression)
 /// [Left Hand Side Expressions](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-left-hand-side-expressions)
@@ -87,23 +183,11 @@ pub enum Expression {
     /// A function expression is a function defined in an expression position.
     /// Arrow functions are one where the body is a single statement that is an expression
     /// statement.
-    Function {
-        /// A function expression can be anonymous, where it has no name.
-        id: Option<Id>,
-        /// The formal parameters to a function.
-        params: Vec<Id>,
-        /// The body is a list of statements. This can include pragmas.
-        body: Vec<Statement>,
-        /// This is true if the function was defined with the `async` keyword before the
-        /// `function` keyword.
-        async: bool,
-        /// This is true if there is a `*` character after the `function` keyword.
-        generator: bool,
-    },
+    Function(Box<Function>),
     // Class,
     /// A regex literal can be used in expression position.
     /// eg (/asd/.test(123))
-    RegexLiteral(RegexLiteral),
+    RegexLiteral(Box<RegexLiteral>),
     /// A Template literal expression has many template elements with expressions littered
     /// between.
     ///
@@ -133,20 +217,9 @@ pub enum Expression {
     MetaProperty,
     /// This is the `new MemberExpression` expression. It will construct the callee
     /// and return an object.
-    New {
-        /// The callee is the function we are trying to construct.
-        callee: Box<Expression>,
-        /// The arguments is a list of parameters to the function we're trying to construct.
-        arguments: Vec<Expression>,
-    },
+    New(Box<New>),
     /// This is a regular function call, eg. `myFunction(expr1, expr2)`
-    Call {
-        /// The callee is the function we're trying to call. It may be an IIFE (immediately
-        /// invoked function expression) or any other dynamic function.
-        callee: Box<Expression>,
-        /// The list of parameters to pass to the function.
-        arguments: Vec<Expression>,
-    },
+    Call(Box<Call>),
     /// This is an expression where we pass the elements of the template literal to the
     /// tag function.
     ///
@@ -238,20 +311,66 @@ pub enum Expression {
     /// The JsxElement is an inlined expression of the form:
     /// <name key={value}>
     /// The JsxElement must be matched by a closing element, or else it is a syntax error.
-    JsxElement {
-        /// The name of the element to construct.
-        name: String,
-        /// The key={value} pairs.
-        attributes: Vec<JsxAttribute>,
-        /// The child elements.
-        children: Vec<Expression>,
-    },
+    JsxElement(Box<JsxElement>),
     ///*NOTE*: This is an extension to the language proposed by facebook.
     /// This is an anonymous JsxElement, used when you want to return an array of
     /// elements without actually wrapping things into an unneeded DOM element.
     JsxFragment(Vec<Expression>),
 }
 
+/// A function expression is a function defined in an expression position.
+/// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-function-definitions)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    /// A function expression can be anonymous, where it has no name.
+    pub id: Option<Id>,
+    /// The formal parameters to a function.
+    pub params: Vec<Id>,
+    /// The body is a list of statements. This can include pragmas.
+    pub body: Vec<Statement>,
+    /// This is true if the function was defined with the `async` keyword before the
+    /// `function` keyword.
+    pub async: bool,
+    /// This is true if there is a `*` character after the `function` keyword.
+    pub generator: bool,
+}
+
+/// This is the `new MemberExpression` expression, eg. `new Foo(bar)`.
+/// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-new-operator)
+#[derive(Debug, Clone, PartialEq)]
+pub struct New {
+    /// The callee is the function we are trying to construct.
+    pub callee: Expression,
+    /// The arguments is a list of parameters to the function we're trying to construct.
+    pub arguments: Vec<Expression>,
+}
+
+/// This is a regular function call, eg. `myFunction(expr1, expr2)`
+/// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-function-calls)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call {
+    /// The callee is the function we're trying to call. It may be an IIFE (immediately
+    /// invoked function expression) or any other dynamic function.
+    pub callee: Expression,
+    /// The list of parameters to pass to the function.
+    pub arguments: Vec<Expression>,
+}
+
+/// *NOTE*: This is an extension to the language proposed by facebook.
+/// The JsxElement is an inlined expression of the form:
+/// <name key={value}>
+///
+/// [Reference](https://facebook.github.io/jsx/)
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsxElement {
+    /// The name of the element to construct.
+    pub name: Atom,
+    /// The key={value} pairs.
+    pub attributes: Vec<JsxAttribute>,
+    /// The child elements.
+    pub children: Vec<Expression>,
+}
+
 /// This represents the Literal production of the PrimaryExpression rule.
 /// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#prod-Literal)
 #[derive(Debug, Clone, PartialEq)]
@@ -262,8 +381,8 @@ pub enum ExpressionLiteral {
     BooleanLiteral(BooleanLiteral),
     /// This is a wrapper around the number literal.
     NumberLiteral(NumberLiteral),
-    /// This is a wrapper around the string literal.
-    StringLiteral(StringLiteral),
+    /// This is a wrapper around the string literal. It is boxed to keep expressions small.
+    StringLiteral(Box<StringLiteral>),
 }
 
 /// An object property is a tuple of a key, value, and a tag representing what kind of
@@ -469,7 +588,7 @@ pub enum JsxAttribute {
     /// the existence of the key is more important than the value of the key.
     JsxAttribute {
         /// The key of the attribute.
-        name: String,
+        name: Atom,
         /// The optional value. If it is None, then it means the value is a boolean true.
         /// The absence of a key can mean false.
         value: Option<Expression>,
@@ -493,6 +612,63 @@ pub struct Program {
     pub source_type: SourceType,
     /// The list of statements or declarations made by the source text.
     pub body: Vec<Statement>,
+    /// The span of the whole source text.
+    pub span: Span,
+    /// The text after `#!` if the source starts with a hashbang line, eg.
+    /// `/usr/bin/env node` for `#!/usr/bin/env node`.
+    pub hashbang: Option<String>,
+    /// Every comment in the source text, in order. Comments are not part of the syntax
+    /// tree, so this is the only place they are kept.
+    pub comments: Vec<Comment>,
+}
+
+impl Spanned for Program {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// Comment is a comment in the source text.
+/// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-comments)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// The text between the delimiters, eg. ` hello ` for `/* hello */`.
+    pub text: String,
+    /// The span of the whole comment, including the delimiters.
+    pub span: Span,
+    /// How the comment was written.
+    pub kind: CommentKind,
+}
+
+impl Comment {
+    /// This is true if the text of the comment has a line terminator in it. A block
+    /// comment like that separates the code around it the same way a new line does, so a
+    /// semicolon can be inserted, eg. `a /*\n*/ b` is `a; b`. The line terminator after a
+    /// line comment is not part of the comment, so this is always false for them.
+    pub fn has_line_terminator(&self) -> bool {
+        self.text.chars().any(is_line_terminator)
+    }
+}
+
+/// CommentKind is the syntax that a comment was written with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommentKind {
+    /// A comment that runs to the end of the line, eg. `// hello`.
+    Line,
+    /// A comment between `/*` and `*/`, which can span many lines.
+    Block,
+    /// An HTML-like comment that runs to the end of the line, eg. `<!-- hello`. These are
+    /// only allowed in a Script.
+    HtmlOpen,
+    /// An HTML-like comment at the start of a line that runs to the end of it, eg.
+    /// `--> hello`. These are only allowed in a Script.
+    HtmlClose,
+}
+
+impl Spanned for Comment {
+    fn span(&self) -> Span {
+        self.span
+    }
 }
 
 /// This enum represents whether or not the source code contains an ECMAScript module.
@@ -508,3 +684,15 @@ pub enum SourceType {
     /// differently than a regular script.
     Module,
 }
+
+#[cfg(test)]
+mod ast_tests {
+    use super::*;
+    use std::mem::size_of;
+
+    #[test]
+    fn test_expression_size() {
+        // if this fails, the new variant should probably be boxed
+        assert!(size_of::<Expression>() <= 4 * size_of::<usize>());
+    }
+}
@@ -0,0 +1,191 @@
+//! This module contains the interned string type used for names in the AST.
+//!
+//! Identifiers are repeated a lot in real programs, so instead of allocating a new
+//! `String` for every occurrence, each distinct name is stored once in a global interner
+//! and shared. Because every atom with the same text points at the same allocation,
+//! comparing two atoms is a pointer comparison.
+//!
+//! The interner is split into shards by the hash of the text, so threads that parse in
+//! parallel rarely wait for each other. A string that no atom uses anymore is freed the
+//! next time its shard grows, so parsing arbitrary input, eg. when fuzzing, does not keep
+//! every name that was ever seen.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+const SHARDS: usize = 16;
+
+// a shard is only swept for unused strings when it has grown to twice what was left after
+// the last sweep, so the cost of sweeping is spread over the insertions
+const MIN_SWEEP_LEN: usize = 64;
+
+struct Shard {
+    strings: HashSet<Arc<str>>,
+    sweep_len: usize,
+}
+
+impl Shard {
+    // a string that is only held by the shard is not used by any atom, and no atom can be
+    // made for it without locking the shard
+    fn sweep(&mut self) {
+        self.strings.retain(|string| Arc::strong_count(string) > 1);
+        self.sweep_len = (self.strings.len() * 2).max(MIN_SWEEP_LEN);
+    }
+}
+
+lazy_static! {
+    static ref INTERNER: Vec<Mutex<Shard>> = (0..SHARDS)
+        .map(|_| {
+            Mutex::new(Shard {
+                strings: HashSet::new(),
+                sweep_len: MIN_SWEEP_LEN,
+            })
+        })
+        .collect();
+}
+
+fn shard(text: &str) -> &'static Mutex<Shard> {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    &INTERNER[hasher.finish() as usize % SHARDS]
+}
+
+/// Atom is an interned, immutable string. Cloning an atom is cheap, and comparing two
+/// atoms does not look at their contents.
+#[derive(Clone)]
+pub struct Atom(Arc<str>);
+
+impl Atom {
+    /// This returns the atom for the given text, interning it if it has not been seen
+    /// before.
+    pub fn new(text: &str) -> Atom {
+        let mut shard = shard(text).lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(existing) = shard.strings.get(text) {
+            return Atom(existing.clone());
+        }
+        if shard.strings.len() >= shard.sweep_len {
+            shard.sweep();
+        }
+        let interned: Arc<str> = Arc::from(text);
+        shard.strings.insert(interned.clone());
+        Atom(interned)
+    }
+
+    /// This returns the text of the atom.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Atom {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Atom {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> From<&'a str> for Atom {
+    fn from(text: &'a str) -> Atom {
+        Atom::new(text)
+    }
+}
+
+impl From<String> for Atom {
+    fn from(text: String) -> Atom {
+        Atom::new(&text)
+    }
+}
+
+impl<'a> From<&'a String> for Atom {
+    fn from(text: &'a String) -> Atom {
+        Atom::new(text)
+    }
+}
+
+impl PartialEq for Atom {
+    fn eq(&self, other: &Atom) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Atom {}
+
+impl Hash for Atom {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.0.as_ptr() as usize).hash(state)
+    }
+}
+
+impl PartialEq<str> for Atom {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Atom {
+    fn eq(&self, other: &&'a str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl fmt::Debug for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod atom_tests {
+    use super::*;
+
+    #[test]
+    fn test_interning() {
+        let a = Atom::new("foo");
+        let b = Atom::from("foo".to_string());
+        assert_eq!(a, b);
+        assert_eq!(a.as_ptr(), b.as_ptr());
+        assert_ne!(a, Atom::new("bar"));
+        assert_eq!(a, "foo");
+    }
+
+    #[test]
+    fn test_unused_strings_are_freed() {
+        let is_interned = |text: &str| {
+            let shard = shard(text).lock().unwrap();
+            shard.strings.contains(text)
+        };
+        let a = Atom::new("test_unused_strings_are_freed");
+        drop(Atom::new("test_unused_strings_are_freed_too"));
+        let sweep = |text: &str| shard(text).lock().unwrap().sweep();
+        sweep("test_unused_strings_are_freed");
+        sweep("test_unused_strings_are_freed_too");
+        assert!(is_interned(&a));
+        assert!(!is_interned("test_unused_strings_are_freed_too"));
+        // an atom made after the sweep is still equal to the ones made before it
+        assert_eq!(a, Atom::new("test_unused_strings_are_freed"));
+    }
+
+    #[test]
+    fn test_formatting() {
+        let a = Atom::new("foo");
+        assert_eq!(format!("{}", a), "foo");
+        assert_eq!(format!("{:?}", a), "\"foo\"");
+    }
+}
@@ -0,0 +1,123 @@
+//! This module parses many files at once, eg. every file in a project.
+//!
+//! With the `parallel` feature enabled, the files are read and parsed on a rayon thread
+//! pool. Without it they are parsed one after the other. The results are the same either
+//! way.
+
+use ast::{Program, SourceType};
+use failure::Error;
+use parser::parse_as;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// ParsedFiles is the result of parsing a set of files.
+#[derive(Debug)]
+pub struct ParsedFiles {
+    /// The programs that parsed successfully, keyed by their path.
+    pub programs: BTreeMap<PathBuf, Program>,
+    /// The files that could not be read or parsed, in the order they were given.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ParsedFiles {
+    /// This is true if every file was parsed successfully.
+    pub fn is_ok(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+/// Diagnostic is an error that happened in a single file.
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// The file that the error happened in.
+    pub path: PathBuf,
+    /// The io error or the syntax error. Syntax errors can be downcast to a `SyntaxError`.
+    pub error: Error,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+/// This reads and parses every file. A file that fails does not stop the others from
+/// being parsed. Each file is parsed as a script or a module depending on its path, see
+/// `SourceType::from_path`.
+pub fn parse_many<P: AsRef<Path> + Sync>(paths: &[P]) -> ParsedFiles {
+    #[cfg(feature = "parallel")]
+    let results: Vec<_> = paths.par_iter().map(parse_file).collect();
+    #[cfg(not(feature = "parallel"))]
+    let results: Vec<_> = paths.iter().map(parse_file).collect();
+
+    let mut parsed = ParsedFiles {
+        programs: BTreeMap::new(),
+        diagnostics: Vec::new(),
+    };
+    for (path, result) in results {
+        match result {
+            Ok(program) => {
+                parsed.programs.insert(path, program);
+            }
+            Err(error) => parsed.diagnostics.push(Diagnostic { path, error }),
+        }
+    }
+    parsed
+}
+
+fn parse_file<P: AsRef<Path>>(path: &P) -> (PathBuf, Result<Program, Error>) {
+    let path = path.as_ref();
+    let result = fs::read_to_string(path)
+        .map_err(Error::from)
+        .and_then(|source| parse_as(&source, SourceType::from_path(path)));
+    (path.to_path_buf(), result)
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use super::*;
+    use parser::SyntaxError;
+
+    #[test]
+    fn test_parse_many() {
+        let root = ::std::env::temp_dir().join(format!("ecma-batch-{}", ::std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        // without this, the source type would come from whatever is above the temp dir
+        fs::write(root.join("package.json"), r#"{"type": "commonjs"}"#).unwrap();
+        fs::write(root.join("a.js"), "").unwrap();
+        fs::write(root.join("b.js"), "a").unwrap();
+
+        let paths = vec![
+            root.join("b.js"),
+            root.join("a.js"),
+            root.join("missing.js"),
+        ];
+        let parsed = parse_many(&paths);
+        assert!(!parsed.is_ok());
+        assert_eq!(
+            parsed.programs.keys().collect::<Vec<_>>(),
+            vec![&root.join("a.js")]
+        );
+        assert_eq!(
+            parsed.programs[&root.join("a.js")].source_type,
+            SourceType::Script
+        );
+        assert_eq!(parsed.diagnostics.len(), 2);
+        assert_eq!(parsed.diagnostics[0].path, root.join("b.js"));
+        assert!(parsed.diagnostics[0]
+            .error
+            .downcast_ref::<SyntaxError>()
+            .is_some());
+        assert_eq!(parsed.diagnostics[1].path, root.join("missing.js"));
+        assert!(parsed.diagnostics[1]
+            .error
+            .downcast_ref::<SyntaxError>()
+            .is_none());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
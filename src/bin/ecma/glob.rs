@@ -0,0 +1,287 @@
+//! A small glob implementation, so that patterns like `src/**/*.js` work the same on
+//! every platform, even when the shell does not expand them for us.
+//!
+//! - `*` matches any run of characters inside a single path component.
+//! - `?` matches a single character.
+//! - `[abc]`, `[a-z]` and `[!abc]` match a single character from (or not from) a set.
+//! - `**` as a whole component matches any number of directories, including none. At the
+//!   end of a pattern it matches every file below the directory, so `src/**` is `src/**/*`.
+//!
+//! Like most shells, wildcards do not match names starting with `.` unless the pattern
+//! component starts with a `.` as well. `**` does not follow symbolic links to
+//! directories, so a link cycle can not make it loop forever.
+//!
+//! Directories that can not be read are skipped, with a warning that is returned to the
+//! caller instead of printed, so it can decide when to show it.
+//!
+//! This is written by hand instead of using the `glob` crate to keep the dependencies of
+//! the command line tool down to the parser itself.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// This returns true if the argument contains any glob syntax.
+pub fn is_pattern(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// This expands a pattern into the sorted list of files it matches. A pattern without
+/// any glob syntax is returned as is, even if the file does not exist. A warning is added
+/// for each directory that could not be read.
+pub fn expand(pattern: &str, warnings: &mut Vec<String>) -> Vec<PathBuf> {
+    if !is_pattern(pattern) {
+        return vec![PathBuf::from(pattern)];
+    }
+    let (root, rest) = match pattern.strip_prefix('/') {
+        Some(rest) => (PathBuf::from("/"), rest),
+        None => (PathBuf::new(), pattern),
+    };
+    let mut components: Vec<&str> = rest.split('/').filter(|c| !c.is_empty()).collect();
+    if components.last() == Some(&"**") {
+        components.push("*");
+    }
+    let mut matches = Vec::new();
+    walk(&root, &components, &mut matches, warnings);
+    matches.sort();
+    matches.dedup();
+    matches
+}
+
+fn walk(dir: &Path, components: &[&str], matches: &mut Vec<PathBuf>, warnings: &mut Vec<String>) {
+    let (component, rest) = match components.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+
+    if *component == "**" {
+        // zero directories
+        walk(dir, rest, matches, warnings);
+        // or one more directory, and then any number after that
+        for entry in read_dir(dir, warnings) {
+            if is_real_dir(&entry) && !is_hidden(&entry) {
+                walk(&entry, components, matches, warnings);
+            }
+        }
+        return;
+    }
+
+    if !is_pattern(component) {
+        let path = dir.join(component);
+        if rest.is_empty() {
+            if path.is_file() {
+                matches.push(path);
+            }
+        } else if path.is_dir() {
+            walk(&path, rest, matches, warnings);
+        }
+        return;
+    }
+
+    let pattern: Vec<char> = component.chars().collect();
+    for entry in read_dir(dir, warnings) {
+        let name: Vec<char> = match entry.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name.chars().collect(),
+            None => continue,
+        };
+        if name.first() == Some(&'.') && pattern.first() != Some(&'.') {
+            continue;
+        }
+        if !matches_component(&pattern, &name) {
+            continue;
+        }
+        if rest.is_empty() {
+            if entry.is_file() {
+                matches.push(entry);
+            }
+        } else if entry.is_dir() {
+            walk(&entry, rest, matches, warnings);
+        }
+    }
+}
+
+// A directory that can not be read is reported, and then treated as if it was empty.
+fn read_dir(dir: &Path, warnings: &mut Vec<String>) -> Vec<PathBuf> {
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    match read_entries(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warnings.push(format!(
+                "warning: could not read directory {}: {}",
+                dir.display(),
+                e
+            ));
+            Vec::new()
+        }
+    }
+}
+
+fn read_entries(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        // read_dir(".") gives us "./name", but we want the paths to look like the pattern
+        entries.push(
+            path.strip_prefix(".")
+                .map(Path::to_path_buf)
+                .unwrap_or(path),
+        );
+    }
+    Ok(entries)
+}
+
+// This is false for a symbolic link, even if it points to a directory.
+fn is_real_dir(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir())
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.'))
+}
+
+/// This matches a single path component against a single pattern component.
+fn matches_component(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((&'*', rest)) => (0..=name.len()).any(|i| matches_component(rest, &name[i..])),
+        Some((&'?', rest)) => !name.is_empty() && matches_component(rest, &name[1..]),
+        Some((&'[', rest)) => match (name.split_first(), class_end(rest)) {
+            (Some((&c, name_rest)), Some(end)) => {
+                matches_class(&rest[..end], c) && matches_component(&rest[end + 1..], name_rest)
+            }
+            // an unterminated class is matched literally
+            (Some((&'[', name_rest)), None) => matches_component(rest, name_rest),
+            _ => false,
+        },
+        Some((&p, rest)) => name.first() == Some(&p) && matches_component(rest, &name[1..]),
+    }
+}
+
+/// This finds the index of the closing `]` of a character class. A `]` right at the
+/// start of the class is treated as a literal character.
+fn class_end(class: &[char]) -> Option<usize> {
+    let start = if class.first() == Some(&'!') { 1 } else { 0 };
+    class
+        .iter()
+        .enumerate()
+        .skip(start + 1)
+        .find(|&(_, &c)| c == ']')
+        .map(|(i, _)| i)
+}
+
+fn matches_class(class: &[char], c: char) -> bool {
+    let (negated, class) = match class.split_first() {
+        Some((&'!', rest)) => (true, rest),
+        _ => (false, class),
+    };
+    let mut matched = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            matched |= class[i] <= c && c <= class[i + 2];
+            i += 3;
+        } else {
+            matched |= class[i] == c;
+            i += 1;
+        }
+    }
+    matched != negated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let name: Vec<char> = name.chars().collect();
+        matches_component(&pattern, &name)
+    }
+
+    #[test]
+    fn test_matches_component() {
+        assert!(matches("*.js", "index.js"));
+        assert!(matches("*.js", ".js"));
+        assert!(!matches("*.js", "index.jsx"));
+        assert!(matches("a?c", "abc"));
+        assert!(!matches("a?c", "ac"));
+        assert!(matches("[ab].js", "b.js"));
+        assert!(!matches("[!ab].js", "b.js"));
+        assert!(matches("[a-c]*", "car"));
+        assert!(!matches("[a-c]*", "dog"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("[", "["));
+    }
+
+    #[test]
+    fn test_expand() {
+        let root = ::std::env::temp_dir().join(format!("ecma-glob-{}", ::std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for file in &[
+            "a.js",
+            "b.ts",
+            "src/c.js",
+            "src/lib/d.js",
+            "src/.hidden/e.js",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::File::create(path).unwrap();
+        }
+        let expand = |pattern: &str| -> Vec<PathBuf> {
+            expand(&format!("{}/{}", root.display(), pattern), &mut Vec::new())
+                .into_iter()
+                .map(|path| path.strip_prefix(&root).unwrap().to_path_buf())
+                .collect()
+        };
+
+        assert_eq!(expand("*.js"), vec![PathBuf::from("a.js")]);
+        assert_eq!(
+            expand("**/*.js"),
+            vec![
+                PathBuf::from("a.js"),
+                PathBuf::from("src/c.js"),
+                PathBuf::from("src/lib/d.js"),
+            ]
+        );
+        assert_eq!(expand("src/*/*.js"), vec![PathBuf::from("src/lib/d.js")]);
+        assert_eq!(
+            expand("*.?s"),
+            vec![PathBuf::from("a.js"), PathBuf::from("b.ts")]
+        );
+        assert_eq!(
+            expand("src/**"),
+            vec![PathBuf::from("src/c.js"), PathBuf::from("src/lib/d.js")]
+        );
+        // directories that can not be read are skipped
+        assert_eq!(expand("missing/*.js"), Vec::<PathBuf>::new());
+        assert_eq!(expand("a.js/**"), Vec::<PathBuf>::new());
+        // and reported to the caller
+        let mut warnings = Vec::new();
+        assert_eq!(
+            read_dir(&root.join("missing"), &mut warnings),
+            Vec::<PathBuf>::new()
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("warning: could not read directory"));
+
+        // `**` does not follow links, so a link to a parent directory does not loop
+        #[cfg(unix)]
+        {
+            ::std::os::unix::fs::symlink(root.join("src"), root.join("src/lib/up")).unwrap();
+            assert_eq!(expand("**/d.js"), vec![PathBuf::from("src/lib/d.js")]);
+            // but other wildcards do
+            assert_eq!(
+                expand("src/lib/up/*.js"),
+                vec![PathBuf::from("src/lib/up/c.js")]
+            );
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
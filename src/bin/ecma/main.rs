@@ -0,0 +1,326 @@
+//! `ecma` is a small command line tool around the parser.
+//!
+//! - `ecma parse [FILE]...` prints the syntax tree of each input.
+//! - `ecma check [FILE]...` prints a code frame for each syntax error, and exits with a
+//!   non-zero status if there were any.
+//! - `ecma explore [FILE]...` prints a self contained HTML page for each input, with a
+//!   collapsible syntax tree next to the source.
+//! - `ecma highlight [FILE]...` prints each input with syntax highlighting for a
+//!   terminal, with the colors reset at the end of every line. The input does not have
+//!   to parse, since it is only split into tokens.
+//!
+//! Files can also be glob patterns, eg. `src/**/*.js`. If no files are given, or a file
+//! is `-`, the source is read from stdin. Files are parsed and highlighted as scripts or
+//! modules with the same rules as Node.js, and stdin is parsed as a module. With `--watch`, the command is
+//! re-run on every file that changes until the process is killed.
+
+extern crate ecmascript;
+
+mod glob;
+
+use ecmascript::ast::SourceType;
+use ecmascript::highlight::{to_ansi_as, DefaultTheme};
+use ecmascript::SyntaxError;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const USAGE: &str = "usage: ecma <command> [--watch] [FILE]...
+
+commands:
+    parse      parse the input and print the syntax tree
+    check      report syntax errors, exiting with a non-zero status if there are any
+    explore    print an HTML page with the source and a collapsible syntax tree
+    highlight  print the source with syntax highlighting
+
+options:
+    -w, --watch    re-run the command whenever one of the files changes
+
+Files may be glob patterns, eg. 'src/**/*.js'. If no files are given, or a file
+is `-`, the source is read from stdin.";
+
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+enum Command {
+    Parse,
+    Check,
+    Explore,
+    Highlight,
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let command = match args.next().as_deref() {
+        Some("parse") => Command::Parse,
+        Some("check") => Command::Check,
+        Some("explore") => Command::Explore,
+        Some("highlight") => Command::Highlight,
+        Some("-h") | Some("--help") | Some("help") => {
+            println!("{}", USAGE);
+            return;
+        }
+        Some(other) => {
+            eprintln!("error: unknown command `{}`\n\n{}", other, USAGE);
+            process::exit(2);
+        }
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let mut watch_mode = false;
+    let mut patterns = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-w" | "--watch" => watch_mode = true,
+            _ => patterns.push(arg),
+        }
+    }
+    if patterns.is_empty() {
+        patterns.push("-".to_string());
+    }
+
+    if watch_mode {
+        if patterns.iter().any(|pattern| pattern == "-") {
+            eprintln!("error: stdin can not be watched");
+            process::exit(2);
+        }
+        watch(&command, &patterns);
+    }
+
+    let mut failed = false;
+    let mut problems = Vec::new();
+    let files = expand(&patterns, &mut problems, &mut failed);
+    for problem in &problems {
+        eprintln!("{}", problem);
+    }
+    for file in files {
+        failed |= !run_file(&command, &file);
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// This expands any glob patterns. The patterns that do not match anything and the
+/// directories that can not be read are added to the problems instead of printed, so the
+/// watch loop only prints them when they change.
+fn expand(patterns: &[String], problems: &mut Vec<String>, failed: &mut bool) -> Vec<String> {
+    let mut files = Vec::new();
+    for pattern in patterns {
+        let matches = glob::expand(pattern, problems);
+        if matches.is_empty() {
+            problems.push(format!("error: no files match `{}`", pattern));
+            *failed = true;
+        }
+        files.extend(matches.into_iter().map(path_to_string));
+    }
+    files
+}
+
+fn path_to_string(path: PathBuf) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// This polls the files for changes, and re-runs the command on the ones that changed.
+/// Patterns are re-expanded every time, so new files are picked up as well. The problems
+/// with the patterns are only printed when they change, instead of on every poll.
+fn watch(command: &Command, patterns: &[String]) -> ! {
+    let mut modified: HashMap<String, SystemTime> = HashMap::new();
+    let mut failing: HashMap<String, bool> = HashMap::new();
+    let mut last_problems = Vec::new();
+    loop {
+        let mut changed = Vec::new();
+        let mut problems = Vec::new();
+        let files = expand(patterns, &mut problems, &mut false);
+        if problems != last_problems {
+            for problem in &problems {
+                eprintln!("[watch] {}", problem);
+            }
+            last_problems = problems;
+        }
+        for file in &files {
+            let time = fs::metadata(file).and_then(|metadata| metadata.modified());
+            if let Ok(time) = time {
+                if modified.get(file) != Some(&time) {
+                    modified.insert(file.clone(), time);
+                    changed.push(file.clone());
+                }
+            }
+        }
+        modified.retain(|file, _| files.contains(file));
+        failing.retain(|file, _| files.contains(file));
+
+        if !changed.is_empty() {
+            for file in &changed {
+                eprintln!("[watch] {}", file);
+                failing.insert(file.clone(), !run_file(command, file));
+            }
+            let count = failing.values().filter(|&&failed| failed).count();
+            eprintln!(
+                "[watch] {} of {} file(s) failed, waiting for changes...",
+                count,
+                files.len()
+            );
+        }
+        thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+/// This reads a single input and runs the command on it, returning false if it failed.
+fn run_file(command: &Command, file: &str) -> bool {
+    match read_source(file) {
+        Ok(source) => run(command, file, &source),
+        Err(e) => {
+            eprintln!("error: could not read {}: {}", display_name(file), e);
+            false
+        }
+    }
+}
+
+/// This runs a command on a single input, returning false if it failed.
+fn run(command: &Command, file: &str, source: &str) -> bool {
+    let source_type = if file == "-" {
+        SourceType::Module
+    } else {
+        SourceType::from_path(file)
+    };
+    if let Command::Highlight = *command {
+        print!("{}", to_ansi_as(source, source_type, &DefaultTheme));
+        return true;
+    }
+    match ecmascript::parse_as(source, source_type) {
+        Ok(program) => {
+            match *command {
+                Command::Parse => println!("{:#?}", program),
+                Command::Check => {}
+                Command::Explore => print!("{}", program.to_html(source)),
+                Command::Highlight => unreachable!(),
+            }
+            true
+        }
+        Err(e) => {
+            match e.downcast_ref::<SyntaxError>() {
+                Some(syntax_error) => {
+                    eprintln!("{}", code_frame(file, source, syntax_error))
+                }
+                None => eprintln!("error: {}: {}", display_name(file), e),
+            }
+            false
+        }
+    }
+}
+
+fn read_source(file: &str) -> io::Result<String> {
+    let mut source = String::new();
+    if file == "-" {
+        io::stdin().read_to_string(&mut source)?;
+    } else {
+        File::open(file)?.read_to_string(&mut source)?;
+    }
+    Ok(source)
+}
+
+fn display_name(file: &str) -> &str {
+    if file == "-" {
+        "<stdin>"
+    } else {
+        file
+    }
+}
+
+/// This renders the error message, followed by the offending line with a caret under the
+/// column where the parser gave up, and the suggestion if there is one.
+fn code_frame(file: &str, source: &str, error: &SyntaxError) -> String {
+    // lines and columns start at 1, but an error from elsewhere might not follow that
+    let index = |n: i32| n.saturating_sub(1).max(0) as usize;
+    let text = source.lines().nth(index(error.line)).unwrap_or("");
+    let gutter = error.line.to_string().len();
+
+    // columns count characters, so we copy any tabs from the source line to make sure the
+    // caret lines up in the terminal
+    let padding: String = text
+        .chars()
+        .take(index(error.column))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    let help = match error.suggestion {
+        Some(ref suggestion) => format!(
+            "\n{} = help: did you mean `{}`?",
+            " ".repeat(gutter),
+            suggestion
+        ),
+        None => String::new(),
+    };
+
+    format!(
+        "error: {message}\n{pad} --> {file}:{line}:{column}\n{pad} |\n{line:>gutter$} | {text}\n{pad} | {padding}^{help}",
+        message = error.message.trim_end().replace('\n', ", "),
+        pad = " ".repeat(gutter),
+        file = display_name(file),
+        line = error.line,
+        column = error.column,
+        gutter = gutter,
+        text = text,
+        padding = padding,
+        help = help,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_frame() {
+        let error = SyntaxError {
+            line: 2,
+            column: 2,
+            message: "Unexpected `b`\nExpected `end of input`\n".to_string(),
+            suggestion: None,
+        };
+        assert_eq!(
+            code_frame("test.js", "a\n\tb", &error),
+            "error: Unexpected `b`, Expected `end of input`\n  --> test.js:2:2\n  |\n2 | \tb\n  | \t^"
+        );
+
+        let error = SyntaxError {
+            line: 1,
+            column: 1,
+            message: "Unexpected `r`\n".to_string(),
+            suggestion: Some("return".to_string()),
+        };
+        assert_eq!(
+            code_frame("test.js", "retrun", &error),
+            "error: Unexpected `r`\n  --> test.js:1:1\n  |\n1 | retrun\n  | ^\n  = help: did you mean `return`?"
+        );
+
+        // positions outside of the source do not panic
+        let error = SyntaxError {
+            line: 0,
+            column: 0,
+            message: "Unexpected end of input\n".to_string(),
+            suggestion: None,
+        };
+        assert_eq!(
+            code_frame("test.js", "a", &error),
+            "error: Unexpected end of input\n  --> test.js:0:0\n  |\n0 | a\n  | ^"
+        );
+        let error = SyntaxError {
+            line: 3,
+            column: 5,
+            ..error
+        };
+        assert_eq!(
+            code_frame("test.js", "a", &error),
+            "error: Unexpected end of input\n  --> test.js:3:5\n  |\n3 | \n  | ^"
+        );
+    }
+}
@@ -0,0 +1,798 @@
+//! This module renders syntax trees in formats that are easier to read than the derived
+//! `Debug` output, eg. `f(a)` is rendered by `dump_tree` as
+//!
+//! ```text
+//! Call
+//! ├─ callee: IdReference f
+//! └─ arguments[0]: IdReference a
+//! ```
+//!
+//! `to_dot` renders the same tree as a Graphviz graph, which is easier to follow for
+//! wide trees, and `to_html` renders it as a collapsible tree next to the source text,
+//! where hovering a node with a span highlights its source text.
+//! `structural_diff` compares two trees node by node, which `assert_ast_eq!` uses to show
+//! where they differ.
+//!
+//! A program is shown with its span, its hashbang and its comments, eg. `Program Script @
+//! 0..10`. Expressions do not carry spans yet, so only their kinds and fields are shown.
+
+use ast::*;
+use highlight::{self, DefaultTheme, DEFAULT_CSS};
+use span::Span;
+use std::fmt;
+use std::fmt::Write;
+
+/// Node is a generic view of a syntax tree node, that every format is rendered from.
+struct Node {
+    /// The name of the variant or struct, eg. `Binary`.
+    kind: &'static str,
+    /// The fields that are not child nodes, eg. the operator of a binary expression.
+    detail: Option<String>,
+    /// The part of the source text that the node was parsed from, if it is known.
+    span: Option<Span>,
+    /// The child nodes, labelled with the name of the field they are stored in.
+    children: Vec<(String, Node)>,
+}
+
+impl Node {
+    fn new(kind: &'static str) -> Node {
+        Node {
+            kind,
+            detail: None,
+            span: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn span(mut self, span: Span) -> Node {
+        self.span = Some(span);
+        self
+    }
+
+    fn detail<D: Into<String>>(mut self, detail: D) -> Node {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    fn child(mut self, field: &str, expression: &Expression) -> Node {
+        self.children
+            .push((field.to_string(), expression_node(expression)));
+        self
+    }
+
+    fn optional_child(self, field: &str, expression: Option<&Expression>) -> Node {
+        match expression {
+            Some(expression) => self.child(field, expression),
+            None => self,
+        }
+    }
+
+    fn children<'a, I>(mut self, field: &str, expressions: I) -> Node
+    where
+        I: IntoIterator<Item = &'a Expression>,
+    {
+        for (i, expression) in expressions.into_iter().enumerate() {
+            self.children
+                .push((format!("{}[{}]", field, i), expression_node(expression)));
+        }
+        self
+    }
+
+    // the span is not part of the label, so trees are compared without their spans
+    fn label(&self) -> String {
+        match self.detail {
+            Some(ref detail) => format!("{} {}", self.kind, detail),
+            None => self.kind.to_string(),
+        }
+    }
+
+    fn label_with_span(&self) -> String {
+        match self.span {
+            Some(span) => format!("{} @ {}..{}", self.label(), span.start, span.end),
+            None => self.label(),
+        }
+    }
+}
+
+fn program_node(program: &Program) -> Node {
+    // there are no statements to render yet
+    let mut node = Node::new("Program")
+        .detail(format!("{:?}", program.source_type))
+        .span(program.span);
+    if let Some(ref hashbang) = program.hashbang {
+        let start = program.span.start;
+        let hashbang_node = Node::new("Hashbang")
+            .detail(format!("{:?}", hashbang))
+            .span(Span::new(start, start + 2 + hashbang.len() as u32));
+        node.children.push(("hashbang".to_string(), hashbang_node));
+    }
+    for (i, comment) in program.comments.iter().enumerate() {
+        let comment_node = Node::new("Comment")
+            .detail(format!("{:?} {:?}", comment.kind, comment.text))
+            .span(comment.span);
+        node.children.push((format!("comments[{}]", i), comment_node));
+    }
+    node
+}
+
+fn expression_node(expression: &Expression) -> Node {
+    match *expression {
+        Expression::This => Node::new("This"),
+        Expression::IdReference(ref id) => Node::new("IdReference").detail(id.as_str()),
+        Expression::Literal(ref literal) => match *literal {
+            ExpressionLiteral::NullLiteral(_) => Node::new("NullLiteral"),
+            ExpressionLiteral::BooleanLiteral(b) => {
+                Node::new("BooleanLiteral").detail(b.to_string())
+            }
+            ExpressionLiteral::NumberLiteral(ref n) => {
+                let value = n.value.to_string();
+                // the raw text is shown when the number was spelled differently, eg. `1_000`
+                Node::new("NumberLiteral").detail(match (n.raw.as_ref(), n.kind) {
+                    (Some(raw), _) if **raw != value => format!("{} ({})", value, raw),
+                    (Some(_), _) | (None, NumberKind::Decimal) => value,
+                    (None, kind) => format!("{} ({:?})", value, kind),
+                })
+            }
+            ExpressionLiteral::StringLiteral(ref s) => {
+                let value = format!("{:?}", s.value);
+                // the raw text is shown when the string has escapes, eg. `'\x07'`
+                let quoted = |raw: &str| s.value.as_str() == Some(&raw[1..raw.len() - 1]);
+                Node::new("StringLiteral").detail(match s.raw {
+                    Some(ref raw) if !quoted(raw) => format!("{} ({})", value, raw),
+                    _ => value,
+                })
+            }
+        },
+        Expression::ArrayLiteral(ref elements) => {
+            Node::new("ArrayLiteral").children("elements", elements)
+        }
+        Expression::ObjectLiteral(ref properties) => {
+            let mut node = Node::new("ObjectLiteral");
+            for (i, property) in properties.iter().enumerate() {
+                let property_node = Node::new("Property")
+                    .detail(format!("{:?}", property.kind))
+                    .child("key", &property.key)
+                    .child("value", &property.value);
+                node.children
+                    .push((format!("properties[{}]", i), property_node));
+            }
+            node
+        }
+        Expression::Function(ref function) => {
+            let mut detail: Vec<&str> = function.id.iter().map(|id| id.as_str()).collect();
+            if function.async {
+                detail.push("async");
+            }
+            if function.generator {
+                detail.push("generator");
+            }
+            let mut node = Node::new("Function");
+            if !detail.is_empty() {
+                node = node.detail(detail.join(" "));
+            }
+            for (i, param) in function.params.iter().enumerate() {
+                node.children.push((
+                    format!("params[{}]", i),
+                    Node::new("Id").detail(param.as_str()),
+                ));
+            }
+            node
+        }
+        Expression::RegexLiteral(ref regex) => {
+            Node::new("RegexLiteral").detail(format!("/{}/{}", regex.pattern, regex.flags))
+        }
+        Expression::TemplateLiteral(ref elements) => {
+            let mut node = Node::new("TemplateLiteral");
+            for (i, element) in elements.iter().enumerate() {
+                let element_node = match *element {
+                    TemplateLiteralElement::TemplateElement(ref element) => {
+                        Node::new("TemplateElement").detail(format!("{:?}", element.raw))
+                    }
+                    TemplateLiteralElement::Expression(ref expression) => {
+                        expression_node(expression)
+                    }
+                };
+                node.children
+                    .push((format!("elements[{}]", i), element_node));
+            }
+            node
+        }
+        Expression::Spread(ref argument) => Node::new("Spread").child("argument", argument),
+        Expression::Member {
+            ref lhs,
+            ref rhs,
+            computed,
+        } => {
+            let node = Node::new("Member");
+            let node = if computed {
+                node.detail("computed")
+            } else {
+                node
+            };
+            node.child("lhs", lhs).child("rhs", rhs)
+        }
+        Expression::Super => Node::new("Super"),
+        Expression::MetaProperty => Node::new("MetaProperty"),
+        Expression::New(ref new) => Node::new("New")
+            .child("callee", &new.callee)
+            .children("arguments", &new.arguments),
+        Expression::Call(ref call) => Node::new("Call")
+            .child("callee", &call.callee)
+            .children("arguments", &call.arguments),
+        Expression::TaggedTemplate { ref tag, ref quasi } => Node::new("TaggedTemplate")
+            .child("tag", tag)
+            .child("quasi", quasi),
+        Expression::Update {
+            ref operator,
+            ref argument,
+            prefix,
+        } => Node::new("Update")
+            .detail(format!(
+                "{:?} {}",
+                operator,
+                if prefix { "prefix" } else { "postfix" }
+            ))
+            .child("argument", argument),
+        Expression::Unary {
+            ref operator,
+            ref argument,
+        } => Node::new("Unary")
+            .detail(format!("{:?}", operator))
+            .child("argument", argument),
+        Expression::Binary {
+            ref operator,
+            ref lhs,
+            ref rhs,
+        } => Node::new("Binary")
+            .detail(format!("{:?}", operator))
+            .child("lhs", lhs)
+            .child("rhs", rhs),
+        Expression::Conditional {
+            ref test,
+            ref alternate,
+            ref consequent,
+        } => Node::new("Conditional")
+            .child("test", test)
+            .child("alternate", alternate)
+            .child("consequent", consequent),
+        Expression::Assignment {
+            ref operator,
+            ref lhs,
+            ref rhs,
+        } => Node::new("Assignment")
+            .detail(format!("{:?}", operator))
+            .child("lhs", lhs)
+            .child("rhs", rhs),
+        Expression::Yield {
+            ref argument,
+            delegate,
+        } => {
+            let node = Node::new("Yield");
+            let node = if delegate {
+                node.detail("delegate")
+            } else {
+                node
+            };
+            node.optional_child("argument", argument.as_ref().map(|argument| &**argument))
+        }
+        Expression::Comma(ref expressions) => {
+            Node::new("Comma").children("expressions", expressions)
+        }
+        Expression::JsxElement(ref element) => {
+            let mut node = Node::new("JsxElement").detail(element.name.as_str());
+            for (i, attribute) in element.attributes.iter().enumerate() {
+                let attribute_node = match *attribute {
+                    JsxAttribute::JsxSpreadAttribute { ref expression } => {
+                        Node::new("JsxSpreadAttribute").child("expression", expression)
+                    }
+                    JsxAttribute::JsxAttribute {
+                        ref name,
+                        ref value,
+                    } => Node::new("JsxAttribute")
+                        .detail(name.as_str())
+                        .optional_child("value", value.as_ref()),
+                };
+                node.children
+                    .push((format!("attributes[{}]", i), attribute_node));
+            }
+            node.children("children", &element.children)
+        }
+        Expression::JsxFragment(ref children) => {
+            Node::new("JsxFragment").children("children", children)
+        }
+    }
+}
+
+fn write_tree(out: &mut String, node: &Node, prefix: &str) {
+    for (i, (field, child)) in node.children.iter().enumerate() {
+        let last = i + 1 == node.children.len();
+        let _ = writeln!(
+            out,
+            "{}{} {}: {}",
+            prefix,
+            if last { "└─" } else { "├─" },
+            field,
+            child.label_with_span()
+        );
+        let prefix = format!("{}{}", prefix, if last { "   " } else { "│  " });
+        write_tree(out, child, &prefix);
+    }
+}
+
+fn dump_tree(node: &Node) -> String {
+    let mut out = node.label_with_span();
+    out.push('\n');
+    write_tree(&mut out, node, "");
+    out
+}
+
+/// This writes the node and its children as DOT statements, returning the id of the node.
+fn write_dot(out: &mut String, node: &Node, next_id: &mut usize) -> usize {
+    let id = *next_id;
+    *next_id += 1;
+    let _ = writeln!(out, "  n{} [label=\"{}\"];", id, escape_dot(&node.label_with_span()));
+    for (field, child) in &node.children {
+        let child_id = write_dot(out, child, next_id);
+        let _ = writeln!(
+            out,
+            "  n{} -> n{} [label=\"{}\"];",
+            id,
+            child_id,
+            escape_dot(field)
+        );
+    }
+    id
+}
+
+fn escape_dot(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn to_dot(node: &Node) -> String {
+    let mut out = String::from("digraph ast {\n  node [shape=box];\n");
+    write_dot(&mut out, node, &mut 0);
+    out.push_str("}\n");
+    out
+}
+
+fn write_html(out: &mut String, field: Option<&str>, node: &Node) {
+    let field = match field {
+        Some(field) => format!("<span class=\"field\">{}:</span> ", escape_html(field)),
+        None => String::new(),
+    };
+    let label = format!(
+        "{}<span class=\"kind\">{}</span>",
+        field,
+        escape_html(node.kind)
+    );
+    let label = match node.detail {
+        Some(ref detail) => format!("{} {}", label, escape_html(detail)),
+        None => label,
+    };
+    // the page script links the node to the tokens of the source text in its span
+    let span = match node.span {
+        Some(span) => format!(" data-start=\"{}\" data-end=\"{}\"", span.start, span.end),
+        None => String::new(),
+    };
+    if node.children.is_empty() {
+        let _ = write!(out, "<div class=\"leaf\"{}>{}</div>", span, label);
+    } else {
+        let _ = write!(out, "<details open{}><summary>{}</summary>", span, label);
+        for (field, child) in &node.children {
+            write_html(out, Some(field), child);
+        }
+        out.push_str("</details>");
+    }
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const HTML_STYLE: &str = "body { margin: 0; font: 13px monospace; }
+main { display: flex; height: 100vh; }
+pre, .tree { flex: 1; margin: 0; padding: 1em; overflow: auto; }
+pre { border-right: 1px solid #ccc; }
+details, .leaf { margin-left: 1.5em; }
+.tree > details, .tree > .leaf { margin-left: 0; }
+summary { cursor: pointer; }
+.leaf { padding-left: 1em; }
+.field { color: #888; }
+.kind { color: #1a5fb4; font-weight: bold; }
+.selected { background: #fff3a0; }";
+
+// Hovering a node with a span selects the tokens in the span, and clicking it scrolls
+// the first of them into view. The offsets are bytes on both sides, so they match.
+const HTML_SCRIPT: &str = "var tokens = document.querySelectorAll('pre [data-start]');
+function select(start, end) {
+  tokens.forEach(function (token) {
+    var selected = start < +token.dataset.end && +token.dataset.start < end;
+    token.classList.toggle('selected', selected);
+  });
+}
+document.querySelectorAll('.tree [data-start]').forEach(function (node) {
+  var start = +node.dataset.start, end = +node.dataset.end;
+  node.addEventListener('mouseover', function (event) {
+    event.stopPropagation();
+    select(start, end);
+  });
+  node.addEventListener('mouseout', function () { select(0, 0); });
+  node.addEventListener('click', function (event) {
+    event.stopPropagation();
+    var token = document.querySelector('pre .selected');
+    if (token) token.scrollIntoView({ block: 'nearest' });
+  });
+});";
+
+fn to_html(node: &Node, source: &str, source_type: SourceType) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>\n{}\n{}\n</style>\n</head>\n<body>\n<main>\n<pre>{}</pre>\n<div class=\"tree\">",
+        node.kind,
+        HTML_STYLE,
+        DEFAULT_CSS,
+        highlight::write_html(source, source_type, &DefaultTheme, true)
+    );
+    write_html(&mut out, None, node);
+    out.push_str("</div>\n</main>\n<script>\n");
+    out.push_str(HTML_SCRIPT);
+    out.push_str("\n</script>\n</body>\n</html>\n");
+    out
+}
+
+impl Program {
+    /// This renders the program as an indented tree, with one node per line.
+    pub fn dump_tree(&self) -> String {
+        dump_tree(&program_node(self))
+    }
+
+    /// This renders the program as a Graphviz DOT graph, eg. for `dot -Tsvg`.
+    pub fn to_dot(&self) -> String {
+        to_dot(&program_node(self))
+    }
+
+    /// This renders a self contained HTML page, with the highlighted source text next to
+    /// a collapsible tree of the program. The page does not load anything else, so it can
+    /// be shared as a single file. Hovering the program or a comment in the tree
+    /// highlights its source text, and clicking it scrolls the source text to it.
+    pub fn to_html(&self, source: &str) -> String {
+        to_html(&program_node(self), source, self.source_type.clone())
+    }
+
+    /// This compares two programs, without their spans, and describes the first
+    /// difference between them. It returns `None` if they are the same. The comments are
+    /// compared by their kind and text.
+    pub fn structural_diff(&self, other: &Program) -> Option<String> {
+        let comment = |comment: &Comment| (comment.kind, comment.text.clone());
+        let comments: Vec<_> = self.comments.iter().map(comment).collect();
+        let other_comments: Vec<_> = other.comments.iter().map(comment).collect();
+        if self.hashbang != other.hashbang {
+            let heading = "the hashbangs are different";
+            Some(value_diff(heading, &self.hashbang, &other.hashbang))
+        } else if comments != other_comments {
+            let heading = "the comments are different";
+            Some(value_diff(heading, &comments, &other_comments))
+        } else if self.source_type != other.source_type || self.body != other.body {
+            node_diff("", &program_node(self), &program_node(other))
+                .or_else(|| Some(value_diff(HIDDEN_DIFF, &self.body, &other.body)))
+        } else {
+            None
+        }
+    }
+}
+
+impl Expression {
+    /// This renders the expression as an indented tree, with one node per line.
+    pub fn dump_tree(&self) -> String {
+        dump_tree(&expression_node(self))
+    }
+
+    /// This renders the expression as a Graphviz DOT graph, eg. for `dot -Tsvg`.
+    pub fn to_dot(&self) -> String {
+        to_dot(&expression_node(self))
+    }
+
+    /// This renders a self contained HTML page, with the highlighted source text next to
+    /// a collapsible tree of the expression. The source text is highlighted as a Module,
+    /// which is how expressions are parsed.
+    pub fn to_html(&self, source: &str) -> String {
+        to_html(&expression_node(self), source, SourceType::Module)
+    }
+
+    /// This compares two expressions and describes the first difference between them,
+    /// with the path to the node that differs. It returns `None` if they are the same.
+    pub fn structural_diff(&self, other: &Expression) -> Option<String> {
+        if self == other {
+            return None;
+        }
+        node_diff("", &expression_node(self), &expression_node(other))
+            .or_else(|| Some(value_diff(HIDDEN_DIFF, self, other)))
+    }
+}
+
+/// This finds the first node that is not the same in both trees, going depth first,
+/// and returns the path to it with both versions of it.
+fn node_diff(path: &str, expected: &Node, actual: &Node) -> Option<String> {
+    let differs = |expected: &Node, actual: &Node| {
+        let at = if path.is_empty() {
+            "the root".to_string()
+        } else {
+            format!("`{}`", path)
+        };
+        Some(format!(
+            "first difference at {}\nexpected:\n{}actual:\n{}",
+            at,
+            dump_tree(expected),
+            dump_tree(actual)
+        ))
+    };
+    if expected.label() != actual.label() {
+        return differs(expected, actual);
+    }
+    let children = expected.children.len().max(actual.children.len());
+    for i in 0..children {
+        match (expected.children.get(i), actual.children.get(i)) {
+            (Some((field, expected)), Some((actual_field, actual)))
+                if field == actual_field =>
+            {
+                let path = if path.is_empty() {
+                    field.clone()
+                } else {
+                    format!("{}.{}", path, field)
+                };
+                if let Some(diff) = node_diff(&path, expected, actual) {
+                    return Some(diff);
+                }
+            }
+            // a missing or extra child changes the fields of the node itself
+            _ => return differs(expected, actual),
+        }
+    }
+    None
+}
+
+/// This describes a difference that is not in the rendered trees, eg. the cooked text of
+/// a template element.
+fn value_diff<T: fmt::Debug>(heading: &str, expected: &T, actual: &T) -> String {
+    format!(
+        "{}\nexpected: {:?}\nactual: {:?}",
+        heading, expected, actual
+    )
+}
+
+const HIDDEN_DIFF: &str = "the trees differ in a field that is not rendered";
+
+#[cfg(test)]
+mod dump_tests {
+    use super::*;
+    use span::Span;
+
+    #[test]
+    fn test_dump_tree() {
+        let expression = Expression::Call(Box::new(Call {
+            callee: Expression::Member {
+                lhs: Box::new(build_ast!(id "console")),
+                rhs: Box::new(build_ast!(id "log")),
+                computed: false,
+            },
+            arguments: vec![
+                build_ast!(str "hi".to_string()),
+                build_ast!(array [ [num 1.0], [null] ]),
+            ],
+        }));
+        assert_eq!(
+            expression.dump_tree(),
+            "Call
+├─ callee: Member
+│  ├─ lhs: IdReference console
+│  └─ rhs: IdReference log
+├─ arguments[0]: StringLiteral \"hi\"
+└─ arguments[1]: ArrayLiteral
+   ├─ elements[0]: NumberLiteral 1
+   └─ elements[1]: NullLiteral
+"
+        );
+
+        let program = Program {
+            source_type: SourceType::Script,
+            body: Vec::new(),
+            span: Span::default(),
+            hashbang: None,
+            comments: Vec::new(),
+        };
+        assert_eq!(program.dump_tree(), "Program Script @ 0..0\n");
+        let program = ::parser::parse_as("#!node\n// a\n/* b */", SourceType::Script).unwrap();
+        assert_eq!(
+            program.dump_tree(),
+            r#"Program Script @ 0..19
+├─ hashbang: Hashbang "node" @ 0..6
+├─ comments[0]: Comment Line " a" @ 7..11
+└─ comments[1]: Comment Block " b " @ 12..19
+"#
+        );
+
+        // array literals can not hold numbers yet, so the elements are parsed one by one
+        let numbers = ["1_000", "0XFF", "1", "0x1"].iter();
+        let numbers = Expression::ArrayLiteral(
+            numbers
+                .map(|source| ::parser::parse_expression(source).unwrap())
+                .collect(),
+        );
+        let hex = NumberLiteral {
+            value: 16.0,
+            kind: NumberKind::Hex,
+            raw: None,
+        };
+        assert_eq!(
+            numbers.dump_tree(),
+            "ArrayLiteral
+├─ elements[0]: NumberLiteral 1000 (1_000)
+├─ elements[1]: NumberLiteral 255 (0XFF)
+├─ elements[2]: NumberLiteral 1
+└─ elements[3]: NumberLiteral 1 (0x1)
+"
+        );
+        assert_eq!(
+            Expression::Literal(ExpressionLiteral::NumberLiteral(hex)).dump_tree(),
+            "NumberLiteral 16 (Hex)\n"
+        );
+        let strings = [r"'a'", r"'\x07'", r"'\u0007'"].iter();
+        let strings = Expression::ArrayLiteral(
+            strings
+                .map(|source| ::parser::parse_expression(source).unwrap())
+                .collect(),
+        );
+        assert_eq!(
+            strings.dump_tree(),
+            r#"ArrayLiteral
+├─ elements[0]: StringLiteral "a"
+├─ elements[1]: StringLiteral "\u{7}" ('\x07')
+└─ elements[2]: StringLiteral "\u{7}" ('\u0007')
+"#
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let expression = Expression::Binary {
+            operator: BinaryOperator::Plus,
+            lhs: Box::new(build_ast!(id "a")),
+            rhs: Box::new(build_ast!(str "\"b\"".to_string())),
+        };
+        assert_eq!(
+            expression.to_dot(),
+            r#"digraph ast {
+  node [shape=box];
+  n0 [label="Binary Plus"];
+  n1 [label="IdReference a"];
+  n0 -> n1 [label="lhs"];
+  n2 [label="StringLiteral \"\\\"b\\\"\""];
+  n0 -> n2 [label="rhs"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_to_html() {
+        let expression = Expression::Binary {
+            operator: BinaryOperator::Lt,
+            lhs: Box::new(build_ast!(id "a")),
+            rhs: Box::new(build_ast!(str "<b>".to_string())),
+        };
+        let html = expression.to_html("a < '<b>'");
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        // the source text is highlighted
+        assert!(html.contains(DEFAULT_CSS));
+        assert!(html.contains(
+            "<pre><span class=\"identifier\" data-start=\"0\" data-end=\"1\">a</span>\
+             <span data-start=\"1\" data-end=\"2\"> </span>\
+             <span data-start=\"2\" data-end=\"3\">&lt;</span>\
+             <span data-start=\"3\" data-end=\"4\"> </span>\
+             <span class=\"string\" data-start=\"4\" data-end=\"9\">'&lt;b&gt;'</span></pre>"
+        ));
+        assert!(html.contains(
+            "<details open><summary><span class=\"kind\">Binary</span> Lt</summary>\
+             <div class=\"leaf\"><span class=\"field\">lhs:</span> <span class=\"kind\">IdReference</span> a</div>\
+             <div class=\"leaf\"><span class=\"field\">rhs:</span> <span class=\"kind\">StringLiteral</span> &quot;&lt;b&gt;&quot;</div>\
+             </details>"
+        ));
+        assert!(html.contains(HTML_SCRIPT));
+
+        // the nodes with a span are linked to the source text, which is highlighted as the
+        // source type of the program
+        let source = "<!-- a\n// b";
+        let program = ::parser::parse_as(source, SourceType::Script).unwrap();
+        let html = program.to_html(source);
+        assert!(html.contains(
+            "<pre><span class=\"comment\" data-start=\"0\" data-end=\"6\">&lt;!-- a</span>"
+        ));
+        assert!(html.contains("<details open data-start=\"0\" data-end=\"11\"><summary>"));
+        assert!(html.contains("<div class=\"leaf\" data-start=\"7\" data-end=\"11\">"));
+    }
+
+    #[test]
+    fn test_structural_diff() {
+        let call = |argument| {
+            Expression::Call(Box::new(Call {
+                callee: build_ast!(id "f"),
+                arguments: vec![Expression::ArrayLiteral(vec![build_ast!(true), argument])],
+            }))
+        };
+        let expected = call(build_ast!(id "a"));
+        assert_eq!(expected.structural_diff(&expected.clone()), None);
+        assert_eq!(
+            expected.structural_diff(&call(build_ast!(array [[id "a"]]))),
+            Some(
+                "first difference at `arguments[0].elements[1]`\n\
+                 expected:\nIdReference a\n\
+                 actual:\nArrayLiteral\n└─ elements[0]: IdReference a\n"
+                    .to_string()
+            )
+        );
+        // the cooked text is not rendered
+        let template = |cooked: &str| {
+            Expression::TemplateLiteral(vec![TemplateLiteralElement::TemplateElement(
+                build_ast!(templ_el {cooked} {"a".to_string()}),
+            )])
+        };
+        assert!(template("a")
+            .structural_diff(&template(""))
+            .unwrap()
+            .starts_with(HIDDEN_DIFF));
+    }
+
+    #[test]
+    fn test_program_structural_diff() {
+        let program = |start, text: &str| Program {
+            source_type: SourceType::Script,
+            body: Vec::new(),
+            span: Span::new(start, 10),
+            hashbang: None,
+            comments: vec![Comment {
+                text: text.to_string(),
+                span: Span::new(start, 10),
+                kind: CommentKind::Line,
+            }],
+        };
+        // spans are ignored
+        assert_eq!(program(0, "a").structural_diff(&program(5, "a")), None);
+        assert_ast_eq!(program(0, "a"), program(5, "a"));
+        assert!(program(0, "a")
+            .structural_diff(&program(0, "b"))
+            .unwrap()
+            .starts_with("the comments are different"));
+        let mut module = program(0, "a");
+        module.source_type = SourceType::Module;
+        assert_eq!(
+            program(0, "a").structural_diff(&module),
+            Some(
+                "first difference at the root\n\
+                 expected:\nProgram Script @ 0..10\n\
+                 └─ comments[0]: Comment Line \"a\" @ 0..10\n\
+                 actual:\nProgram Module @ 0..10\n\
+                 └─ comments[0]: Comment Line \"a\" @ 0..10\n"
+                    .to_string()
+            )
+        );
+    }
+}
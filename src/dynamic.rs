@@ -0,0 +1,415 @@
+//! This module finds code that is compiled at runtime, ie. calls to `eval` and the
+//! `Function` constructor, eg.
+//!
+//! ```
+//! # #[macro_use] extern crate ecmascript;
+//! # use ecmascript::ast::*;
+//! use ecmascript::dynamic::DynamicKind;
+//!
+//! # fn main() {
+//! let call = Expression::Call(Box::new(Call {
+//!     callee: build_ast!(id "eval"),
+//!     arguments: vec![build_ast!(id "source")],
+//! }));
+//! let found = call.dynamic_code();
+//! assert_eq!(found[0].kind, DynamicKind::DirectEval);
+//! # }
+//! ```
+//!
+//! Dynamic code can read and write variables that do not appear anywhere in the source
+//! text, so a pass that renames or removes variables has to leave a scope alone if
+//! `is_dynamic_scope` is true for it:
+//!
+//! * A direct `eval` runs in the scope of the function that calls it, so it taints that
+//!   function and every function around it.
+//! * An indirect `eval` and the `Function` constructor run in the global scope, so they
+//!   only taint the program.
+//!
+//! `with` statements also make scopes dynamic, but statements are not parsed yet, so
+//! they can not be found.
+
+use ast::*;
+
+/// DynamicKind is the way that code is compiled at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DynamicKind {
+    /// A call to `eval` by its name, eg. `eval(source)`. This can see the local variables
+    /// of the caller.
+    DirectEval,
+    /// Any other use of `eval`, eg. `(0, eval)(source)`, `window.eval(source)` or
+    /// `var e = eval`. This runs in the global scope.
+    IndirectEval,
+    /// A use of the `Function` constructor, eg. `new Function('a', 'return a')`. This
+    /// runs in the global scope.
+    FunctionConstructor,
+}
+
+/// DynamicCode is an expression that compiles code at runtime.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DynamicCode<'a> {
+    /// How the code is compiled.
+    pub kind: DynamicKind,
+    /// The call, `new` expression or reference.
+    pub expression: &'a Expression,
+}
+
+/// The names that the global object can be reached through.
+const GLOBAL_OBJECTS: &[&str] = &["globalThis", "window", "self", "global"];
+
+#[derive(Default)]
+struct Finder<'a> {
+    found: Vec<DynamicCode<'a>>,
+}
+
+impl<'a> Finder<'a> {
+    fn push(&mut self, kind: DynamicKind, expression: &'a Expression) {
+        self.found.push(DynamicCode { kind, expression });
+    }
+
+    fn expression(&mut self, expression: &'a Expression) {
+        match *expression {
+            Expression::IdReference(ref id) if id == "eval" => {
+                self.push(DynamicKind::IndirectEval, expression)
+            }
+            Expression::IdReference(ref id) if id == "Function" => {
+                self.push(DynamicKind::FunctionConstructor, expression)
+            }
+            Expression::This
+            | Expression::IdReference(_)
+            | Expression::Literal(_)
+            | Expression::RegexLiteral(_)
+            | Expression::Super
+            | Expression::MetaProperty => {}
+            Expression::ArrayLiteral(ref expressions)
+            | Expression::Comma(ref expressions)
+            | Expression::JsxFragment(ref expressions) => self.expressions(expressions),
+            Expression::ObjectLiteral(ref properties) => {
+                for property in properties {
+                    // a name or a literal key, eg. `{eval: 1}`, is not a reference
+                    match property.key {
+                        Expression::IdReference(_) | Expression::Literal(_) => {}
+                        ref key => self.expression(key),
+                    }
+                    self.expression(&property.value);
+                }
+            }
+            Expression::Function(ref function) => {
+                for statement in &function.body {
+                    self.statement(statement);
+                }
+            }
+            Expression::TemplateLiteral(ref elements) => self.template(elements),
+            Expression::Member {
+                ref lhs,
+                ref rhs,
+                computed,
+            } if is_global_eval(lhs, rhs, computed) => {
+                self.push(DynamicKind::IndirectEval, expression)
+            }
+            Expression::Member {
+                ref lhs,
+                ref rhs,
+                computed,
+            } => {
+                self.expression(lhs);
+                if computed {
+                    self.expression(rhs);
+                }
+            }
+            Expression::New(ref new) => self.call(expression, &new.callee, &new.arguments),
+            Expression::Call(ref call) => self.call(expression, &call.callee, &call.arguments),
+            Expression::TaggedTemplate { ref tag, ref quasi } => {
+                self.expression(tag);
+                self.expression(quasi);
+            }
+            Expression::Spread(ref argument)
+            | Expression::Update { ref argument, .. }
+            | Expression::Unary { ref argument, .. } => self.expression(argument),
+            Expression::Binary {
+                ref lhs, ref rhs, ..
+            }
+            | Expression::Assignment {
+                ref lhs, ref rhs, ..
+            } => {
+                self.expression(lhs);
+                self.expression(rhs);
+            }
+            Expression::Conditional {
+                ref test,
+                ref alternate,
+                ref consequent,
+            } => {
+                self.expression(test);
+                self.expression(alternate);
+                self.expression(consequent);
+            }
+            Expression::Yield { ref argument, .. } => {
+                if let Some(ref argument) = *argument {
+                    self.expression(argument);
+                }
+            }
+            Expression::JsxElement(ref element) => {
+                for attribute in &element.attributes {
+                    match *attribute {
+                        JsxAttribute::JsxSpreadAttribute { ref expression }
+                        | JsxAttribute::JsxAttribute {
+                            value: Some(ref expression),
+                            ..
+                        } => self.expression(expression),
+                        JsxAttribute::JsxAttribute { value: None, .. } => {}
+                    }
+                }
+                self.expressions(&element.children);
+            }
+        }
+    }
+
+    /// A call or `new` expression is reported as a whole, instead of as the reference to
+    /// `eval` or `Function` in its callee.
+    fn call(&mut self, call: &'a Expression, callee: &'a Expression, arguments: &'a [Expression]) {
+        let direct = match (call, callee) {
+            (Expression::Call(_), Expression::IdReference(id)) if id == "eval" => {
+                Some(DynamicKind::DirectEval)
+            }
+            (_, Expression::IdReference(id)) if id == "Function" => {
+                Some(DynamicKind::FunctionConstructor)
+            }
+            _ => None,
+        };
+        match direct {
+            Some(kind) => self.push(kind, call),
+            None => {
+                let before = self.found.len();
+                self.expression(callee);
+                // a callee like `(0, eval)` is reported as the call itself
+                if self.found.len() == before + 1 && is_eval_reference(callee) {
+                    self.found[before].expression = call;
+                }
+            }
+        }
+        self.expressions(arguments);
+    }
+
+    fn template(&mut self, elements: &'a [TemplateLiteralElement]) {
+        for element in elements {
+            if let TemplateLiteralElement::Expression(ref expression) = *element {
+                self.expression(expression);
+            }
+        }
+    }
+
+    fn expressions(&mut self, expressions: &'a [Expression]) {
+        for expression in expressions {
+            self.expression(expression);
+        }
+    }
+
+    fn statement(&mut self, statement: &'a Statement) {
+        match *statement {}
+    }
+}
+
+/// This is true for `globalThis.eval`, `window['eval']` and so on. The name after a dot
+/// is an IdReference, and a computed property is only `eval` if it is the string, since
+/// `window[eval]` looks up whatever `eval` converts to.
+fn is_global_eval(lhs: &Expression, rhs: &Expression, computed: bool) -> bool {
+    let is_global = match *lhs {
+        Expression::IdReference(ref id) => GLOBAL_OBJECTS.contains(&id.as_str()),
+        _ => false,
+    };
+    let is_eval = match (computed, rhs) {
+        (false, Expression::IdReference(id)) => id == "eval",
+        (true, Expression::Literal(ExpressionLiteral::StringLiteral(s))) => s.value == "eval",
+        _ => false,
+    };
+    is_global && is_eval
+}
+
+/// This is true if the callee evaluates to `eval` without calling anything.
+fn is_eval_reference(callee: &Expression) -> bool {
+    match *callee {
+        Expression::Comma(ref expressions) => expressions.last().is_some_and(is_eval_reference),
+        Expression::IdReference(ref id) => id == "eval",
+        Expression::Member {
+            ref lhs,
+            ref rhs,
+            computed,
+        } => is_global_eval(lhs, rhs, computed),
+        _ => false,
+    }
+}
+
+impl Program {
+    /// This finds all of the dynamic code in the program, in source order.
+    pub fn dynamic_code(&self) -> Vec<DynamicCode<'_>> {
+        let mut finder = Finder::default();
+        for statement in &self.body {
+            finder.statement(statement);
+        }
+        finder.found
+    }
+
+    /// This is true if dynamic code could add, read or change global variables.
+    pub fn is_dynamic_scope(&self) -> bool {
+        !self.dynamic_code().is_empty()
+    }
+}
+
+impl Function {
+    /// This is true if a direct `eval` in the function, or in a function nested inside
+    /// of it, could add, read or change the local variables of the function.
+    pub fn is_dynamic_scope(&self) -> bool {
+        let mut finder = Finder::default();
+        for statement in &self.body {
+            finder.statement(statement);
+        }
+        finder
+            .found
+            .iter()
+            .any(|code| code.kind == DynamicKind::DirectEval)
+    }
+}
+
+impl Expression {
+    /// This finds all of the dynamic code in the expression, in source order.
+    pub fn dynamic_code(&self) -> Vec<DynamicCode<'_>> {
+        let mut finder = Finder::default();
+        finder.expression(self);
+        finder.found
+    }
+}
+
+#[cfg(test)]
+mod dynamic_tests {
+    use super::*;
+    use span::Span;
+
+    fn call(callee: Expression, arguments: Vec<Expression>) -> Expression {
+        Expression::Call(Box::new(Call { callee, arguments }))
+    }
+
+    fn kinds(expression: &Expression) -> Vec<DynamicKind> {
+        expression
+            .dynamic_code()
+            .iter()
+            .map(|code| code.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_eval() {
+        let direct = call(build_ast!(id "eval"), vec![build_ast!(str "a".to_string())]);
+        assert_eq!(kinds(&direct), vec![DynamicKind::DirectEval]);
+        assert_eq!(direct.dynamic_code()[0].expression, &direct);
+
+        // (0, eval)('a')
+        let comma = Expression::Comma(vec![build_ast!(num 0.0), build_ast!(id "eval")]);
+        let indirect = call(comma, vec![build_ast!(str "a".to_string())]);
+        assert_eq!(kinds(&indirect), vec![DynamicKind::IndirectEval]);
+        assert_eq!(indirect.dynamic_code()[0].expression, &indirect);
+
+        // window.eval('a'), and an alias of eval
+        let member = Expression::Member {
+            lhs: Box::new(build_ast!(id "window")),
+            rhs: Box::new(build_ast!(id "eval")),
+            computed: false,
+        };
+        assert_eq!(
+            kinds(&call(member, vec![])),
+            vec![DynamicKind::IndirectEval]
+        );
+        let alias = Expression::Assignment {
+            operator: AssignmentOperator::Eq,
+            lhs: Box::new(build_ast!(id "e")),
+            rhs: Box::new(build_ast!(id "eval")),
+        };
+        assert_eq!(kinds(&alias), vec![DynamicKind::IndirectEval]);
+
+        // a.eval is a method, not the global eval
+        let method = Expression::Member {
+            lhs: Box::new(build_ast!(id "a")),
+            rhs: Box::new(build_ast!(id "eval")),
+            computed: false,
+        };
+        assert!(kinds(&call(method, vec![])).is_empty());
+
+        // window['eval'] is the global eval, window[eval] only reads the eval variable
+        let member = |rhs, computed| Expression::Member {
+            lhs: Box::new(build_ast!(id "window")),
+            rhs: Box::new(rhs),
+            computed,
+        };
+        let string = build_ast!(str "eval".to_string());
+        assert_eq!(
+            kinds(&member(string.clone(), true)),
+            vec![DynamicKind::IndirectEval]
+        );
+        assert_eq!(
+            kinds(&member(build_ast!(id "eval"), true)),
+            vec![DynamicKind::IndirectEval]
+        );
+        assert_eq!(
+            member(build_ast!(id "eval"), true).dynamic_code()[0].expression,
+            &build_ast!(id "eval")
+        );
+        assert!(kinds(&member(string, false)).is_empty());
+
+        // {eval: 1} only has a key named eval
+        let object = |key| {
+            Expression::ObjectLiteral(vec![Property {
+                kind: PropertyKind::Init,
+                key,
+                value: build_ast!(num 1.0),
+            }])
+        };
+        assert!(kinds(&object(build_ast!(id "eval"))).is_empty());
+        assert!(kinds(&object(build_ast!(str "eval".to_string()))).is_empty());
+        let computed = call(build_ast!(id "eval"), vec![]);
+        assert_eq!(kinds(&object(computed)), vec![DynamicKind::DirectEval]);
+    }
+
+    #[test]
+    fn test_function_constructor() {
+        let new = Expression::New(Box::new(New {
+            callee: build_ast!(id "Function"),
+            arguments: vec![
+                build_ast!(str "a".to_string()),
+                call(build_ast!(id "eval"), vec![]),
+            ],
+        }));
+        assert_eq!(
+            kinds(&new),
+            vec![DynamicKind::FunctionConstructor, DynamicKind::DirectEval]
+        );
+        assert_eq!(
+            kinds(&call(build_ast!(id "Function"), vec![])),
+            vec![DynamicKind::FunctionConstructor]
+        );
+        // new eval() throws, but it is still indirect
+        let new_eval = Expression::New(Box::new(New {
+            callee: build_ast!(id "eval"),
+            arguments: vec![],
+        }));
+        assert_eq!(kinds(&new_eval), vec![DynamicKind::IndirectEval]);
+    }
+
+    #[test]
+    fn test_dynamic_scope() {
+        let program = Program {
+            source_type: SourceType::Script,
+            body: Vec::new(),
+            span: Span::default(),
+            hashbang: None,
+            comments: Vec::new(),
+        };
+        assert!(!program.is_dynamic_scope());
+        let function = Function {
+            id: None,
+            params: Vec::new(),
+            body: Vec::new(),
+            async: false,
+            generator: false,
+        };
+        assert!(!function.is_dynamic_scope());
+    }
+}
@@ -0,0 +1,216 @@
+//! This module contains text edits, which replace a span of the source text with new
+//! text. They are shared by anything that rewrites source code, like autofixes and
+//! codemods, eg.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::edit::{apply_edits, TextEdit};
+//! use ecmascript::span::Span;
+//!
+//! let edits = vec![
+//!     TextEdit::new(Span::new(4, 7), "baz"),
+//!     TextEdit::insert(0, "// "),
+//! ];
+//! assert_eq!(apply_edits("foo(bar)", &edits).unwrap(), "// foo(baz)");
+//! ```
+//!
+//! Edits always refer to offsets in the original source, so a batch of edits can be
+//! collected in any order and applied at once.
+
+use failure::Fail;
+use span::Span;
+use std::fmt;
+
+/// TextEdit replaces the text in a span with a replacement. An empty span inserts the
+/// replacement, and an empty replacement deletes the span.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TextEdit {
+    /// The part of the original source text to replace.
+    pub span: Span,
+    /// The text to put in its place.
+    pub replacement: String,
+}
+
+impl TextEdit {
+    /// This creates an edit that replaces the span with the replacement.
+    pub fn new<S: Into<String>>(span: Span, replacement: S) -> TextEdit {
+        TextEdit {
+            span,
+            replacement: replacement.into(),
+        }
+    }
+
+    /// This creates an edit that inserts text at an offset.
+    pub fn insert<S: Into<String>>(offset: u32, text: S) -> TextEdit {
+        TextEdit::new(Span::empty(offset), text)
+    }
+
+    /// This creates an edit that deletes the span.
+    pub fn delete(span: Span) -> TextEdit {
+        TextEdit::new(span, String::new())
+    }
+}
+
+/// EditError is returned when a batch of edits can not be applied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditError {
+    /// Two edits change the same part of the source text, so the result would depend
+    /// on which one is applied first.
+    Overlap(Span, Span),
+    /// The span is outside of the source text, or does not fall on a char boundary.
+    InvalidSpan(Span),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EditError::Overlap(a, b) => write!(
+                f,
+                "The edits at {}..{} and {}..{} overlap",
+                a.start, a.end, b.start, b.end
+            ),
+            EditError::InvalidSpan(span) => write!(
+                f,
+                "The edit at {}..{} is not a valid range of the source text",
+                span.start, span.end
+            ),
+        }
+    }
+}
+
+impl Fail for EditError {}
+
+/// This sorts edits by where they start in the source text. Insertions come before
+/// replacements that start at the same offset, and edits that are otherwise the same
+/// keep their order, so two insertions at one offset are applied in the order given.
+pub fn sort_edits(edits: &mut [TextEdit]) {
+    edits.sort_by_key(|edit| (edit.span.start, edit.span.end));
+}
+
+/// This returns the first pair of edits that overlap, if there is one. The edits must
+/// be sorted with `sort_edits` first.
+///
+/// Edits that only touch, eg. `0..2` and `2..4`, do not overlap. An insertion overlaps a
+/// replacement if it is strictly inside of it.
+pub fn find_overlap(edits: &[TextEdit]) -> Option<(&TextEdit, &TextEdit)> {
+    edits
+        .windows(2)
+        .find(|pair| pair[0].span.end > pair[1].span.start)
+        .map(|pair| (&pair[0], &pair[1]))
+}
+
+/// This applies a batch of edits to the source text. It fails without changing
+/// anything if any of the edits overlap, or do not fit the source text.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> Result<String, EditError> {
+    let mut edits = edits.to_vec();
+    sort_edits(&mut edits);
+    if let Some((a, b)) = find_overlap(&edits) {
+        return Err(EditError::Overlap(a.span, b.span));
+    }
+
+    let mut result = String::with_capacity(source.len());
+    let mut last = 0;
+    for edit in &edits {
+        if source.get(edit.span.range()).is_none() {
+            return Err(EditError::InvalidSpan(edit.span));
+        }
+        result.push_str(&source[last..edit.span.start as usize]);
+        result.push_str(&edit.replacement);
+        last = edit.span.end as usize;
+    }
+    result.push_str(&source[last..]);
+    Ok(result)
+}
+
+/// This maps an offset in the original source text to the same place in the edited
+/// text. The edits must be sorted and must not overlap.
+///
+/// An offset inside of a replaced span maps to the end of its replacement. An offset at
+/// an insertion point stays before the inserted text.
+pub fn map_offset(edits: &[TextEdit], offset: u32) -> u32 {
+    let mut delta: i64 = 0;
+    for edit in edits {
+        let span = edit.span;
+        let replacement_len = edit.replacement.len() as i64;
+        if offset < span.start || (offset == span.start && span.is_empty()) {
+            break;
+        }
+        if offset < span.end {
+            return (i64::from(span.start) + delta + replacement_len) as u32;
+        }
+        delta += replacement_len - i64::from(span.len());
+    }
+    (i64::from(offset) + delta) as u32
+}
+
+#[cfg(test)]
+mod edit_tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_edits() {
+        let source = "let a = 1;";
+        assert_eq!(apply_edits(source, &[]).unwrap(), source);
+        assert_eq!(
+            apply_edits(
+                source,
+                &[
+                    TextEdit::new(Span::new(0, 3), "const"),
+                    TextEdit::insert(10, " // one"),
+                    TextEdit::insert(4, "_"),
+                    TextEdit::insert(4, "$"),
+                    TextEdit::delete(Span::new(4, 5)),
+                    TextEdit::new(Span::new(8, 9), "2"),
+                ]
+            )
+            .unwrap(),
+            "const _$ = 2; // one"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            apply_edits(
+                "abcdef",
+                &[
+                    TextEdit::delete(Span::new(3, 5)),
+                    TextEdit::delete(Span::new(1, 4)),
+                ]
+            ),
+            Err(EditError::Overlap(Span::new(1, 4), Span::new(3, 5)))
+        );
+        assert_eq!(
+            apply_edits(
+                "abcdef",
+                &[TextEdit::delete(Span::new(1, 4)), TextEdit::insert(2, "x")]
+            ),
+            Err(EditError::Overlap(Span::new(1, 4), Span::new(2, 2)))
+        );
+        assert_eq!(
+            apply_edits("abc", &[TextEdit::insert(4, "x")]),
+            Err(EditError::InvalidSpan(Span::new(4, 4)))
+        );
+        assert_eq!(
+            apply_edits("é", &[TextEdit::insert(1, "x")]),
+            Err(EditError::InvalidSpan(Span::new(1, 1)))
+        );
+    }
+
+    #[test]
+    fn test_map_offset() {
+        let mut edits = vec![
+            TextEdit::new(Span::new(4, 5), "abc"),
+            TextEdit::insert(0, "//"),
+            TextEdit::delete(Span::new(6, 8)),
+        ];
+        sort_edits(&mut edits);
+        assert_eq!(map_offset(&edits, 0), 0);
+        assert_eq!(map_offset(&edits, 2), 4);
+        assert_eq!(map_offset(&edits, 4), 9);
+        assert_eq!(map_offset(&edits, 5), 9);
+        assert_eq!(map_offset(&edits, 7), 10);
+        assert_eq!(map_offset(&edits, 8), 10);
+        assert_eq!(map_offset(&edits, 10), 12);
+    }
+}
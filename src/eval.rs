@@ -0,0 +1,692 @@
+//! This module contains a small constant expression evaluator. It can compute the value
+//! of expressions that have no side effects and do not depend on any runtime state,
+//! eg. `1 + 2`, `'a' + 1`, `!0` or `typeof null`.
+//!
+//! The evaluator follows the real ECMAScript semantics for type coercion, so it can be
+//! used for things like constant folding, lint rules, or reading simple config values.
+//! If an expression can not be evaluated safely, `eval_const` returns `None` instead.
+
+use ast::*;
+use parser::{is_line_terminator, is_whitespace};
+use std::cmp::Ordering;
+use std::f64;
+
+/// JsValue is the result of evaluating a constant expression. Only the primitive
+/// language types are represented, as objects can not be produced without side effects.
+/// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-ecmascript-language-types)
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsValue {
+    /// The value of `void 0`.
+    Undefined,
+    /// The value of `null`.
+    Null,
+    /// Either `true` or `false`.
+    Boolean(bool),
+    /// All numbers are double precision floats, including NaN and -0.
+    Number(f64),
+    /// A string value.
+    String(String),
+}
+
+impl JsValue {
+    /// This converts the value to a boolean, the same way `!!value` would.
+    /// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-toboolean)
+    pub fn to_boolean(&self) -> bool {
+        match *self {
+            JsValue::Undefined | JsValue::Null => false,
+            JsValue::Boolean(b) => b,
+            JsValue::Number(n) => !(n == 0f64 || n.is_nan()),
+            JsValue::String(ref s) => !s.is_empty(),
+        }
+    }
+
+    /// This converts the value to a number, the same way `+value` would.
+    /// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-tonumber)
+    pub fn to_number(&self) -> f64 {
+        match *self {
+            JsValue::Undefined => f64::NAN,
+            JsValue::Null => 0f64,
+            JsValue::Boolean(b) => {
+                if b {
+                    1f64
+                } else {
+                    0f64
+                }
+            }
+            JsValue::Number(n) => n,
+            JsValue::String(ref s) => string_to_number(s),
+        }
+    }
+
+    /// This converts the value to a string, the same way `'' + value` would.
+    /// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-tostring)
+    pub fn to_js_string(&self) -> String {
+        match *self {
+            JsValue::Undefined => "undefined".to_string(),
+            JsValue::Null => "null".to_string(),
+            JsValue::Boolean(b) => b.to_string(),
+            JsValue::Number(n) => number_to_string(n),
+            JsValue::String(ref s) => s.clone(),
+        }
+    }
+
+    /// This returns the same string as the `typeof` operator would.
+    /// [Reference](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-typeof-operator)
+    pub fn type_of(&self) -> &'static str {
+        match *self {
+            JsValue::Undefined => "undefined",
+            JsValue::Null => "object",
+            JsValue::Boolean(_) => "boolean",
+            JsValue::Number(_) => "number",
+            JsValue::String(_) => "string",
+        }
+    }
+}
+
+/// This will try to evaluate an expression without running any code that could have side
+/// effects. Identifiers are never resolved, as even `undefined` and `NaN` can be shadowed.
+pub fn eval_const(expr: &Expression) -> Option<JsValue> {
+    match *expr {
+        Expression::Literal(ref literal) => Some(match *literal {
+            ExpressionLiteral::NullLiteral(_) => JsValue::Null,
+            ExpressionLiteral::BooleanLiteral(b) => JsValue::Boolean(b),
+            ExpressionLiteral::NumberLiteral(ref n) => JsValue::Number(n.value),
+            // a JsValue can not hold lone surrogates
+            ExpressionLiteral::StringLiteral(ref s) => {
+                JsValue::String(s.value.as_str()?.to_string())
+            }
+        }),
+        Expression::TemplateLiteral(ref elements) => {
+            let mut result = String::new();
+            for element in elements {
+                match *element {
+                    TemplateLiteralElement::TemplateElement(ref el) => match el.cooked {
+                        Some(ref cooked) => result += cooked.as_str()?,
+                        None => result += el.cook()?.as_str()?,
+                    },
+                    TemplateLiteralElement::Expression(ref e) => {
+                        result += &eval_const(e)?.to_js_string()
+                    }
+                }
+            }
+            Some(JsValue::String(result))
+        }
+        Expression::Unary {
+            ref operator,
+            ref argument,
+        } => eval_unary(operator, eval_const(argument)?),
+        Expression::Binary {
+            operator: BinaryOperator::And,
+            ref lhs,
+            ref rhs,
+        } => {
+            let lhs = eval_const(lhs)?;
+            if lhs.to_boolean() {
+                eval_const(rhs)
+            } else {
+                Some(lhs)
+            }
+        }
+        Expression::Binary {
+            operator: BinaryOperator::Or,
+            ref lhs,
+            ref rhs,
+        } => {
+            let lhs = eval_const(lhs)?;
+            if lhs.to_boolean() {
+                Some(lhs)
+            } else {
+                eval_const(rhs)
+            }
+        }
+        Expression::Binary {
+            ref operator,
+            ref lhs,
+            ref rhs,
+        } => eval_binary(operator, eval_const(lhs)?, eval_const(rhs)?),
+        Expression::Conditional {
+            ref test,
+            ref alternate,
+            ref consequent,
+        } => {
+            if eval_const(test)?.to_boolean() {
+                eval_const(alternate)
+            } else {
+                eval_const(consequent)
+            }
+        }
+        Expression::Comma(ref expressions) => {
+            let mut last = None;
+            for e in expressions {
+                last = Some(eval_const(e)?);
+            }
+            last
+        }
+        _ => None,
+    }
+}
+
+fn eval_unary(operator: &UnaryOperator, argument: JsValue) -> Option<JsValue> {
+    match *operator {
+        UnaryOperator::Minus => Some(JsValue::Number(-argument.to_number())),
+        UnaryOperator::Plus => Some(JsValue::Number(argument.to_number())),
+        UnaryOperator::Not => Some(JsValue::Boolean(!argument.to_boolean())),
+        UnaryOperator::BitwiseNot => {
+            Some(JsValue::Number(f64::from(!to_int32(argument.to_number()))))
+        }
+        UnaryOperator::Typeof => Some(JsValue::String(argument.type_of().to_string())),
+        UnaryOperator::Void => Some(JsValue::Undefined),
+        UnaryOperator::Delete => None,
+    }
+}
+
+fn eval_binary(operator: &BinaryOperator, lhs: JsValue, rhs: JsValue) -> Option<JsValue> {
+    let number = |n: f64| Some(JsValue::Number(n));
+    let boolean = |b: bool| Some(JsValue::Boolean(b));
+    match *operator {
+        BinaryOperator::EqEq => boolean(loose_equals(&lhs, &rhs)),
+        BinaryOperator::NotEq => boolean(!loose_equals(&lhs, &rhs)),
+        BinaryOperator::EqEqEq => boolean(strict_equals(&lhs, &rhs)),
+        BinaryOperator::NotEqEq => boolean(!strict_equals(&lhs, &rhs)),
+        BinaryOperator::Lt => boolean(less_than(&lhs, &rhs).unwrap_or(false)),
+        BinaryOperator::Gt => boolean(less_than(&rhs, &lhs).unwrap_or(false)),
+        BinaryOperator::Lte => boolean(less_than(&rhs, &lhs) == Some(false)),
+        BinaryOperator::Gte => boolean(less_than(&lhs, &rhs) == Some(false)),
+        BinaryOperator::Shl => number(f64::from(
+            to_int32(lhs.to_number()).wrapping_shl(to_uint32(rhs.to_number()) & 0x1F),
+        )),
+        BinaryOperator::Shr => number(f64::from(
+            to_int32(lhs.to_number()) >> (to_uint32(rhs.to_number()) & 0x1F),
+        )),
+        BinaryOperator::UnsignedShr => number(f64::from(
+            to_uint32(lhs.to_number()) >> (to_uint32(rhs.to_number()) & 0x1F),
+        )),
+        BinaryOperator::Plus => match (lhs, rhs) {
+            (JsValue::String(l), r) => Some(JsValue::String(l + &r.to_js_string())),
+            (l, JsValue::String(r)) => Some(JsValue::String(l.to_js_string() + &r)),
+            (l, r) => number(l.to_number() + r.to_number()),
+        },
+        BinaryOperator::Minus => number(lhs.to_number() - rhs.to_number()),
+        BinaryOperator::Multiply => number(lhs.to_number() * rhs.to_number()),
+        BinaryOperator::Divide => number(lhs.to_number() / rhs.to_number()),
+        BinaryOperator::Mod => number(lhs.to_number() % rhs.to_number()),
+        BinaryOperator::BitwiseOr => number(f64::from(
+            to_int32(lhs.to_number()) | to_int32(rhs.to_number()),
+        )),
+        BinaryOperator::BitwiseXor => number(f64::from(
+            to_int32(lhs.to_number()) ^ to_int32(rhs.to_number()),
+        )),
+        BinaryOperator::BitwiseAnd => number(f64::from(
+            to_int32(lhs.to_number()) & to_int32(rhs.to_number()),
+        )),
+        BinaryOperator::Exponentiation => number(exponentiate(lhs.to_number(), rhs.to_number())),
+        // these are handled in eval_const so they can short circuit
+        BinaryOperator::And | BinaryOperator::Or => None,
+        // these need objects on the right hand side
+        BinaryOperator::In | BinaryOperator::InstanceOf => None,
+    }
+}
+
+// https://www.ecma-international.org/ecma-262/9.0/index.html#sec-strict-equality-comparison
+fn strict_equals(lhs: &JsValue, rhs: &JsValue) -> bool {
+    match (lhs, rhs) {
+        (&JsValue::Number(l), &JsValue::Number(r)) => l == r,
+        (l, r) => l == r,
+    }
+}
+
+// https://www.ecma-international.org/ecma-262/9.0/index.html#sec-abstract-equality-comparison
+fn loose_equals(lhs: &JsValue, rhs: &JsValue) -> bool {
+    match (lhs, rhs) {
+        (&JsValue::Undefined, &JsValue::Null) | (&JsValue::Null, &JsValue::Undefined) => true,
+        (&JsValue::Number(l), &JsValue::String(_)) => l == rhs.to_number(),
+        (&JsValue::String(_), &JsValue::Number(r)) => lhs.to_number() == r,
+        (&JsValue::Boolean(_), _) => loose_equals(&JsValue::Number(lhs.to_number()), rhs),
+        (_, &JsValue::Boolean(_)) => loose_equals(lhs, &JsValue::Number(rhs.to_number())),
+        (l, r) => strict_equals(l, r),
+    }
+}
+
+// This returns None when the spec returns undefined, ie. one of the operands is NaN.
+// https://www.ecma-international.org/ecma-262/9.0/index.html#sec-abstract-relational-comparison
+fn less_than(lhs: &JsValue, rhs: &JsValue) -> Option<bool> {
+    match (lhs, rhs) {
+        // strings are compared by their utf-16 code units, not by their code points
+        (JsValue::String(l), JsValue::String(r)) => {
+            Some(l.encode_utf16().cmp(r.encode_utf16()) == Ordering::Less)
+        }
+        (l, r) => {
+            let (l, r) = (l.to_number(), r.to_number());
+            if l.is_nan() || r.is_nan() {
+                None
+            } else {
+                Some(l < r)
+            }
+        }
+    }
+}
+
+// https://www.ecma-international.org/ecma-262/9.0/index.html#sec-applying-the-exp-operator
+fn exponentiate(base: f64, exponent: f64) -> f64 {
+    // rust follows the c standard here, which says 1 ** NaN and 1 ** Infinity are 1
+    if exponent.is_nan() || (base.abs() == 1f64 && exponent.is_infinite()) {
+        f64::NAN
+    } else {
+        base.powf(exponent)
+    }
+}
+
+// https://www.ecma-international.org/ecma-262/9.0/index.html#sec-touint32
+fn to_uint32(n: f64) -> u32 {
+    if n.is_nan() || n.is_infinite() {
+        return 0;
+    }
+    let modulo = n.trunc() % 4_294_967_296f64;
+    if modulo < 0f64 {
+        (modulo + 4_294_967_296f64) as u32
+    } else {
+        modulo as u32
+    }
+}
+
+// https://www.ecma-international.org/ecma-262/9.0/index.html#sec-toint32
+fn to_int32(n: f64) -> i32 {
+    to_uint32(n) as i32
+}
+
+fn is_js_whitespace(c: char) -> bool {
+    is_whitespace(c) || is_line_terminator(c)
+}
+
+// https://www.ecma-international.org/ecma-262/9.0/index.html#sec-tonumber-applied-to-the-string-type
+fn string_to_number(s: &str) -> f64 {
+    let trimmed = s.trim_matches(is_js_whitespace);
+    if trimmed.is_empty() {
+        return 0f64;
+    }
+    let radix_prefixes = [
+        ("0x", 16),
+        ("0X", 16),
+        ("0o", 8),
+        ("0O", 8),
+        ("0b", 2),
+        ("0B", 2),
+    ];
+    for &(prefix, radix) in &radix_prefixes {
+        if let Some(digits) = trimmed.strip_prefix(prefix) {
+            if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+                return f64::NAN;
+            }
+            return digits.chars().fold(0f64, |acc, c| {
+                acc * f64::from(radix) + f64::from(c.to_digit(radix).unwrap_or(0))
+            });
+        }
+    }
+    let unsigned = trimmed.trim_start_matches(['+', '-']);
+    if unsigned == "Infinity" && trimmed.len() - unsigned.len() <= 1 {
+        return if trimmed.starts_with('-') {
+            f64::NEG_INFINITY
+        } else {
+            f64::INFINITY
+        };
+    }
+    // rust would also accept things like "inf" and "NaN", which are not valid in js
+    if !trimmed
+        .chars()
+        .all(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+    {
+        return f64::NAN;
+    }
+    trimmed.parse::<f64>().unwrap_or(f64::NAN)
+}
+
+// https://www.ecma-international.org/ecma-262/9.0/index.html#sec-tostring-applied-to-the-number-type
+fn number_to_string(n: f64) -> String {
+    if n.is_nan() {
+        return "NaN".to_string();
+    }
+    if n == 0f64 {
+        return "0".to_string();
+    }
+    if n < 0f64 {
+        return format!("-{}", number_to_string(-n));
+    }
+    if n.is_infinite() {
+        return "Infinity".to_string();
+    }
+
+    // the exponent format gives us the shortest digits that round trip, eg. "1.2345e3"
+    let formatted = format!("{:e}", n);
+    let (mantissa, exponent) = formatted.split_at(formatted.find('e').unwrap_or(0));
+    let digits: String = mantissa.chars().filter(|&c| c != '.').collect();
+    let k = digits.len() as i32;
+    let n = exponent[1..].parse::<i32>().unwrap_or(0) + 1;
+
+    if k <= n && n <= 21 {
+        digits + &"0".repeat((n - k) as usize)
+    } else if 0 < n && n <= 21 {
+        let (int, frac) = digits.split_at(n as usize);
+        format!("{}.{}", int, frac)
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let sign = if n - 1 < 0 { '-' } else { '+' };
+        let (first, rest) = digits.split_at(1);
+        if rest.is_empty() {
+            format!("{}e{}{}", first, sign, (n - 1).abs())
+        } else {
+            format!("{}.{}e{}{}", first, rest, sign, (n - 1).abs())
+        }
+    }
+}
+
+#[cfg(test)]
+mod eval_tests {
+    use super::*;
+
+    fn unary(operator: UnaryOperator, argument: Expression) -> Expression {
+        Expression::Unary {
+            operator,
+            argument: Box::new(argument),
+        }
+    }
+
+    fn binary(operator: BinaryOperator, lhs: Expression, rhs: Expression) -> Expression {
+        Expression::Binary {
+            operator,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    fn num(n: f64) -> JsValue {
+        JsValue::Number(n)
+    }
+
+    fn string(s: &str) -> JsValue {
+        JsValue::String(s.to_string())
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(eval_const(&build_ast!(null)), Some(JsValue::Null));
+        assert_eq!(eval_const(&build_ast!(true)), Some(JsValue::Boolean(true)));
+        assert_eq!(eval_const(&build_ast!(num 1f64)), Some(num(1f64)));
+        assert_eq!(
+            eval_const(&build_ast!(str "a".to_string())),
+            Some(string("a"))
+        );
+        assert_eq!(
+            eval_const(&unary(UnaryOperator::Void, build_ast!(num 0f64))),
+            Some(JsValue::Undefined)
+        );
+        // identifiers can be shadowed, so they are never constant
+        assert_eq!(eval_const(&build_ast!(id "undefined".to_string())), None);
+        assert_eq!(eval_const(&build_ast!(this)), None);
+    }
+
+    #[test]
+    fn test_coercions() {
+        // '1' + 2 is '12'
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::Plus,
+                build_ast!(str "1".to_string()),
+                build_ast!(num 2f64)
+            )),
+            Some(string("12"))
+        );
+        // true + null is 1
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::Plus,
+                build_ast!(true),
+                build_ast!(null)
+            )),
+            Some(num(1f64))
+        );
+        // ' 0x1F ' * 1 is 31
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::Multiply,
+                build_ast!(str " 0x1F ".to_string()),
+                build_ast!(num 1f64)
+            )),
+            Some(num(31f64))
+        );
+        // U+0085 is not whitespace, so it is not trimmed
+        match eval_const(&unary(
+            UnaryOperator::Plus,
+            build_ast!(str "\u{85}1".to_string()),
+        )) {
+            Some(JsValue::Number(n)) => assert!(n.is_nan()),
+            other => panic!("expected NaN, got {:?}", other),
+        }
+        // +'inf' is NaN, +'-Infinity' is -Infinity, +'' is 0
+        match eval_const(&unary(
+            UnaryOperator::Plus,
+            build_ast!(str "inf".to_string()),
+        )) {
+            Some(JsValue::Number(n)) => assert!(n.is_nan()),
+            other => panic!("expected NaN, got {:?}", other),
+        }
+        assert_eq!(
+            eval_const(&unary(
+                UnaryOperator::Plus,
+                build_ast!(str "-Infinity".to_string())
+            )),
+            Some(num(f64::NEG_INFINITY))
+        );
+        assert_eq!(
+            eval_const(&unary(UnaryOperator::Plus, build_ast!(str "".to_string()))),
+            Some(num(0f64))
+        );
+        // typeof null is 'object'
+        assert_eq!(
+            eval_const(&unary(UnaryOperator::Typeof, build_ast!(null))),
+            Some(string("object"))
+        );
+    }
+
+    #[test]
+    fn test_number_to_string() {
+        assert_eq!(number_to_string(1f64), "1");
+        assert_eq!(number_to_string(-0f64), "0");
+        assert_eq!(number_to_string(0.1f64), "0.1");
+        assert_eq!(number_to_string(123.456f64), "123.456");
+        assert_eq!(number_to_string(1e21f64), "1e+21");
+        assert_eq!(number_to_string(1.5e21f64), "1.5e+21");
+        assert_eq!(number_to_string(1e20f64), "100000000000000000000");
+        assert_eq!(number_to_string(1e-6f64), "0.000001");
+        assert_eq!(number_to_string(1e-7f64), "1e-7");
+        assert_eq!(number_to_string(f64::NAN), "NaN");
+        assert_eq!(number_to_string(f64::NEG_INFINITY), "-Infinity");
+    }
+
+    #[test]
+    fn test_negative_zero() {
+        // -0 === 0, but 1 / -0 is -Infinity
+        let negative_zero = unary(UnaryOperator::Minus, build_ast!(num 0f64));
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::EqEqEq,
+                negative_zero.clone(),
+                build_ast!(num 0f64)
+            )),
+            Some(JsValue::Boolean(true))
+        );
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::Divide,
+                build_ast!(num 1f64),
+                negative_zero
+            )),
+            Some(num(f64::NEG_INFINITY))
+        );
+    }
+
+    #[test]
+    fn test_bitwise() {
+        // 2147483648 | 0 is -2147483648
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::BitwiseOr,
+                build_ast!(num 2_147_483_648f64),
+                build_ast!(num 0f64)
+            )),
+            Some(num(-2_147_483_648f64))
+        );
+        // -8 >>> 1 is 2147483644
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::UnsignedShr,
+                build_ast!(num - 8f64),
+                build_ast!(num 1f64)
+            )),
+            Some(num(2_147_483_644f64))
+        );
+        // 1 << 33 is 2, as the shift count is masked to 5 bits
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::Shl,
+                build_ast!(num 1f64),
+                build_ast!(num 33f64)
+            )),
+            Some(num(2f64))
+        );
+        // ~NaN is -1
+        assert_eq!(
+            eval_const(&unary(
+                UnaryOperator::BitwiseNot,
+                build_ast!(str "a".to_string())
+            )),
+            Some(num(-1f64))
+        );
+    }
+
+    #[test]
+    fn test_comparisons() {
+        // null == undefined, but null !== undefined
+        let undefined = unary(UnaryOperator::Void, build_ast!(num 0f64));
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::EqEq,
+                build_ast!(null),
+                undefined.clone()
+            )),
+            Some(JsValue::Boolean(true))
+        );
+        assert_eq!(
+            eval_const(&binary(BinaryOperator::EqEqEq, build_ast!(null), undefined)),
+            Some(JsValue::Boolean(false))
+        );
+        // '1' == true
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::EqEq,
+                build_ast!(str "1".to_string()),
+                build_ast!(true)
+            )),
+            Some(JsValue::Boolean(true))
+        );
+        // NaN <= NaN is false
+        let nan = binary(
+            BinaryOperator::Divide,
+            build_ast!(num 0f64),
+            build_ast!(num 0f64),
+        );
+        assert_eq!(
+            eval_const(&binary(BinaryOperator::Lte, nan.clone(), nan)),
+            Some(JsValue::Boolean(false))
+        );
+        // '10' < '9' compares strings, 10 < '9' compares numbers
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::Lt,
+                build_ast!(str "10".to_string()),
+                build_ast!(str "9".to_string())
+            )),
+            Some(JsValue::Boolean(true))
+        );
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::Lt,
+                build_ast!(num 10f64),
+                build_ast!(str "9".to_string())
+            )),
+            Some(JsValue::Boolean(false))
+        );
+    }
+
+    #[test]
+    fn test_exponentiation() {
+        match eval_const(&binary(
+            BinaryOperator::Exponentiation,
+            build_ast!(num 1f64),
+            build_ast!(num f64::INFINITY),
+        )) {
+            Some(JsValue::Number(n)) => assert!(n.is_nan()),
+            other => panic!("expected NaN, got {:?}", other),
+        }
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::Exponentiation,
+                build_ast!(num 2f64),
+                build_ast!(num 4f64)
+            )),
+            Some(num(16f64))
+        );
+    }
+
+    #[test]
+    fn test_short_circuit() {
+        // the right hand side is never evaluated, so it does not need to be constant
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::And,
+                build_ast!(num 0f64),
+                build_ast!(id "sideEffect".to_string())
+            )),
+            Some(num(0f64))
+        );
+        assert_eq!(
+            eval_const(&binary(
+                BinaryOperator::Or,
+                build_ast!(num 0f64),
+                build_ast!(id "sideEffect".to_string())
+            )),
+            None
+        );
+        assert_eq!(
+            eval_const(&Expression::Conditional {
+                test: Box::new(build_ast!(str "".to_string())),
+                alternate: Box::new(build_ast!(id "a".to_string())),
+                consequent: Box::new(build_ast!(num 2f64)),
+            }),
+            Some(num(2f64))
+        );
+    }
+
+    #[test]
+    fn test_template_literal() {
+        assert_eq!(
+            eval_const(&Expression::TemplateLiteral(vec![
+                TemplateLiteralElement::TemplateElement(build_ast!(templ_el {"a".to_string()})),
+                TemplateLiteralElement::Expression(build_ast!(num 1e21f64)),
+                TemplateLiteralElement::TemplateElement(build_ast!(templ_el {"b".to_string()})),
+            ])),
+            Some(string("a1e+21b"))
+        );
+        // an element that was not cooked is cooked on demand
+        let element = TemplateElement {
+            cooked: None,
+            raw: r"\x41".to_string(),
+        };
+        assert_eq!(
+            eval_const(&Expression::TemplateLiteral(vec![
+                TemplateLiteralElement::TemplateElement(element),
+            ])),
+            Some(string("A"))
+        );
+    }
+}
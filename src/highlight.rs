@@ -0,0 +1,211 @@
+//! This module renders source text with syntax highlighting, as HTML or for a terminal,
+//! eg.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::highlight::{to_html, DefaultTheme};
+//!
+//! assert_eq!(
+//!     to_html("if (a < 1)", &DefaultTheme),
+//!     "<span class=\"keyword\">if</span> (<span class=\"identifier\">a</span> &lt; \
+//!      <span class=\"number\">1</span>)"
+//! );
+//! ```
+//!
+//! The colors come from a `Theme`, which picks a style for each kind of token. Tokens
+//! without a style, like whitespace and punctuators in the default theme, are written as
+//! they are. The source text does not have to parse, since it is only split into tokens.
+
+use ast::SourceType;
+use dump::escape_html;
+use lexer::{Lexer, TokenKind};
+use parser::is_line_terminator;
+
+/// Theme picks how each kind of token is styled. Both methods have defaults, so a theme
+/// only has to change the ones it cares about.
+pub trait Theme {
+    /// The CSS class of the `<span>` around tokens of the kind in HTML.
+    fn class(&self, kind: TokenKind) -> Option<&str> {
+        match kind {
+            TokenKind::Comment(_) | TokenKind::Hashbang => Some("comment"),
+            TokenKind::Identifier => Some("identifier"),
+            TokenKind::Keyword => Some("keyword"),
+            TokenKind::Number => Some("number"),
+            TokenKind::String | TokenKind::Template => Some("string"),
+            TokenKind::Regex => Some("regex"),
+            TokenKind::Error => Some("error"),
+            TokenKind::Whitespace | TokenKind::LineTerminator | TokenKind::Punctuator => None,
+        }
+    }
+
+    /// The parameters of the SGR escape sequence for tokens of the kind in a terminal,
+    /// eg. `1;34` for bold blue.
+    fn color(&self, kind: TokenKind) -> Option<&str> {
+        match kind {
+            TokenKind::Comment(_) | TokenKind::Hashbang => Some("2"),
+            TokenKind::Keyword => Some("35"),
+            TokenKind::Number => Some("33"),
+            TokenKind::String | TokenKind::Template => Some("32"),
+            TokenKind::Regex => Some("36"),
+            TokenKind::Error => Some("4;31"),
+            TokenKind::Whitespace
+            | TokenKind::LineTerminator
+            | TokenKind::Identifier
+            | TokenKind::Punctuator => None,
+        }
+    }
+}
+
+/// DefaultTheme is a theme with the default styles.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultTheme;
+
+impl Theme for DefaultTheme {}
+
+/// The styles for the classes of the default theme, eg. for a `<style>` element.
+pub const DEFAULT_CSS: &str = ".comment { color: #888; font-style: italic; }
+.keyword { color: #a626a4; }
+.number { color: #986801; }
+.string { color: #50a14f; }
+.regex { color: #0184bc; }
+.error { color: #e45649; text-decoration: underline wavy; }";
+
+/// This renders the source text as HTML, with a `<span>` around each styled token. The
+/// result is meant to be put in a `<pre>` element. The source text is split into tokens
+/// as a Module.
+pub fn to_html<T: Theme + ?Sized>(source: &str, theme: &T) -> String {
+    to_html_as(source, SourceType::Module, theme)
+}
+
+/// This is the same as `to_html`, but the source text is split into tokens as the given
+/// source type, eg. so `<!--` is a comment in a Script.
+pub fn to_html_as<T: Theme + ?Sized>(source: &str, source_type: SourceType, theme: &T) -> String {
+    write_html(source, source_type, theme, false)
+}
+
+// With `offsets`, every token is in a `<span>` with its byte offsets in the `data-start`
+// and `data-end` attributes, so a page can find the tokens that are in a span.
+pub(crate) fn write_html<T: Theme + ?Sized>(
+    source: &str,
+    source_type: SourceType,
+    theme: &T,
+    offsets: bool,
+) -> String {
+    let mut out = String::new();
+    for token in Lexer::with_source_type(source, source_type) {
+        let text = escape_html(&source[token.span.range()]);
+        let class = theme.class(token.kind);
+        if offsets {
+            out.push_str("<span");
+            if let Some(class) = class {
+                out.push_str(&format!(" class=\"{}\"", class));
+            }
+            out.push_str(&format!(
+                " data-start=\"{}\" data-end=\"{}\">{}</span>",
+                token.span.start, token.span.end, text
+            ));
+        } else {
+            match class {
+                Some(class) => {
+                    out.push_str(&format!("<span class=\"{}\">{}</span>", class, text));
+                }
+                None => out.push_str(&text),
+            }
+        }
+    }
+    out
+}
+
+/// This renders the source text with ANSI escape sequences for a terminal. Every styled
+/// token is reset at the end of each line, so the output can be paged or cut into lines.
+/// The source text is split into tokens as a Module.
+pub fn to_ansi<T: Theme + ?Sized>(source: &str, theme: &T) -> String {
+    to_ansi_as(source, SourceType::Module, theme)
+}
+
+/// This is the same as `to_ansi`, but the source text is split into tokens as the given
+/// source type.
+pub fn to_ansi_as<T: Theme + ?Sized>(source: &str, source_type: SourceType, theme: &T) -> String {
+    let mut out = String::new();
+    for token in Lexer::with_source_type(source, source_type) {
+        let text = &source[token.span.range()];
+        match theme.color(token.kind) {
+            // a comment or template can span many lines, with any of the line terminators
+            Some(color) => {
+                let mut rest = text;
+                loop {
+                    let end = rest.find(is_line_terminator).unwrap_or(rest.len());
+                    if end > 0 {
+                        out.push_str(&format!("\x1b[{}m{}\x1b[0m", color, &rest[..end]));
+                    }
+                    let terminator = match rest[end..].chars().next() {
+                        Some('\r') if rest[end..].starts_with("\r\n") => 2,
+                        Some(c) => c.len_utf8(),
+                        None => break,
+                    };
+                    out.push_str(&rest[end..end + terminator]);
+                    rest = &rest[end + terminator..];
+                }
+            }
+            None => out.push_str(text),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod highlight_tests {
+    use super::*;
+
+    struct Loud;
+
+    impl Theme for Loud {
+        fn class(&self, kind: TokenKind) -> Option<&str> {
+            match kind {
+                TokenKind::Punctuator => Some("p"),
+                _ => None,
+            }
+        }
+
+        fn color(&self, _: TokenKind) -> Option<&str> {
+            Some("1")
+        }
+    }
+
+    #[test]
+    fn test_to_html() {
+        assert_eq!(
+            to_html("'<a>' // &", &DefaultTheme),
+            "<span class=\"string\">'&lt;a&gt;'</span> <span class=\"comment\">// &amp;</span>"
+        );
+        assert_eq!(to_html("a + b", &Loud), "a <span class=\"p\">+</span> b");
+        // html-like comments are only comments in a script
+        assert_eq!(
+            to_html_as("<!-- a", SourceType::Script, &DefaultTheme),
+            "<span class=\"comment\">&lt;!-- a</span>"
+        );
+        assert!(!to_html("<!-- a", &DefaultTheme).contains("comment"));
+        assert_eq!(
+            write_html("a;", SourceType::Module, &Loud, true),
+            "<span data-start=\"0\" data-end=\"1\">a</span>\
+             <span class=\"p\" data-start=\"1\" data-end=\"2\">;</span>"
+        );
+    }
+
+    #[test]
+    fn test_to_ansi() {
+        assert_eq!(
+            to_ansi("x = 1 /* a\nb */", &DefaultTheme),
+            "x = \x1b[33m1\x1b[0m \x1b[2m/* a\x1b[0m\n\x1b[2mb */\x1b[0m"
+        );
+        assert_eq!(to_ansi("a\n", &Loud), "\x1b[1ma\x1b[0m\n");
+        assert_eq!(
+            to_ansi("/* a\r\nb\u{2028}c\rd */", &DefaultTheme),
+            "\x1b[2m/* a\x1b[0m\r\n\x1b[2mb\x1b[0m\u{2028}\x1b[2mc\x1b[0m\r\x1b[2md */\x1b[0m"
+        );
+        assert_eq!(
+            to_ansi_as("<!-- a", SourceType::Script, &DefaultTheme),
+            "\x1b[2m<!-- a\x1b[0m"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! This module contains the string type used for string values in the AST.
+//!
+//! ECMAScript strings are sequences of UTF-16 code units, so they can contain lone
+//! surrogates, eg. `"\uD800"`, which a Rust `String` can not hold. Nearly every string in
+//! real programs is well formed though, so a `JsString` is stored as a `String` whenever
+//! it can be, and only falls back to the UTF-16 code units when it has a lone surrogate.
+//! A string is never stored as code units if it could be stored as a `String`, so two
+//! equal strings always have the same representation.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::ast::JsString;
+//!
+//! let s = JsString::from_utf16(&[0x61, 0xD800]);
+//! assert_eq!(s.as_str(), None);
+//! assert_eq!(s.to_string_lossy(), "a\u{FFFD}");
+//! assert_eq!(s.to_utf16(), vec![0x61, 0xD800]);
+//! assert_eq!(JsString::from_utf16(&[0xD83D, 0xDE00]), "😀");
+//! ```
+
+use std::borrow::Cow;
+use std::char;
+use std::fmt;
+use std::fmt::Write;
+
+/// JsString is a string value that can contain lone surrogates.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct JsString(Repr);
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Repr {
+    Utf8(String),
+    // only used when the code units are not valid UTF-16
+    Utf16(Box<[u16]>),
+}
+
+impl JsString {
+    /// This creates a string from UTF-16 code units. Surrogate pairs are combined, and lone
+    /// surrogates are kept as they are.
+    pub fn from_utf16(units: &[u16]) -> JsString {
+        match String::from_utf16(units) {
+            Ok(s) => JsString(Repr::Utf8(s)),
+            Err(_) => JsString(Repr::Utf16(units.into())),
+        }
+    }
+
+    /// This creates a string from code points, which are allowed to be surrogates. As in
+    /// `from_utf16`, a high surrogate followed by a low surrogate is combined into one
+    /// character.
+    ///
+    /// # Panics
+    ///
+    /// This panics if a code point is larger than `0x10FFFF`.
+    pub fn from_code_points<I: IntoIterator<Item = u32>>(code_points: I) -> JsString {
+        let mut units = Vec::new();
+        let mut buffer = [0u16; 2];
+        for code_point in code_points {
+            match char::from_u32(code_point) {
+                Some(c) => units.extend_from_slice(c.encode_utf16(&mut buffer)),
+                None if code_point <= 0xFFFF => units.push(code_point as u16),
+                None => panic!("invalid code point {:#x}", code_point),
+            }
+        }
+        JsString::from_utf16(&units)
+    }
+
+    /// This returns the string as a `&str`, or `None` if it has a lone surrogate.
+    pub fn as_str(&self) -> Option<&str> {
+        match self.0 {
+            Repr::Utf8(ref s) => Some(s),
+            Repr::Utf16(_) => None,
+        }
+    }
+
+    /// This returns the string with every lone surrogate replaced by U+FFFD REPLACEMENT
+    /// CHARACTER.
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        match self.0 {
+            Repr::Utf8(ref s) => Cow::Borrowed(s),
+            Repr::Utf16(ref units) => Cow::Owned(String::from_utf16_lossy(units)),
+        }
+    }
+
+    /// This returns the UTF-16 code units of the string, which is the exact value the
+    /// string has in ECMAScript.
+    pub fn to_utf16(&self) -> Vec<u16> {
+        match self.0 {
+            Repr::Utf8(ref s) => s.encode_utf16().collect(),
+            Repr::Utf16(ref units) => units.to_vec(),
+        }
+    }
+
+    /// This returns the code points of the string, where each lone surrogate is its own
+    /// code point.
+    pub fn code_points(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        match self.0 {
+            Repr::Utf8(ref s) => Box::new(s.chars().map(u32::from)),
+            Repr::Utf16(ref units) => Box::new(char::decode_utf16(units.iter().cloned()).map(
+                |result| match result {
+                    Ok(c) => u32::from(c),
+                    Err(error) => u32::from(error.unpaired_surrogate()),
+                },
+            )),
+        }
+    }
+
+    /// This is true if the string does not have any lone surrogates.
+    pub fn is_well_formed(&self) -> bool {
+        self.as_str().is_some()
+    }
+
+    /// The number of UTF-16 code units in the string, which is the same as its `length`
+    /// in ECMAScript.
+    pub fn len_utf16(&self) -> usize {
+        match self.0 {
+            Repr::Utf8(ref s) => s.encode_utf16().count(),
+            Repr::Utf16(ref units) => units.len(),
+        }
+    }
+
+    /// This is true if the string has no code units.
+    pub fn is_empty(&self) -> bool {
+        match self.0 {
+            Repr::Utf8(ref s) => s.is_empty(),
+            Repr::Utf16(ref units) => units.is_empty(),
+        }
+    }
+}
+
+impl Default for JsString {
+    fn default() -> JsString {
+        JsString(Repr::Utf8(String::new()))
+    }
+}
+
+impl From<String> for JsString {
+    fn from(s: String) -> JsString {
+        JsString(Repr::Utf8(s))
+    }
+}
+
+impl<'a> From<&'a str> for JsString {
+    fn from(s: &'a str) -> JsString {
+        JsString(Repr::Utf8(s.to_string()))
+    }
+}
+
+impl PartialEq<str> for JsString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == Some(other)
+    }
+}
+
+impl<'a> PartialEq<&'a str> for JsString {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == Some(*other)
+    }
+}
+
+/// This is formatted like the `Debug` output of a `str`, with lone surrogates written as
+/// escapes, eg. `"a\u{d800}"`.
+impl fmt::Debug for JsString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Repr::Utf8(ref s) = self.0 {
+            return fmt::Debug::fmt(s, f);
+        }
+        f.write_char('"')?;
+        for code_point in self.code_points() {
+            match char::from_u32(code_point) {
+                Some(c) => write!(f, "{}", c.escape_debug())?,
+                None => write!(f, "\\u{{{:x}}}", code_point)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+#[cfg(test)]
+mod js_string_tests {
+    use super::*;
+    use std::mem::size_of;
+
+    #[test]
+    fn test_size() {
+        assert_eq!(size_of::<JsString>(), size_of::<String>());
+    }
+
+    #[test]
+    fn test_representation() {
+        // well formed strings are always stored as a String, however they were created
+        assert_eq!(JsString::from_utf16(&[0x61, 0x62]), JsString::from("ab"));
+        assert_eq!(JsString::from_code_points(vec![0xD83D, 0xDE00]), "😀");
+        assert_eq!(JsString::from_code_points(vec![0x1F600]), "😀");
+
+        let lone = JsString::from_code_points(vec![0x61, 0xDE00, 0xD83D]);
+        assert!(!lone.is_well_formed());
+        assert_eq!(lone.to_utf16(), vec![0x61, 0xDE00, 0xD83D]);
+        assert_eq!(lone.code_points().collect::<Vec<_>>(), vec![0x61, 0xDE00, 0xD83D]);
+        assert_eq!(lone.len_utf16(), 3);
+        assert_ne!(lone, "a\u{FFFD}\u{FFFD}");
+        assert_eq!(format!("{:?}", lone), r#""a\u{de00}\u{d83d}""#);
+        assert_eq!(format!("{:?}", JsString::from("a\n")), r#""a\n""#);
+        assert_eq!(JsString::from("😀").len_utf16(), 2);
+    }
+}
@@ -0,0 +1,396 @@
+//! This module contains a parser for JSDoc comments, eg.
+//!
+//! ```javascript
+//! /**
+//!  * Adds two numbers, see {@link subtract}.
+//!  * @param {number} a - the first number
+//!  * @param {number} [b=0] the second number
+//!  * @returns {number} the sum
+//!  * @deprecated
+//!  */
+//! ```
+//!
+//! The comment text is turned into a description and a list of tags. Tags that are not
+//! understood are kept with their raw text, so no information is lost. `parse_jsdoc` takes
+//! the comment as it is written in the source text, and `parse_jsdoc_comment` takes one
+//! of the comments of a `Program`, whose text does not have the delimiters.
+//!
+//! [Reference](https://jsdoc.app/)
+
+use ast::{Comment, CommentKind};
+use combine::error::ParseError;
+use combine::parser::char::{spaces, string};
+use combine::parser::choice::{choice, optional};
+use combine::parser::combinator::try;
+use combine::parser::item::{none_of, satisfy, token};
+use combine::parser::repeat::{many, many1};
+use combine::{eof, Parser, Stream};
+
+/// JsDoc is a parsed documentation comment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsDoc {
+    /// The free text before the first tag.
+    pub description: Vec<JsDocText>,
+    /// The block tags, in the order that they were written.
+    pub tags: Vec<JsDocTag>,
+}
+
+/// Descriptions are text, with inline tags mixed in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsDocText {
+    /// Plain text. Line breaks in the comment are kept as `\n`.
+    Text(String),
+    /// An inline link, eg. `{@link Foo#bar}` or `{@link Foo|the foo class}`.
+    /// `{@linkcode}` and `{@linkplain}` are treated the same way.
+    Link {
+        /// The namepath or url that is being linked to.
+        target: String,
+        /// The text to display instead of the target.
+        text: Option<String>,
+    },
+}
+
+/// JsDocTag is a block tag, which starts with an `@` at the beginning of a line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsDocTag {
+    /// `@param {type} name description`, also written as `@arg` or `@argument`.
+    Param {
+        /// The name of the parameter, eg. `options.verbose`.
+        name: String,
+        /// The text between the braces, if it was given.
+        type_expression: Option<String>,
+        /// This is true if the name was written in square brackets, eg. `[name]`.
+        optional: bool,
+        /// The default value written in the square brackets, eg. `[name=1]`.
+        default: Option<String>,
+        /// The description after the name. A leading `-` is not included.
+        description: Vec<JsDocText>,
+    },
+    /// `@returns {type} description`, also written as `@return`.
+    Returns {
+        /// The text between the braces, if it was given.
+        type_expression: Option<String>,
+        /// The description after the type.
+        description: Vec<JsDocText>,
+    },
+    /// `@deprecated description`.
+    Deprecated {
+        /// The reason, or what to use instead.
+        description: Vec<JsDocText>,
+    },
+    /// Any other tag, eg. `@example` or `@since`.
+    Other {
+        /// The tag name without the `@`.
+        name: String,
+        /// The rest of the tag, exactly as it was written.
+        text: String,
+    },
+}
+
+/// This parses the text of a `/** */` comment, including the comment delimiters.
+/// It returns None if the comment is not a JSDoc comment, eg. `/* */` or `/***/`.
+pub fn parse_jsdoc(comment: &str) -> Option<JsDoc> {
+    if !comment.starts_with("/*") || !comment.ends_with("*/") || comment.len() < 4 {
+        return None;
+    }
+    parse_block_text(&comment[2..comment.len() - 2])
+}
+
+/// This parses a comment of a `Program`, eg. the one before a function. It returns None
+/// if the comment is not a JSDoc comment, like `parse_jsdoc` does.
+pub fn parse_jsdoc_comment(comment: &Comment) -> Option<JsDoc> {
+    if comment.kind != CommentKind::Block {
+        return None;
+    }
+    parse_block_text(&comment.text)
+}
+
+// The text is what is between `/*` and `*/`, which starts with exactly one more `*` in a
+// JSDoc comment.
+fn parse_block_text(text: &str) -> Option<JsDoc> {
+    let body = text.strip_prefix('*')?;
+    if body.is_empty() || body.starts_with('*') {
+        return None;
+    }
+    let lines: Vec<&str> = body.lines().map(strip_leading_asterisk).collect();
+
+    // a tag starts at the beginning of a line, and continues until the next tag
+    let mut blocks = vec![String::new()];
+    for line in lines {
+        if line.trim_start().starts_with('@') {
+            blocks.push(String::new());
+        }
+        let block = blocks.last_mut().unwrap();
+        if !block.is_empty() {
+            block.push('\n');
+        }
+        *block += line;
+    }
+
+    let description = parse_description(&blocks[0]);
+    let tags = blocks[1..].iter().map(|block| parse_tag(block)).collect();
+    Some(JsDoc { description, tags })
+}
+
+fn strip_leading_asterisk(line: &str) -> &str {
+    let trimmed = line.trim_start();
+    match trimmed.strip_prefix('*') {
+        Some(rest) => rest.strip_prefix(' ').unwrap_or(rest),
+        None => trimmed,
+    }
+}
+
+fn parse_tag(block: &str) -> JsDocTag {
+    let block = block.trim();
+    let name_end = block.find(char::is_whitespace).unwrap_or(block.len());
+    let name = &block[1..name_end];
+    let rest = block[name_end..].trim_start();
+    match name {
+        "param" | "arg" | "argument" => parse_param(rest),
+        "returns" | "return" => {
+            let (type_expression, rest) = split_type_expression(rest);
+            JsDocTag::Returns {
+                type_expression,
+                description: parse_description(rest),
+            }
+        }
+        "deprecated" => JsDocTag::Deprecated {
+            description: parse_description(rest),
+        },
+        _ => JsDocTag::Other {
+            name: name.to_string(),
+            text: rest.to_string(),
+        },
+    }
+}
+
+fn parse_param(text: &str) -> JsDocTag {
+    let (type_expression, rest) = split_type_expression(text);
+    let (name, optional, default, rest) = if rest.starts_with('[') {
+        let end = rest.find(']').unwrap_or(rest.len());
+        let inner = &rest[1..end];
+        let (name, default) = match inner.find('=') {
+            Some(i) => (&inner[..i], Some(inner[i + 1..].trim().to_string())),
+            None => (inner, None),
+        };
+        let rest = if end < rest.len() {
+            &rest[end + 1..]
+        } else {
+            ""
+        };
+        (name.trim(), true, default, rest)
+    } else {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        (&rest[..end], false, None, &rest[end..])
+    };
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('-').map_or(rest, str::trim_start);
+    JsDocTag::Param {
+        name: name.to_string(),
+        type_expression,
+        optional,
+        default,
+        description: parse_description(rest),
+    }
+}
+
+/// This splits off a leading `{type}`, taking care of nested braces like `{{a: number}}`.
+fn split_type_expression(text: &str) -> (Option<String>, &str) {
+    if !text.starts_with('{') {
+        return (None, text);
+    }
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    let type_expression = text[1..i].trim().to_string();
+                    return (Some(type_expression), text[i + 1..].trim_start());
+                }
+            }
+            _ => {}
+        }
+    }
+    // the type was never closed, so treat it as part of the description
+    (None, text)
+}
+
+fn parse_description(text: &str) -> Vec<JsDocText> {
+    let text = text.trim();
+    match description().skip(eof()).parse(text) {
+        Ok((parts, _)) => parts,
+        Err(_) => vec![JsDocText::Text(text.to_string())],
+    }
+}
+
+fn description<I>() -> impl Parser<Input = I, Output = Vec<JsDocText>>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    many::<Vec<_>, _>(choice((
+        try(inline_link()),
+        many1(none_of("{".chars())).map(JsDocText::Text),
+        token('{').map(|c: char| JsDocText::Text(c.to_string())),
+    )))
+    .map(|parts| {
+        // merge the text that was split up by braces that were not links
+        let mut merged: Vec<JsDocText> = Vec::new();
+        for part in parts {
+            match (merged.last_mut(), part) {
+                (Some(&mut JsDocText::Text(ref mut last)), JsDocText::Text(ref text)) => {
+                    last.push_str(text)
+                }
+                (_, part) => merged.push(part),
+            }
+        }
+        merged
+    })
+}
+
+fn inline_link<I>() -> impl Parser<Input = I, Output = JsDocText>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    (
+        string("{@link"),
+        optional(try(string("code")).or(try(string("plain")))),
+        satisfy(char::is_whitespace),
+        spaces(),
+        many1::<String, _>(satisfy(|c: char| {
+            c != '}' && c != '|' && !c.is_whitespace()
+        })),
+        many::<String, _>(none_of("}".chars())),
+        token('}'),
+    )
+        .map(|(_, _, _, _, target, text, _)| {
+            let text = text.trim();
+            let text = text.strip_prefix('|').map_or(text, str::trim);
+            JsDocText::Link {
+                target,
+                text: if text.is_empty() {
+                    None
+                } else {
+                    Some(text.to_string())
+                },
+            }
+        })
+}
+
+#[cfg(test)]
+mod jsdoc_tests {
+    use super::*;
+
+    fn text(s: &str) -> JsDocText {
+        JsDocText::Text(s.to_string())
+    }
+
+    #[test]
+    fn test_not_jsdoc() {
+        assert_eq!(parse_jsdoc("/* hello */"), None);
+        assert_eq!(parse_jsdoc("/**/"), None);
+        assert_eq!(parse_jsdoc("/*** hello */"), None);
+        assert_eq!(parse_jsdoc("// hello"), None);
+        assert_eq!(parse_jsdoc("/***/"), None);
+        assert_eq!(parse_jsdoc("/*/"), None);
+    }
+
+    #[test]
+    fn test_program_comments() {
+        let program = ::parse("/** Hello */ /* a */ /**/ /***/ // * b").unwrap();
+        let docs: Vec<_> = program.comments.iter().map(parse_jsdoc_comment).collect();
+        assert_eq!(
+            docs,
+            vec![parse_jsdoc("/** Hello */"), None, None, None, None]
+        );
+        assert!(docs[0].is_some());
+    }
+
+    #[test]
+    fn test_description() {
+        assert_eq!(
+            parse_jsdoc("/** Hello\n * world {@link Foo#bar} and {@linkcode baz|Baz}. {x} */"),
+            Some(JsDoc {
+                description: vec![
+                    text("Hello\nworld "),
+                    JsDocText::Link {
+                        target: "Foo#bar".to_string(),
+                        text: None,
+                    },
+                    text(" and "),
+                    JsDocText::Link {
+                        target: "baz".to_string(),
+                        text: Some("Baz".to_string()),
+                    },
+                    text(". {x}"),
+                ],
+                tags: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_tags() {
+        let doc = parse_jsdoc(
+            "/**
+              * Adds numbers.
+              * @param {number} a - the first
+              *   number
+              * @param {{x: number}} [b=0] the second
+              * @arg c
+              * @returns {number} the sum
+              * @deprecated use {@link add2} instead
+              * @since 1.0
+              */",
+        )
+        .unwrap();
+        assert_eq!(doc.description, vec![text("Adds numbers.")]);
+        assert_eq!(
+            doc.tags,
+            vec![
+                JsDocTag::Param {
+                    name: "a".to_string(),
+                    type_expression: Some("number".to_string()),
+                    optional: false,
+                    default: None,
+                    description: vec![text("the first\n  number")],
+                },
+                JsDocTag::Param {
+                    name: "b".to_string(),
+                    type_expression: Some("{x: number}".to_string()),
+                    optional: true,
+                    default: Some("0".to_string()),
+                    description: vec![text("the second")],
+                },
+                JsDocTag::Param {
+                    name: "c".to_string(),
+                    type_expression: None,
+                    optional: false,
+                    default: None,
+                    description: Vec::new(),
+                },
+                JsDocTag::Returns {
+                    type_expression: Some("number".to_string()),
+                    description: vec![text("the sum")],
+                },
+                JsDocTag::Deprecated {
+                    description: vec![
+                        text("use "),
+                        JsDocText::Link {
+                            target: "add2".to_string(),
+                            text: None,
+                        },
+                        text(" instead"),
+                    ],
+                },
+                JsDocTag::Other {
+                    name: "since".to_string(),
+                    text: "1.0".to_string(),
+                },
+            ]
+        );
+    }
+}
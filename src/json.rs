@@ -0,0 +1,781 @@
+//! This module parses JSON and JSON5 text into expressions, so config files like
+//! `package.json` or `tsconfig.json` can be used with the same tools as source text, eg.
+//!
+//! ```
+//! # #[macro_use] extern crate ecmascript;
+//! use ecmascript::ast::*;
+//! use ecmascript::json::{parse_json, Dialect};
+//!
+//! # fn main() {
+//! let expression = parse_json(r#"{"private": true}"#, Dialect::Json).unwrap();
+//! assert_eq!(
+//!     expression,
+//!     Expression::ObjectLiteral(vec![build_ast!([str "private"]: [true])])
+//! );
+//! # }
+//! ```
+//!
+//! Every JSON text is also an ECMAScript expression, and the tree is the one that the text
+//! means as an expression. Negative numbers are a `-` applied to a number, like they are
+//! in source text, and in JSON5 `Infinity` and `NaN` are identifier references. Keys are
+//! string literals, or identifier references for the unquoted keys of JSON5. Only what
+//! the dialect allows is accepted, eg. a trailing comma is a syntax error in JSON, just
+//! like it is for `JSON.parse`.
+//!
+//! With the `serde_json` feature, `to_value` goes the other way, and turns an expression
+//! that is only made of literals into a `serde_json::Value`, eg. a JSON5 config file that
+//! was parsed with `parse_json`, so it can be read with serde.
+//!
+//! [JSON](https://www.ecma-international.org/ecma-262/9.0/index.html#sec-json.parse)
+//! [JSON5](https://spec.json5.org/)
+
+use ast::*;
+use combine::easy;
+use combine::error::ParseError;
+use combine::parser::char::{digit, hex_digit, string};
+use combine::parser::choice::{choice, optional};
+use combine::parser::combinator::try;
+use combine::parser::item::{one_of, satisfy, token};
+use combine::parser::repeat::{many, many1, skip_many};
+use combine::parser::sequence::between;
+use combine::stream::state::{SourcePosition, State};
+use combine::{eof, Parser, Stream};
+#[cfg(feature = "serde_json")]
+use eval::JsValue;
+#[cfg(feature = "serde_json")]
+use failure::Fail;
+use parser::{
+    comment_text, identifier_name, integer_value, is_line_terminator, is_whitespace,
+    string_literal, SyntaxError,
+};
+#[cfg(feature = "serde_json")]
+use parser::{is_reserved_word, satisfy_id_continue};
+#[cfg(feature = "serde_json")]
+use serde_json::{Map, Number, Value};
+#[cfg(feature = "serde_json")]
+use std::fmt;
+
+/// Dialect is the flavor of JSON that is accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// Plain JSON, which is what `JSON.parse` accepts.
+    Json,
+    /// JSON5, which adds comments, trailing commas, unquoted keys, single quoted
+    /// strings, hexadecimal numbers, `Infinity` and `NaN` and more.
+    Json5,
+}
+
+/// The number of arrays and objects that can be nested in each other. Code that walks
+/// the tree is usually recursive, so deeper text is a syntax error instead of a tree that
+/// could overflow the stack.
+pub const MAX_DEPTH: usize = 128;
+
+/// This parses the text as a single JSON value in the dialect. Whitespace around the
+/// value is allowed, and comments as well in JSON5. Arrays and objects that are nested
+/// in each other more than `MAX_DEPTH` times are a syntax error.
+pub fn parse_json(source: &str, dialect: Dialect) -> Result<Expression, SyntaxError> {
+    let json5 = dialect == Dialect::Json5;
+    let mut input = State::new(source);
+    // the arrays and objects that are open, innermost last, are kept here instead of
+    // being parsed recursively, so the depth of the text can not overflow the stack
+    let mut open: Vec<Open> = Vec::new();
+    loop {
+        let (item, rest) = step(item(json5), input.clone())?;
+        let mut value = match item {
+            Item::Value(value) => {
+                input = rest;
+                value
+            }
+            Item::Open(_) if open.len() == MAX_DEPTH => {
+                // the position is of the `[` or `{`, which is right before the rest
+                return Err(SyntaxError {
+                    line: rest.positioner.line,
+                    column: rest.positioner.column - 1,
+                    message: format!(
+                        "Arrays and objects can not be nested more than {} deep\n",
+                        MAX_DEPTH
+                    ),
+                    suggestion: None,
+                });
+            }
+            Item::Open(container) => {
+                open.push(container);
+                // the array or object can be empty
+                let (closed, rest) = begin_element(&mut open, true, json5, rest)?;
+                input = rest;
+                match closed {
+                    Some(value) => value,
+                    None => continue,
+                }
+            }
+        };
+        // the value can be the last element of the innermost container, and the
+        // container can be the last element of the one that it is in, and so on
+        loop {
+            let close = match open.last_mut() {
+                Some(container) => {
+                    container.push(value);
+                    container.close()
+                }
+                None => {
+                    step(whitespace(json5).with(eof()), input)?;
+                    return Ok(value);
+                }
+            };
+            let separator = whitespace(json5).with(token(',').or(token(close)));
+            let (separator, rest) = step(separator, input)?;
+            if separator == ',' {
+                // JSON5 allows a trailing comma
+                let (closed, rest) = begin_element(&mut open, json5, json5, rest)?;
+                input = rest;
+                match closed {
+                    Some(closed) => value = closed,
+                    None => break,
+                }
+            } else {
+                input = rest;
+                value = open.pop().unwrap().into_expression();
+            }
+        }
+    }
+}
+
+type Input<'a> = State<&'a str, SourcePosition>;
+
+// This runs the parser at the input, and returns what it parsed with the input after it.
+fn step<'a, P>(mut parser: P, input: Input<'a>) -> Result<(P::Output, Input<'a>), SyntaxError>
+where
+    P: Parser<Input = easy::Stream<Input<'a>>>,
+{
+    Ok(parser.easy_parse(input)?)
+}
+
+// Open is an array or an object that is being parsed.
+enum Open {
+    Array(Vec<Expression>),
+    // the properties so far, and the key of the property whose value is parsed next
+    Object(Vec<Property>, Option<Expression>),
+}
+
+impl Open {
+    fn close(&self) -> char {
+        match *self {
+            Open::Array(_) => ']',
+            Open::Object(..) => '}',
+        }
+    }
+
+    fn push(&mut self, value: Expression) {
+        match *self {
+            Open::Array(ref mut elements) => elements.push(value),
+            Open::Object(ref mut properties, ref mut key) => properties.push(Property {
+                key: key.take().unwrap(),
+                value,
+                kind: PropertyKind::Init,
+            }),
+        }
+    }
+
+    fn into_expression(self) -> Expression {
+        match self {
+            Open::Array(elements) => Expression::ArrayLiteral(elements),
+            Open::Object(properties, _) => Expression::ObjectLiteral(properties),
+        }
+    }
+}
+
+// This parses what comes before the next element of the innermost container, which is
+// the key of a property in an object. If the container can be closed and it is, it is
+// returned instead.
+fn begin_element<'a>(
+    open: &mut Vec<Open>,
+    can_close: bool,
+    json5: bool,
+    mut input: Input<'a>,
+) -> Result<(Option<Expression>, Input<'a>), SyntaxError> {
+    if can_close {
+        let close = open.last().unwrap().close();
+        let (closed, rest) = step(optional(try(whitespace(json5).with(token(close)))), input)?;
+        if closed.is_some() {
+            return Ok((Some(open.pop().unwrap().into_expression()), rest));
+        }
+        input = rest;
+    }
+    if let Some(&mut Open::Object(_, ref mut key)) = open.last_mut() {
+        let property = (
+            whitespace(json5),
+            key_name(json5),
+            whitespace(json5),
+            token(':'),
+        );
+        let (name, rest) = step(property.map(|(_, name, _, _)| name), input)?;
+        *key = Some(name);
+        input = rest;
+    }
+    Ok((None, input))
+}
+
+// Item is a value, or the start of an array or an object.
+enum Item {
+    Value(Expression),
+    Open(Open),
+}
+
+// Every item skips the whitespace before it, and so do the punctuators between them.
+fn item<I>(json5: bool) -> impl Parser<Input = I, Output = Item>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let quoted = if json5 {
+        string_literal(true).map(|s| s.value).left()
+    } else {
+        json_string().right()
+    };
+    let literal = |expression| Item::Value(Expression::Literal(expression));
+    let item = choice((
+        token('[').map(|_| Item::Open(Open::Array(Vec::new()))),
+        token('{').map(|_| Item::Open(Open::Object(Vec::new(), None))),
+        quoted.map(move |s| literal(ExpressionLiteral::StringLiteral(Box::new(s.into())))),
+        number(json5).map(Item::Value),
+        try(string("null")).map(move |_| literal(ExpressionLiteral::NullLiteral(NullLiteral))),
+        try(string("true")).map(move |_| literal(ExpressionLiteral::BooleanLiteral(true))),
+        try(string("false")).map(move |_| literal(ExpressionLiteral::BooleanLiteral(false))),
+    ));
+    whitespace(json5).with(item)
+}
+
+fn key_name<I>(json5: bool) -> impl Parser<Input = I, Output = Expression>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    if json5 {
+        choice((
+            string_literal(true).map(|s| {
+                Expression::Literal(ExpressionLiteral::StringLiteral(Box::new(s.value.into())))
+            }),
+            identifier_name().map(|(name, _)| Expression::IdReference(name.into())),
+        ))
+        .left()
+    } else {
+        json_string()
+            .map(|s| Expression::Literal(ExpressionLiteral::StringLiteral(Box::new(s.into()))))
+            .right()
+    }
+}
+
+// JSON strings can only be written with double quotes, and only have a few escapes.
+// Lone surrogates are allowed, eg. `"\uD800"`.
+fn json_string<I>() -> impl Parser<Input = I, Output = JsString>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let escape = token('\\').with(choice((
+        one_of("\"\\/bfnrt".chars()).map(|c| match c {
+            'b' => 0x08,
+            'f' => 0x0C,
+            'n' => 0x0A,
+            'r' => 0x0D,
+            't' => 0x09,
+            c => c as u16,
+        }),
+        token('u')
+            .with((hex_digit(), hex_digit(), hex_digit(), hex_digit()))
+            .map(|(a, b, c, d): (char, char, char, char)| {
+                [a, b, c, d].iter().fold(0, |unit, digit: &char| {
+                    unit * 16 + digit.to_digit(16).unwrap() as u16
+                })
+            }),
+    )));
+    let character = choice((
+        escape.map(|unit| vec![unit]),
+        // control characters have to be escaped
+        satisfy(|c: char| c != '"' && c != '\\' && c >= ' ')
+            .map(|c: char| c.encode_utf16(&mut [0; 2]).to_vec()),
+    ));
+    between(token('"'), token('"'), many(character))
+        .map(|units: Vec<Vec<u16>>| JsString::from_utf16(&units.concat()))
+}
+
+// JSON only allows a leading `-`, and JSON5 allows a `+` as well.
+fn number<I>(json5: bool) -> impl Parser<Input = I, Output = Expression>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let signs = if json5 { "+-" } else { "-" };
+    let magnitude = if json5 {
+        choice((
+            try(hex_number()),
+            try(string("Infinity")).map(|_| Expression::IdReference("Infinity".into())),
+            try(string("NaN")).map(|_| Expression::IdReference("NaN".into())),
+            decimal_number(true),
+        ))
+        .left()
+    } else {
+        decimal_number(false).right()
+    };
+    (optional(one_of(signs.chars())), magnitude).map(|(sign, argument)| {
+        let operator = match sign {
+            Some('-') => UnaryOperator::Minus,
+            Some(_) => UnaryOperator::Plus,
+            None => return argument,
+        };
+        Expression::Unary {
+            operator,
+            argument: Box::new(argument),
+        }
+    })
+}
+
+fn hex_number<I>() -> impl Parser<Input = I, Output = Expression>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    (token('0'), one_of("xX".chars()), many1(hex_digit())).map(|(_, _, digits): (_, _, String)| {
+        Expression::Literal(ExpressionLiteral::NumberLiteral(NumberLiteral {
+            value: integer_value(&digits, 16),
+            kind: NumberKind::Hex,
+            raw: None,
+        }))
+    })
+}
+
+// JSON5 allows the integer or the fraction to be left out, eg. `.5` or `5.`, but JSON
+// needs both sides of the dot.
+fn decimal_number<I>(json5: bool) -> impl Parser<Input = I, Output = Expression>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let with_integer =
+        (integer_part(), optional(fraction(json5))).map(|(integer, fraction)| match fraction {
+            Some(ref fraction) if !fraction.is_empty() => format!("{}.{}", integer, fraction),
+            _ => integer,
+        });
+    let mantissa = if json5 {
+        choice((
+            with_integer,
+            token('.')
+                .with(many1(digit()))
+                .map(|fraction: String| format!("0.{}", fraction)),
+        ))
+        .left()
+    } else {
+        with_integer.right()
+    };
+    (mantissa, optional(exponent())).map(|(mantissa, exponent)| {
+        let text = format!("{}{}", mantissa, exponent.unwrap_or_default());
+        let value = text.parse::<f64>().unwrap();
+        Expression::Literal(ExpressionLiteral::NumberLiteral(value.into()))
+    })
+}
+
+// Leading zeros are not allowed, eg. `01`.
+fn integer_part<I>() -> impl Parser<Input = I, Output = String>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    choice((
+        token('0').map(|_| "0".to_string()),
+        (one_of("123456789".chars()), many(digit()))
+            .map(|(first, rest): (char, String)| format!("{}{}", first, rest)),
+    ))
+}
+
+fn fraction<I>(json5: bool) -> impl Parser<Input = I, Output = String>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let digits = if json5 {
+        many(digit()).left()
+    } else {
+        many1(digit()).right()
+    };
+    token('.').with(digits)
+}
+
+fn exponent<I>() -> impl Parser<Input = I, Output = String>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    (
+        one_of("eE".chars()),
+        optional(one_of("+-".chars())),
+        many1(digit()),
+    )
+        .map(|(_, sign, digits): (_, _, String)| format!("e{}{}", sign.unwrap_or('+'), digits))
+}
+
+// JSON only has four whitespace characters, and JSON5 has every whitespace character
+// and line terminator of ECMAScript, and comments.
+fn whitespace<I>(json5: bool) -> impl Parser<Input = I, Output = ()>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let space = satisfy(move |c: char| {
+        matches!(c, ' ' | '\t' | '\n' | '\r')
+            || (json5 && (is_whitespace(c) || is_line_terminator(c)))
+    })
+    .map(|_| ());
+    if json5 {
+        skip_many(space.or(try(comment_text()).map(|_| ()))).left()
+    } else {
+        skip_many(space).right()
+    }
+}
+
+/// ValueError is returned when an expression can not be turned into a JSON value.
+#[cfg(feature = "serde_json")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueError {
+    /// Where the expression is in the value, eg. `dependencies["left-pad"]`. It is empty
+    /// for the whole value.
+    pub path: String,
+    /// Why the expression can not be turned into a JSON value.
+    pub message: String,
+}
+
+#[cfg(feature = "serde_json")]
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{} at `{}`", self.message, self.path)
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl Fail for ValueError {}
+
+/// This turns an expression into the JSON value that it evaluates to, if it is only made
+/// of object, array, string, number, boolean and `null` literals, and numbers with a sign.
+/// Anything else is an error, eg. an identifier, a function, a spread element, a getter
+/// or a computed key, since its value is not known without running the code. Numbers
+/// that are not finite and strings with lone surrogates are errors as well, since they
+/// can not be represented in JSON.
+///
+/// Integers are turned into integer values, so `1` is written back as `1` instead of
+/// `1.0`. When a key is used more than once, the last value wins, like it does in an
+/// object literal. A `__proto__` key is an error, since in an object literal it sets the
+/// prototype instead of adding a field.
+///
+/// An identifier reference key is taken as the name of the field, like the unquoted keys
+/// of JSON5. A property does not record whether its key was computed, so `{[a]: 1}` in
+/// a tree that was built by hand is the same as `{a: 1}`.
+#[cfg(feature = "serde_json")]
+pub fn to_value(expression: &Expression) -> Result<Value, ValueError> {
+    let mut path = String::new();
+    convert(expression, &mut path)
+}
+
+#[cfg(feature = "serde_json")]
+fn convert(expression: &Expression, path: &mut String) -> Result<Value, ValueError> {
+    match *expression {
+        Expression::Literal(ExpressionLiteral::NullLiteral(_)) => Ok(Value::Null),
+        Expression::Literal(ExpressionLiteral::BooleanLiteral(b)) => Ok(Value::Bool(b)),
+        Expression::Literal(ExpressionLiteral::NumberLiteral(ref n)) => number_value(n.value, path),
+        Expression::Literal(ExpressionLiteral::StringLiteral(ref s)) => {
+            Ok(Value::String(string_value(&s.value, path)?))
+        }
+        Expression::Unary {
+            ref operator,
+            ref argument,
+        } => match (operator, &**argument) {
+            (
+                UnaryOperator::Minus,
+                Expression::Literal(ExpressionLiteral::NumberLiteral(ref n)),
+            ) => number_value(-n.value, path),
+            (UnaryOperator::Plus, Expression::Literal(ExpressionLiteral::NumberLiteral(ref n))) => {
+                number_value(n.value, path)
+            }
+            _ => Err(error(
+                path,
+                "The operand of a sign has to be a number literal",
+            )),
+        },
+        Expression::ArrayLiteral(ref elements) => {
+            let mut values = Vec::new();
+            for (i, element) in elements.iter().enumerate() {
+                let length = path.len();
+                path.push_str(&format!("[{}]", i));
+                values.push(convert(element, path)?);
+                path.truncate(length);
+            }
+            Ok(Value::Array(values))
+        }
+        Expression::ObjectLiteral(ref properties) => {
+            let mut map = Map::new();
+            for property in properties {
+                let key = match property.key {
+                    Expression::IdReference(ref id) => id.to_string(),
+                    Expression::Literal(ExpressionLiteral::StringLiteral(ref s)) => {
+                        string_value(&s.value, path)?
+                    }
+                    Expression::Literal(ExpressionLiteral::NumberLiteral(ref n)) => {
+                        JsValue::Number(n.value).to_js_string()
+                    }
+                    _ => return Err(error(path, "A computed key has no known value")),
+                };
+                let length = path.len();
+                push_key(path, &key);
+                if property.kind != PropertyKind::Init {
+                    return Err(error(path, "A getter or setter has no known value"));
+                }
+                if key == "__proto__" {
+                    return Err(error(path, "A `__proto__` key sets the prototype"));
+                }
+                let value = convert(&property.value, path)?;
+                path.truncate(length);
+                map.insert(key, value);
+            }
+            Ok(Value::Object(map))
+        }
+        _ => Err(error(path, "The expression is not a literal")),
+    }
+}
+
+// Integers that a double can hold exactly are kept as integers. `-0` is not, so it keeps
+// its sign.
+#[cfg(feature = "serde_json")]
+fn number_value(n: f64, path: &str) -> Result<Value, ValueError> {
+    const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991f64;
+    if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER && !(n == 0.0 && n.is_sign_negative()) {
+        return Ok(Value::Number((n as i64).into()));
+    }
+    Number::from_f64(n)
+        .map(Value::Number)
+        .ok_or_else(|| error(path, "The number is not finite"))
+}
+
+#[cfg(feature = "serde_json")]
+fn string_value(s: &JsString, path: &str) -> Result<String, ValueError> {
+    s.as_str()
+        .map(str::to_string)
+        .ok_or_else(|| error(path, "The string has a lone surrogate"))
+}
+
+// Keys that are identifiers are written as `.key`, and any other key as `["key"]`.
+#[cfg(feature = "serde_json")]
+fn push_key(path: &mut String, key: &str) {
+    let mut chars = key.chars();
+    let is_identifier = chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '$' || c == '_')
+        && chars.all(satisfy_id_continue)
+        && !is_reserved_word(key, true);
+    if !is_identifier {
+        path.push_str(&format!("[{:?}]", key));
+    } else if path.is_empty() {
+        path.push_str(key);
+    } else {
+        path.push('.');
+        path.push_str(key);
+    }
+}
+
+#[cfg(feature = "serde_json")]
+fn error(path: &str, message: &str) -> ValueError {
+    ValueError {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod json_tests {
+    use super::*;
+
+    fn minus(argument: Expression) -> Expression {
+        Expression::Unary {
+            operator: UnaryOperator::Minus,
+            argument: Box::new(argument),
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        let source = " {\"a\": [1, -0.5, 2e-3, \"x\\u0041\\n\\uD800\"], \"b\": null}\r\n";
+        assert_eq!(
+            parse_json(source, Dialect::Json),
+            Ok(Expression::ObjectLiteral(vec![
+                Property {
+                    key: build_ast!(str "a"),
+                    value: Expression::ArrayLiteral(vec![
+                        build_ast!(num 1f64),
+                        minus(build_ast!(num 0.5)),
+                        build_ast!(num 0.002),
+                        build_ast!(str JsString::from_utf16(&[0x78, 0x41, 0x0A, 0xD800])),
+                    ]),
+                    kind: PropertyKind::Init,
+                },
+                build_ast!([str "b"]: [null]),
+            ]))
+        );
+        assert_eq!(
+            parse_json("[[], {}, true, false]", Dialect::Json),
+            Ok(Expression::ArrayLiteral(vec![
+                build_ast!(array []),
+                Expression::ObjectLiteral(vec![]),
+                build_ast!(true),
+                build_ast!(false),
+            ]))
+        );
+        for source in &[
+            "",
+            "[1,]",
+            "{a: 1}",
+            "'a'",
+            "01",
+            ".5",
+            "5.",
+            "+1",
+            "0x1",
+            "NaN",
+            "\"\t\"",
+            "/* a */ 1",
+            "1 2",
+        ] {
+            assert!(parse_json(source, Dialect::Json).is_err(), "{:?}", source);
+        }
+        let error = parse_json("{\n  \"a\": tru\n}", Dialect::Json).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 8));
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let nested = |depth: usize, open: &str, close: &str| {
+            format!("{}1{}", open.repeat(depth), close.repeat(depth))
+        };
+        let deepest = nested(MAX_DEPTH, "[", "]");
+        assert!(parse_json(&deepest, Dialect::Json).is_ok());
+        let objects = nested(MAX_DEPTH, "{\"a\":", "}");
+        assert!(parse_json(&objects, Dialect::Json5).is_ok());
+
+        let too_deep = nested(MAX_DEPTH + 1, "[", "]");
+        let error = parse_json(&too_deep, Dialect::Json).unwrap_err();
+        assert_eq!((error.line, error.column), (1, MAX_DEPTH as i32 + 1));
+        assert!(
+            error.message.contains("nested more than 128 deep"),
+            "{}",
+            error.message
+        );
+        // this used to overflow the stack
+        let error = parse_json(&"[".repeat(100_000), Dialect::Json5).unwrap_err();
+        assert!(
+            error.message.contains("nested more than 128 deep"),
+            "{}",
+            error.message
+        );
+    }
+
+    #[test]
+    fn test_json5() {
+        let source = "// config\n{unquoted: 'a\\x41', 'b': [.5, 5., +0xFF, -Infinity, NaN,],}";
+        let hex = NumberLiteral {
+            value: 255f64,
+            kind: NumberKind::Hex,
+            raw: None,
+        };
+        assert_eq!(
+            parse_json(source, Dialect::Json5),
+            Ok(Expression::ObjectLiteral(vec![
+                build_ast!([id "unquoted"]: [str "aA"]),
+                Property {
+                    key: build_ast!(str "b"),
+                    value: Expression::ArrayLiteral(vec![
+                        build_ast!(num 0.5),
+                        build_ast!(num 5f64),
+                        Expression::Unary {
+                            operator: UnaryOperator::Plus,
+                            argument: Box::new(build_ast!(num hex)),
+                        },
+                        minus(build_ast!(id "Infinity")),
+                        build_ast!(id "NaN"),
+                    ]),
+                    kind: PropertyKind::Init,
+                },
+            ]))
+        );
+        assert_eq!(
+            parse_json("/* a */ [1] // b", Dialect::Json5),
+            Ok(build_ast!(array [ [num 1f64] ]))
+        );
+        assert!(parse_json("\u{2028}\u{3000}1\u{FEFF}", Dialect::Json5).is_ok());
+        for source in &[
+            "[1,,]",
+            "{,}",
+            "{a b: 1}",
+            "01",
+            "0b1",
+            "undefined",
+            "\u{85}1",
+        ] {
+            assert!(parse_json(source, Dialect::Json5).is_err(), "{:?}", source);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde_json")]
+    fn test_to_value() {
+        let source = "{a: [1, -0.5, 'b', null, true], 'c-d': {'2': 1e300, $e: +3}, f: 1, f: -0}";
+        let expression = parse_json(source, Dialect::Json5).unwrap();
+        assert_eq!(
+            to_value(&expression).unwrap().to_string(),
+            r#"{"a":[1,-0.5,"b",null,true],"c-d":{"$e":3,"2":1e+300},"f":-0.0}"#
+        );
+
+        let errors: Vec<_> = [
+            "[NaN]",
+            "{a: {b: [1, -Infinity]}}",
+            "{'a b': {c: 1e400}}",
+            "['\\uD800']",
+            "{a: {__proto__: {}}}",
+            "{'__proto__': 1}",
+        ]
+        .iter()
+        .map(|source| {
+            let expression = parse_json(source, Dialect::Json5).unwrap();
+            to_value(&expression).unwrap_err().to_string()
+        })
+        .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "The expression is not a literal at `[0]`",
+                "The operand of a sign has to be a number literal at `a.b[1]`",
+                "The number is not finite at `[\"a b\"].c`",
+                "The string has a lone surrogate at `[0]`",
+                "A `__proto__` key sets the prototype at `a.__proto__`",
+                "A `__proto__` key sets the prototype at `__proto__`",
+            ]
+        );
+
+        let getter = Expression::ObjectLiteral(vec![Property {
+            key: build_ast!(id "a"),
+            value: build_ast!(null),
+            kind: PropertyKind::Get,
+        }]);
+        assert_eq!(
+            to_value(&getter).unwrap_err(),
+            ValueError {
+                path: "a".to_string(),
+                message: "A getter or setter has no known value".to_string(),
+            }
+        );
+        let computed = Expression::ObjectLiteral(vec![build_ast!([this]: [null])]);
+        assert_eq!(
+            to_value(&computed).unwrap_err().to_string(),
+            "A computed key has no known value"
+        );
+    }
+}
@@ -0,0 +1,533 @@
+//! This module splits source text into tokens, eg. for syntax highlighting.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::lexer::{Lexer, TokenKind};
+//!
+//! let kinds: Vec<_> = Lexer::new("a = /b/g")
+//!     .filter(|token| token.kind != TokenKind::Whitespace)
+//!     .map(|token| token.kind)
+//!     .collect();
+//! assert_eq!(
+//!     kinds,
+//!     vec![TokenKind::Identifier, TokenKind::Punctuator, TokenKind::Regex]
+//! );
+//! ```
+//!
+//! Every character of the source is in exactly one token, so the tokens can be joined
+//! back into the source text, and a character that does not start a valid token is an
+//! `Error` token of its own. Lexing never fails, which is what an editor needs while a
+//! document is being typed.
+//!
+//! Whether `/` starts a regular expression or is a division depends on the token before
+//! it, and a `}` can end a template substitution. The lexer keeps track of both in a
+//! `LexerState`, so it can be saved with `checkpoint` and resumed with `relex_from`, eg.
+//! to only lex the lines after an edit:
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::lexer::Lexer;
+//!
+//! let mut lexer = Lexer::new("`${a}` / 2");
+//! lexer.by_ref().take(3).count();
+//! let checkpoint = lexer.checkpoint();
+//! let tail: Vec<_> = lexer.collect();
+//! let edited = "`${a}` / 3";
+//! let relexed: Vec<_> = Lexer::relex_from(edited, checkpoint.offset, checkpoint.state).collect();
+//! assert_eq!(tail.len(), relexed.len());
+//! ```
+//!
+//! The choice between a regular expression and a division is made the way most editors
+//! make it, from the previous token alone. It is wrong in rare cases, eg. `if (a) /b/.test(c)`.
+
+use ast::{CommentKind, SourceType};
+use combine::parser::repeat::many;
+use combine::Parser;
+use parser::{
+    comment_text, hashbang, html_open_comment, identifier_name, is_line_terminator,
+    is_reserved_word, is_whitespace, numeric_literal, regex_literal, string_literal,
+    template_character,
+};
+use span::{Span, Spanned};
+use std::mem;
+
+/// Token is a piece of the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    /// What the token is.
+    pub kind: TokenKind,
+    /// The span of the token in the source text.
+    pub span: Span,
+}
+
+impl Spanned for Token {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// TokenKind is the kind of a token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// Whitespace on a single line.
+    Whitespace,
+    /// A single line terminator, where `\r\n` counts as one.
+    LineTerminator,
+    /// A comment.
+    Comment(CommentKind),
+    /// The `#!` line at the start of the source text.
+    Hashbang,
+    /// An identifier name that is not reserved, or a reserved word written with escapes.
+    Identifier,
+    /// A reserved word, including `null`, `true` and `false`.
+    Keyword,
+    /// A punctuator, eg. `{` or `>>>=`.
+    Punctuator,
+    /// A numeric literal.
+    Number,
+    /// A string literal.
+    String,
+    /// A whole template without substitutions, or the part of a template before, between
+    /// or after its substitutions, eg. `` `a${ ``, `}b${` and `` }c` ``.
+    Template,
+    /// A regular expression literal, including its flags.
+    Regex,
+    /// A character that does not start a valid token.
+    Error,
+}
+
+impl TokenKind {
+    /// This is true for tokens that the parser skips, ie. whitespace, line terminators,
+    /// comments and the hashbang.
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            TokenKind::Whitespace
+                | TokenKind::LineTerminator
+                | TokenKind::Comment(_)
+                | TokenKind::Hashbang
+        )
+    }
+}
+
+/// LexerState is what the lexer needs to know about the tokens before an offset to keep
+/// lexing from it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LexerState {
+    // true if a `/` would start a regular expression
+    regex_allowed: bool,
+    // the number of unclosed `{` in each template substitution that is open
+    braces: Vec<u32>,
+    // true if HTML-like comments are allowed, which is only in a Script
+    html_comments: bool,
+    // true if only whitespace and comments have been lexed since the start of the source
+    // text, the last line terminator or the last block comment with a line terminator in
+    // it, so `-->` would start an HTML-like comment
+    line_start: bool,
+}
+
+impl LexerState {
+    /// The state at the start of source text of the type. HTML-like comments, eg.
+    /// `<!-- hello`, are only lexed in a Script.
+    pub fn new(source_type: SourceType) -> LexerState {
+        LexerState {
+            regex_allowed: true,
+            braces: Vec::new(),
+            html_comments: source_type == SourceType::Script,
+            line_start: true,
+        }
+    }
+}
+
+impl Default for LexerState {
+    /// The state at the start of the source text of a Module.
+    fn default() -> LexerState {
+        LexerState::new(SourceType::Module)
+    }
+}
+
+/// Checkpoint is a position that a lexer can be rewound to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Checkpoint {
+    /// The offset of the next token.
+    pub offset: u32,
+    /// The state of the lexer at the offset.
+    pub state: LexerState,
+}
+
+/// Lexer is an iterator over the tokens of the source text.
+#[derive(Debug, Clone)]
+pub struct Lexer<'a> {
+    source: &'a str,
+    offset: usize,
+    state: LexerState,
+}
+
+/// The punctuators, longest first so the longest one that matches is found first.
+const PUNCTUATORS: &[&str] = &[
+    ">>>=", "...", "===", "!==", "**=", "<<=", ">>=", ">>>", "=>", "==", "!=", "<=", ">=", "&&",
+    "||", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<", ">>", "**", "{", "}",
+    "(", ")", "[", "]", ".", ";", ",", "<", ">", "+", "-", "*", "/", "%", "&", "|", "^", "!", "~",
+    "?", ":", "=",
+];
+
+/// The reserved words that end an expression, so a `/` after them is a division.
+const VALUE_KEYWORDS: &[&str] = &["this", "super", "null", "true", "false"];
+
+impl<'a> Lexer<'a> {
+    /// This creates a lexer for the whole source text. HTML-like comments are not lexed,
+    /// like in a Module.
+    pub fn new(source: &'a str) -> Lexer<'a> {
+        Lexer::relex_from(source, 0, LexerState::default())
+    }
+
+    /// This creates a lexer for the whole source text of the type.
+    pub fn with_source_type(source: &'a str, source_type: SourceType) -> Lexer<'a> {
+        Lexer::relex_from(source, 0, LexerState::new(source_type))
+    }
+
+    /// This creates a lexer that starts at the offset, with the state that a lexer had
+    /// there. The spans of the tokens are still offsets into the whole source text.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the offset is not on a character boundary of the source text.
+    pub fn relex_from(source: &'a str, offset: u32, state: LexerState) -> Lexer<'a> {
+        let offset = offset as usize;
+        assert!(
+            source.is_char_boundary(offset),
+            "offset {} is not a character boundary",
+            offset
+        );
+        Lexer {
+            source,
+            offset,
+            state,
+        }
+    }
+
+    /// This returns the position of the next token.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            offset: self.offset as u32,
+            state: self.state.clone(),
+        }
+    }
+
+    /// This goes back, or forward, to a checkpoint of a lexer for the same source text.
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        *self = Lexer::relex_from(self.source, checkpoint.offset, checkpoint.state);
+    }
+
+    /// This finds the kind and length of the next token, and updates the state for the
+    /// tokens after it. Trivia only changes whether the next token is at the start of a
+    /// line.
+    fn lex(&mut self, rest: &str) -> (TokenKind, usize) {
+        let c = rest.chars().next().unwrap();
+        let line_start = mem::replace(&mut self.state.line_start, false);
+        if self.offset == 0 && rest.starts_with("#!") {
+            if let Some(length) = length(hashbang(), rest) {
+                return (TokenKind::Hashbang, length);
+            }
+        }
+        if rest.starts_with("\r\n") {
+            self.state.line_start = true;
+            return (TokenKind::LineTerminator, 2);
+        }
+        if is_line_terminator(c) {
+            self.state.line_start = true;
+            return (TokenKind::LineTerminator, c.len_utf8());
+        }
+        if is_whitespace(c) {
+            self.state.line_start = line_start;
+            let length = rest.find(|c| !is_whitespace(c)).unwrap_or(rest.len());
+            return (TokenKind::Whitespace, length);
+        }
+        if rest.starts_with("//") || rest.starts_with("/*") {
+            if let Ok(((text, kind), remaining)) = comment_text().parse(rest) {
+                self.state.line_start = line_start || text.contains(is_line_terminator);
+                return (TokenKind::Comment(kind), rest.len() - remaining.len());
+            }
+        }
+        if self.state.html_comments {
+            if let Some(length) = length(html_open_comment(), rest) {
+                return (TokenKind::Comment(CommentKind::HtmlOpen), length);
+            }
+            if line_start && rest.starts_with("-->") {
+                let length = rest.find(is_line_terminator).unwrap_or(rest.len());
+                return (TokenKind::Comment(CommentKind::HtmlClose), length);
+            }
+        }
+        if c == '/' && self.state.regex_allowed {
+            if let Some(length) = length(regex_literal(), rest) {
+                self.state.regex_allowed = false;
+                return (TokenKind::Regex, length);
+            }
+        }
+        if c == '`' {
+            return self.template(rest, 1);
+        }
+        if c == '}' && self.state.braces.last() == Some(&0) {
+            self.state.braces.pop();
+            return self.template(rest, 1);
+        }
+        if c == '\'' || c == '"' {
+            if let Some(length) = length(string_literal(false), rest) {
+                self.state.regex_allowed = false;
+                return (TokenKind::String, length);
+            }
+        }
+        if c.is_ascii_digit() || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) {
+            if let Some(length) = length(numeric_literal(false), rest) {
+                self.state.regex_allowed = false;
+                return (TokenKind::Number, length);
+            }
+        }
+        if let Ok(((name, escaped), remaining)) = identifier_name().parse(rest) {
+            let kind = if !escaped && is_reserved_word(&name, false) {
+                TokenKind::Keyword
+            } else {
+                TokenKind::Identifier
+            };
+            self.state.regex_allowed =
+                kind == TokenKind::Keyword && !VALUE_KEYWORDS.contains(&&*name);
+            return (kind, rest.len() - remaining.len());
+        }
+        if let Some(punctuator) = PUNCTUATORS.iter().find(|p| rest.starts_with(*p)) {
+            match *punctuator {
+                "{" => {
+                    if let Some(braces) = self.state.braces.last_mut() {
+                        *braces += 1;
+                    }
+                }
+                "}" => {
+                    if let Some(braces) = self.state.braces.last_mut() {
+                        *braces -= 1;
+                    }
+                }
+                _ => {}
+            }
+            self.state.regex_allowed = !["}", ")", "]", "++", "--"].contains(punctuator);
+            return (TokenKind::Punctuator, punctuator.len());
+        }
+        self.state.regex_allowed = true;
+        (TokenKind::Error, c.len_utf8())
+    }
+
+    /// This lexes the text of a template after its first character, which is a `` ` ``
+    /// or the `}` that ends a substitution.
+    fn template(&mut self, rest: &str, start: usize) -> (TokenKind, usize) {
+        let body = &rest[start..];
+        let remaining = match many::<Vec<_>, _>(template_character()).parse(body) {
+            Ok((_, remaining)) => remaining,
+            Err(_) => body,
+        };
+        let end = rest.len() - remaining.len();
+        if remaining.starts_with("${") {
+            self.state.braces.push(0);
+            self.state.regex_allowed = true;
+            (TokenKind::Template, end + 2)
+        } else if remaining.starts_with('`') {
+            self.state.regex_allowed = false;
+            (TokenKind::Template, end + 1)
+        } else {
+            // an unterminated template, or an invalid escape sequence
+            self.state.regex_allowed = true;
+            (TokenKind::Error, end.max(1))
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        let source = self.source;
+        let rest = &source[self.offset..];
+        if rest.is_empty() {
+            return None;
+        }
+        let (kind, length) = self.lex(rest);
+        let start = self.offset;
+        self.offset += length;
+        Some(Token {
+            kind,
+            span: Span::new(start as u32, self.offset as u32),
+        })
+    }
+}
+
+/// The number of bytes that the parser consumes, if it succeeds.
+fn length<'a, P: Parser<Input = &'a str>>(mut parser: P, rest: &'a str) -> Option<usize> {
+    parser
+        .parse(rest)
+        .ok()
+        .map(|(_, remaining)| rest.len() - remaining.len())
+}
+
+#[cfg(test)]
+mod lexer_tests {
+    use super::*;
+
+    fn tokens(source: &str) -> Vec<(TokenKind, &str)> {
+        Lexer::new(source)
+            .filter(|token| token.kind != TokenKind::Whitespace)
+            .map(|token| (token.kind, &source[token.span.range()]))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokens() {
+        use self::TokenKind::*;
+        assert_eq!(
+            tokens("#!node\r\nif (a >>>= 0x1_0) return 'b' // c\n/* d */.5"),
+            vec![
+                (Hashbang, "#!node"),
+                (LineTerminator, "\r\n"),
+                (Keyword, "if"),
+                (Punctuator, "("),
+                (Identifier, "a"),
+                (Punctuator, ">>>="),
+                (Number, "0x1_0"),
+                (Punctuator, ")"),
+                (Keyword, "return"),
+                (String, "'b'"),
+                (Comment(CommentKind::Line), "// c"),
+                (LineTerminator, "\n"),
+                (Comment(CommentKind::Block), "/* d */"),
+                (Number, ".5"),
+            ]
+        );
+        // escaped keywords are identifiers, and bad characters are errors of their own
+        assert_eq!(
+            tokens(r"\u0069f # 'a"),
+            vec![
+                (Identifier, r"\u0069f"),
+                (Error, "#"),
+                (Error, "'"),
+                (Identifier, "a"),
+            ]
+        );
+        // U+0085 is not whitespace, but the rest of the Zs category is
+        assert_eq!(tokens("\u{85}\u{3000}"), vec![(Error, "\u{85}")]);
+    }
+
+    #[test]
+    fn test_html_comments() {
+        use self::TokenKind::*;
+        let source = "a <!-- b\n  --> c\nd --> e";
+        let kinds: Vec<_> = Lexer::with_source_type(source, SourceType::Script)
+            .filter(|token| token.kind != Whitespace)
+            .map(|token| (token.kind, &source[token.span.range()]))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (Identifier, "a"),
+                (Comment(CommentKind::HtmlOpen), "<!-- b"),
+                (LineTerminator, "\n"),
+                (Comment(CommentKind::HtmlClose), "--> c"),
+                (LineTerminator, "\n"),
+                (Identifier, "d"),
+                (Punctuator, "--"),
+                (Punctuator, ">"),
+                (Identifier, "e"),
+            ]
+        );
+        assert_eq!(tokens("<!--")[0], (Punctuator, "<"));
+
+        // the start of the source text and a block comment over many lines start a line
+        let close = |source| {
+            Lexer::with_source_type(source, SourceType::Script)
+                .any(|token| token.kind == Comment(CommentKind::HtmlClose))
+        };
+        assert!(close("--> a"));
+        assert!(close("/*\n*/ --> a"));
+        assert!(close("\n/* */ --> a"));
+        assert!(!close("a /* */ --> b"));
+        assert!(!close("/* */ a --> b"));
+    }
+
+    #[test]
+    fn test_regex() {
+        use self::TokenKind::*;
+        assert_eq!(
+            tokens("a / b / c"),
+            vec![
+                (Identifier, "a"),
+                (Punctuator, "/"),
+                (Identifier, "b"),
+                (Punctuator, "/"),
+                (Identifier, "c"),
+            ]
+        );
+        assert_eq!(
+            tokens("return /[/]/g.test(x) /* */ / 2"),
+            vec![
+                (Keyword, "return"),
+                (Regex, "/[/]/g"),
+                (Punctuator, "."),
+                (Identifier, "test"),
+                (Punctuator, "("),
+                (Identifier, "x"),
+                (Punctuator, ")"),
+                (Comment(CommentKind::Block), "/* */"),
+                (Punctuator, "/"),
+                (Number, "2"),
+            ]
+        );
+        assert_eq!(tokens("this /a/")[1], (Punctuator, "/"));
+    }
+
+    #[test]
+    fn test_template() {
+        use self::TokenKind::*;
+        assert_eq!(
+            tokens("`a${ {b: `${c}`} }d${e}f` / 2"),
+            vec![
+                (Template, "`a${"),
+                (Punctuator, "{"),
+                (Identifier, "b"),
+                (Punctuator, ":"),
+                (Template, "`${"),
+                (Identifier, "c"),
+                (Template, "}`"),
+                (Punctuator, "}"),
+                (Template, "}d${"),
+                (Identifier, "e"),
+                (Template, "}f`"),
+                (Punctuator, "/"),
+                (Number, "2"),
+            ]
+        );
+        assert_eq!(tokens("`a"), vec![(Error, "`a")]);
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let source = "x = `${ /a/ }` / 1;\ny = 2";
+        let all: Vec<_> = Lexer::new(source).collect();
+        let joined: String = all
+            .iter()
+            .map(|token| &source[token.span.range()])
+            .collect();
+        assert_eq!(joined, source);
+
+        let mut lexer = Lexer::new(source);
+        let mut checkpoints = Vec::new();
+        while lexer.next().is_some() {
+            checkpoints.push(lexer.checkpoint());
+        }
+        for (i, checkpoint) in checkpoints.iter().enumerate() {
+            let tail: Vec<_> =
+                Lexer::relex_from(source, checkpoint.offset, checkpoint.state.clone()).collect();
+            assert_eq!(&tail[..], &all[i + 1..]);
+        }
+
+        let mut lexer = Lexer::new(source);
+        let start = lexer.checkpoint();
+        let first: Vec<_> = lexer.by_ref().take(5).collect();
+        lexer.rewind(start);
+        assert_eq!(lexer.take(5).collect::<Vec<_>>(), first);
+    }
+}
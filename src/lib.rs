@@ -4,15 +4,53 @@
 //! It also provides some useful macros to help you construct the AST
 //! if you want to perform some operations on it.
 
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
+#[macro_use]
 extern crate combine;
 extern crate failure;
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "parallel")]
+extern crate rayon;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
 extern crate unicode_xid;
 
 #[macro_use]
 mod macros;
+#[cfg(feature = "arbitrary")]
+mod arbitrary_ast;
 pub mod ast;
+pub mod atom;
+pub mod batch;
+pub mod dump;
+pub mod dynamic;
+pub mod edit;
+pub mod eval;
+pub mod highlight;
+pub mod js_string;
+pub mod json;
+pub mod jsdoc;
+pub mod lexer;
+pub mod markdown;
 pub mod parser;
+pub mod position;
+pub mod read;
+pub mod sexp;
+pub mod source_map;
+pub mod source_type;
+pub mod span;
+pub mod stats;
+pub mod suggest;
+pub mod suspicious;
+pub mod template;
+#[cfg(feature = "test262")]
+pub mod test262;
 
-pub use parser::parse;
+pub use batch::parse_many;
+pub use parser::{
+    parse, parse_as, parse_expression, parse_template, try_parse, try_parse_as,
+    try_parse_embedded, try_parse_expression, try_parse_template, Origin, SyntaxError,
+};
+pub use read::parse_reader;
@@ -25,7 +25,6 @@
 /// `build_ast!(call build_ast!(...) [ build_ast!(...), build_ast!(...), build_ast!(...) ])`
 ///
 /// - we use {} to accept a rust expression
-
 #[macro_export]
 macro_rules! build_ast {
     ([$($many:tt)+]) => {
@@ -49,7 +48,7 @@ macro_rules! build_ast {
     };
     (templ_el {$cooked:expr} {$raw:expr}) => {
         TemplateElement {
-            cooked: $cooked,
+            cooked: Some($cooked.into()),
             raw: $raw,
         }
     };
@@ -58,7 +57,7 @@ macro_rules! build_ast {
         Expression::This
     };
     (id $id:expr) => {
-        Expression::IdReference($id)
+        Expression::IdReference($id.into())
     };
     (null) => {
         Expression::Literal(ExpressionLiteral::NullLiteral(NullLiteral))
@@ -70,10 +69,10 @@ macro_rules! build_ast {
         Expression::Literal(ExpressionLiteral::BooleanLiteral(false))
     };
     (num $lit:expr) => {
-        Expression::Literal(ExpressionLiteral::NumberLiteral($lit))
+        Expression::Literal(ExpressionLiteral::NumberLiteral($lit.into()))
     };
     (str $lit:expr) => {
-        Expression::Literal(ExpressionLiteral::StringLiteral($lit))
+        Expression::Literal(ExpressionLiteral::StringLiteral(Box::new($lit.into())))
     };
     (array [$($elements:tt),*]) => {
         Expression::ArrayLiteral(vec![$(build_ast!($elements)),*])
@@ -89,23 +88,23 @@ macro_rules! build_ast {
         }
     };
     (function [$($params:tt),+] {$body:expr}) => {
-        Expression::Function {
+        Expression::Function(Box::new(Function {
             id: None,
             params: vec![$(build_ast!($params)),+],
             body: $body,
             generator: false,
             async: false
-        }
+        }))
     };
     (...[$($expression:tt)+]) => {
         Expression::Spread(Box::new(build_ast!($($expression)+)))
     };
     // whole bunch of other stuff between
     (call [$($id:tt)+] [$($args:tt)+]) => {
-        Expression::Call {
-            callee: Box::new(build_ast!($($id)+)),
+        Expression::Call(Box::new(Call {
+            callee: build_ast!($($id)+),
             arguments: vec![$(build_ast!($args)),+]
-        }
+        }))
     };
     (yield) => {
         Expression::Yield {
@@ -115,11 +114,11 @@ macro_rules! build_ast {
     };
     // JSX
     (<$id:ident />) => {
-        Expression::JsxElement {
-            name: stringify!($id).to_string(),
+        Expression::JsxElement(Box::new(JsxElement {
+            name: stringify!($id).into(),
             attributes: Vec::new(),
             children: Vec::new()
-        }
+        }))
     };
         /*
     (var) => {
@@ -174,6 +173,45 @@ macro_rules! build_ast {
     */
 }
 
+/// This asserts that two syntax trees are the same, ignoring spans. It works for
+/// `Program` and `Expression` values. When they differ, the panic message has the path to
+/// the first node that differs, and both versions of that node, instead of the `Debug`
+/// output of both trees.
+///
+/// # Example
+/// ```should_panic
+/// # #[macro_use] extern crate ecmascript;
+/// # use ecmascript::ast::*;
+/// # fn main() {
+/// let expected = build_ast!(array [ [true], [null] ]);
+/// let actual = build_ast!(array [ [true], [false] ]);
+/// // panics with:
+/// // first difference at `elements[1]`
+/// // expected:
+/// // NullLiteral
+/// // actual:
+/// // BooleanLiteral false
+/// assert_ast_eq!(expected, actual);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_ast_eq {
+    ($expected:expr, $actual:expr) => {
+        if let Some(diff) = $expected.structural_diff(&$actual) {
+            panic!("assertion failed: syntax trees are not equal\n{}", diff);
+        }
+    };
+    ($expected:expr, $actual:expr, $($arg:tt)+) => {
+        if let Some(diff) = $expected.structural_diff(&$actual) {
+            panic!(
+                "assertion failed: syntax trees are not equal: {}\n{}",
+                format_args!($($arg)+),
+                diff
+            );
+        }
+    };
+}
+
 /*
 #[macro_export]
 macro_rules! match_ast {
@@ -0,0 +1,187 @@
+//! This module finds the JavaScript code blocks in a Markdown document and parses them,
+//! eg. to check the examples in a README.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::ast::SourceType;
+//! use ecmascript::markdown::parse_code_blocks;
+//!
+//! let document = "# Example\n\n```js\n// fine\n```\n\n```javascript\n  oops\n```\n";
+//! let results = parse_code_blocks(document, SourceType::Module);
+//! assert_eq!(results.len(), 2);
+//! assert!(results[0].1.is_ok());
+//! let error = results[1].1.as_ref().unwrap_err();
+//! assert_eq!((error.line, error.column), (8, 3));
+//! ```
+//!
+//! Only fenced code blocks are found, with either backticks or tildes, and only if the
+//! first word of their info string is `js` or `javascript`. A block that is never closed
+//! runs to the end of the document, like it does in CommonMark. CommonMark removes the
+//! indentation of the opening fence from every line of the block, but that is not done
+//! here, since it is only whitespace to the parser and the block stays a single slice of
+//! the document.
+//!
+//! [Reference](https://spec.commonmark.org/0.30/#fenced-code-blocks)
+
+use ast::{Program, SourceType};
+use parser::{try_parse_embedded, Origin, SyntaxError};
+use span::{Span, Spanned};
+
+/// CodeBlock is the content of a fenced code block, without the fences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeBlock<'a> {
+    /// The first word of the info string, eg. `js`.
+    pub language: &'a str,
+    /// The source text of the block.
+    pub source: &'a str,
+    /// Where the source text starts in the document.
+    pub origin: Origin,
+}
+
+impl<'a> CodeBlock<'a> {
+    /// This parses the block. The spans in the program and the position of a syntax
+    /// error are in the document.
+    pub fn parse(&self, source_type: SourceType) -> Result<Program, SyntaxError> {
+        try_parse_embedded(self.source, source_type, self.origin)
+    }
+}
+
+impl<'a> Spanned for CodeBlock<'a> {
+    fn span(&self) -> Span {
+        Span::new(
+            self.origin.offset,
+            self.origin.offset + self.source.len() as u32,
+        )
+    }
+}
+
+/// This finds the JavaScript code blocks in the document, in the order that they are
+/// written.
+pub fn code_blocks<'a>(document: &'a str) -> Vec<CodeBlock<'a>> {
+    let mut blocks = Vec::new();
+    // the fence, the language and the offset of the first line of the open block
+    let mut open: Option<(Fence, &str, usize)> = None;
+    let mut offset = 0;
+    for line in document.split_inclusive('\n') {
+        let next = offset + line.len();
+        match open {
+            None => {
+                open = opening_fence(line).map(|(fence, language)| (fence, language, next));
+            }
+            Some((fence, language, start)) => {
+                if fence.is_closed_by(line) {
+                    blocks.push(code_block(document, language, start, offset));
+                    open = None;
+                }
+            }
+        }
+        offset = next;
+    }
+    if let Some((_, language, start)) = open {
+        blocks.push(code_block(document, language, start, document.len()));
+    }
+    blocks.retain(|block| {
+        block.language.eq_ignore_ascii_case("js")
+            || block.language.eq_ignore_ascii_case("javascript")
+    });
+    blocks
+}
+
+/// This parses every JavaScript code block in the document as the source type.
+pub fn parse_code_blocks<'a>(
+    document: &'a str,
+    source_type: SourceType,
+) -> Vec<(CodeBlock<'a>, Result<Program, SyntaxError>)> {
+    code_blocks(document)
+        .into_iter()
+        .map(|block| (block, block.parse(source_type.clone())))
+        .collect()
+}
+
+fn code_block<'a>(document: &'a str, language: &'a str, start: usize, end: usize) -> CodeBlock<'a> {
+    CodeBlock {
+        language,
+        source: &document[start..end],
+        origin: Origin::in_document(document, start as u32),
+    }
+}
+
+/// Fence is the character and the length of an opening fence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fence {
+    character: char,
+    length: usize,
+}
+
+impl Fence {
+    // A closing fence is at least as long as the opening one, and can only be followed by
+    // whitespace.
+    fn is_closed_by(self, line: &str) -> bool {
+        match strip_indentation(line.trim_end()) {
+            Some(rest) => rest.len() >= self.length && rest.chars().all(|c| c == self.character),
+            None => false,
+        }
+    }
+}
+
+// The fence and the first word of the info string, if the line opens a code block.
+fn opening_fence(line: &str) -> Option<(Fence, &str)> {
+    let rest = strip_indentation(line.trim_end())?;
+    let character = rest.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let length = rest.len() - rest.trim_start_matches(character).len();
+    let info = &rest[length..];
+    // the info string of a backtick fence can not have backticks, so inline code like
+    // ```a``` is not a fence
+    if length < 3 || (character == '`' && info.contains('`')) {
+        return None;
+    }
+    let language = info.split_whitespace().next().unwrap_or("");
+    Some((Fence { character, length }, language))
+}
+
+// Fences can be indented by up to three spaces, and more makes them an indented code
+// block instead.
+fn strip_indentation(line: &str) -> Option<&str> {
+    let rest = line.trim_start_matches(' ');
+    if line.len() - rest.len() <= 3 {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod markdown_tests {
+    use super::*;
+    use span::source_text;
+
+    #[test]
+    fn test_code_blocks() {
+        let document = concat!(
+            "Some `code`.\n",
+            "```JS title=\"a.js\"\r\n// a\r\n```\r\n",
+            "```rust\nfn main() {}\n```\n",
+            "~~~~javascript\n~~~\n// b\n  ~~~~~ \n",
+            "```js\n```\n",
+            // indented by four spaces, so it is not a fence
+            "    ```js\n---\n",
+            "```js\n// unclosed",
+        );
+        let blocks = code_blocks(document);
+        let sources: Vec<_> = blocks.iter().map(|block| block.source).collect();
+        assert_eq!(sources, vec!["// a\r\n", "~~~\n// b\n", "", "// unclosed"]);
+        assert_eq!(blocks[0].language, "JS");
+        assert_eq!(blocks[1].origin, Origin::in_document(document, 84));
+        assert_eq!(source_text(&blocks[1], document), "~~~\n// b\n");
+    }
+
+    #[test]
+    fn test_parse_code_blocks() {
+        let document = "é\n```js\n/* a */\n```\n\n```js\n#!node\n a\n```\n";
+        let results = parse_code_blocks(document, SourceType::Module);
+        let program = results[0].1.as_ref().unwrap();
+        assert_eq!(&document[program.comments[0].span.range()], "/* a */");
+        let error = results[1].1.as_ref().unwrap_err();
+        assert_eq!((error.line, error.column), (8, 2));
+    }
+}
@@ -2,20 +2,27 @@
 //! a constructed Abstract Syntax Tree. The AST types are documented in the ast module.
 //!
 //! This parser uses the error type from failure to make error interop easier for users.
+//! Syntax errors can be downcast to a `SyntaxError` to get the position of the error.
 
 use ast::*;
+use combine::easy;
 use combine::error::ParseError;
 use combine::parser::char::{char, crlf, digit, hex_digit, newline, spaces, string};
 use combine::parser::choice::{choice, optional};
 use combine::parser::combinator::{not_followed_by, try};
 use combine::parser::error::unexpected;
-use combine::parser::item::{none_of, one_of, satisfy, token, value};
-use combine::parser::repeat::{count, many, many1, skip_until};
+use combine::parser::item::{any, none_of, one_of, satisfy, token, value};
+use combine::parser::repeat::{count, many, many1, skip_many, skip_until};
 use combine::parser::sequence::between;
-use combine::stream::state::State;
+use combine::stream::state::{Positioner, SourcePosition, State};
 use combine::{eof, Parser, Stream};
-use failure::{self, Error};
+use failure::{Error, Fail};
+use span::Span;
+use std::cell::Cell;
 use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
+use suggest;
 use unicode_xid::UnicodeXID;
 
 // https://www.ecma-international.org/ecma-262/9.0/index.html#sec-ecmascript-language-lexical-grammar
@@ -40,22 +47,55 @@ where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
+    // crlf has to be tried before a lone carriage return, or the line feed is left over
     newline()
+        .or(try(crlf()))
         .or(char('\u{000D}'))
         .or(char('\u{2028}'))
         .or(char('\u{2029}'))
-        .or(crlf())
         .map(|_| ())
 }
 
+pub(crate) fn is_line_terminator(c: char) -> bool {
+    ['\n', '\r', '\u{2028}', '\u{2029}'].contains(&c)
+}
+
+// https://www.ecma-international.org/ecma-262/9.0/index.html#sec-white-space
+// `char::is_whitespace` is not the same set, since it has the line terminators and
+// U+0085 (next line), which is not whitespace in ECMAScript.
+pub(crate) fn is_whitespace(c: char) -> bool {
+    match c {
+        '\t' | '\u{B}' | '\u{C}' | ' ' | '\u{A0}' | '\u{FEFF}' => true,
+        // the rest of the Zs category
+        '\u{1680}' | '\u{2000}'..='\u{200A}' | '\u{202F}' | '\u{205F}' | '\u{3000}' => true,
+        _ => false,
+    }
+}
+
 // https://www.ecma-international.org/ecma-262/9.0/index.html#sec-comments
+// HTML-like comments are only allowed when `html` is true, which is for Script source
+// with the Annex B extensions. They are never allowed in a Module.
 #[allow(dead_code)]
-fn comment<I>() -> impl Parser<Input = I, Output = ()>
+fn comment<I>(html: bool) -> impl Parser<Input = I, Output = ()>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    try(block_comment()).or(line_comment())
+    let comment = try(block_comment()).or(line_comment());
+    if html {
+        let open = html_open_comment().with(optional(line_terminator()));
+        let close = (
+            line_terminator(),
+            skip_many(satisfy(is_whitespace)),
+            html_close_comment(|| true),
+        ).with(optional(line_terminator()));
+        try(comment)
+            .or(try(open.map(|_| ())))
+            .or(close.map(|_| ()))
+            .left()
+    } else {
+        comment.right()
+    }
 }
 
 #[allow(dead_code)]
@@ -83,13 +123,69 @@ where
     ).map(|_| ())
 }
 
+// https://www.ecma-international.org/ecma-262/9.0/index.html#sec-html-like-comments
+// `<!--` starts a comment that runs to the end of the line, like `//`. This returns the
+// text after it, and like `comment_text`, the comment can end the source text.
+pub(crate) fn html_open_comment<I>() -> impl Parser<Input = I, Output = String>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    string("<!--").with(many(satisfy(|c| !is_line_terminator(c))))
+}
+
+// `-->` is only a comment at the start of a line, where it can only follow whitespace and
+// comments that do not end the line. A block comment with a line terminator in it starts a
+// new line as well, eg. `/*\n*/ -->`, and so does the start of the source text. Anywhere
+// else it is the `--` and `>` operators, eg. `a --> b`. The whitespace in front
+// is parsed by the caller, which keeps track of whether it is at the start of a line, so
+// this only has to backtrack over the `-->` itself. This returns the text after `-->`.
+fn html_close_comment<I, F>(at_line_start: F) -> impl Parser<Input = I, Output = String>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    F: Fn() -> bool,
+{
+    try((satisfy(move |c| c == '-' && at_line_start()), string("->")))
+        .with(many(satisfy(|c| !is_line_terminator(c))))
+}
+
+// This is a comment that is kept, with the text between its delimiters and its kind.
+// Unlike `line_comment`, a line comment can end the source text.
+#[allow(dead_code)]
+pub(crate) fn comment_text<I>() -> impl Parser<Input = I, Output = (String, CommentKind)>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let block = string("/*")
+        .with(many(not_followed_by(try(string("*/"))).with(any())))
+        .skip(string("*/"))
+        .map(|text| (text, CommentKind::Block));
+    let line = string("//")
+        .with(many(satisfy(|c| !is_line_terminator(c))))
+        .map(|text| (text, CommentKind::Line));
+    try(block).or(line)
+}
+
+// https://github.com/tc39/proposal-hashbang
+// The hashbang is only allowed at the very start of the source text.
+#[allow(dead_code)]
+pub(crate) fn hashbang<I>() -> impl Parser<Input = I, Output = String>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    string("#!").with(many(satisfy(|c| !is_line_terminator(c))))
+}
+
 #[allow(dead_code)]
 fn skip_tokens<I>() -> impl Parser<Input = I, Output = ()>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    ws().or(comment())
+    ws().or(comment(false))
 }
 
 // https://www.ecma-international.org/ecma-262/9.0/index.html#sec-names-and-keywords
@@ -107,23 +203,23 @@ where
     satisfy(satisfy_id_start)
 }
 
+// The flag is true if the character was written as a unicode escape.
 #[allow(dead_code)]
-fn unicode_id_start<I>() -> impl Parser<Input = I, Output = char>
+fn unicode_id_start<I>() -> impl Parser<Input = I, Output = (char, bool)>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    try(unicode_escape_sequence().map(|x| x.0).then(|c| {
-        if satisfy_id_start(c) {
-            value(c).left()
-        } else {
-            unexpected(c).map(|_| ' ').right()
+    try(unicode_escape_sequence().map(|x| x.0).then(|code_point| {
+        // lone surrogates are never part of an identifier
+        match ::std::char::from_u32(code_point) {
+            Some(c) if satisfy_id_start(c) => value((c, true)).left(),
+            _ => unexpected("escape sequence").map(|_| (' ', true)).right(),
         }
-    })).or(id_start())
+    })).or(id_start().map(|c| (c, false)))
 }
 
-#[allow(dead_code)]
-fn satisfy_id_continue(c: char) -> bool {
+pub(crate) fn satisfy_id_continue(c: char) -> bool {
     // 200c = ZWNJ, 200d = ZWJ
     UnicodeXID::is_xid_continue(c) || c == '\u{200C}' || c == '\u{200D}' || c == '$' || c == '_'
 }
@@ -137,51 +233,87 @@ where
     satisfy(satisfy_id_continue)
 }
 
+// The flag is true if the character was written as a unicode escape.
 #[allow(dead_code)]
-fn unicode_id_continue<I>() -> impl Parser<Input = I, Output = char>
+fn unicode_id_continue<I>() -> impl Parser<Input = I, Output = (char, bool)>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    try(unicode_escape_sequence().map(|x| x.0).then(|c| {
-        if satisfy_id_continue(c) {
-            value(c).left()
-        } else {
-            unexpected(c).map(|_| ' ').right()
+    try(unicode_escape_sequence().map(|x| x.0).then(|code_point| {
+        // lone surrogates are never part of an identifier
+        match ::std::char::from_u32(code_point) {
+            Some(c) if satisfy_id_continue(c) => value((c, true)).left(),
+            _ => unexpected("escape sequence").map(|_| (' ', true)).right(),
         }
-    })).or(id_continue())
+    })).or(id_continue().map(|c| (c, false)))
 }
 
-// TODO strict mode
+// https://www.ecma-international.org/ecma-262/9.0/index.html#prod-IdentifierName
+// The flag is true if any character was written as a unicode escape. An escaped name is
+// never a keyword, eg. `\u0069f` is not `if`, and it can not be an identifier either if
+// it spells a reserved word.
 #[allow(dead_code)]
-fn identifier<I>() -> impl Parser<Input = I, Output = String>
+pub(crate) fn identifier_name<I>() -> impl Parser<Input = I, Output = (String, bool)>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    (unicode_id_start(), many(unicode_id_continue()))
-        .map(|(s, c): (char, String)| s.to_string() + &c)
-        .then(|id| {
-            if KEYWORDS.contains::<str>(&id)
-                || FUTURE_RESERVED_WORDS.contains::<str>(&id)
-                || FUTURE_RESERVED_WORDS_STRICT.contains::<str>(&id)
-                || id == "null"
-                || id == "true"
-                || id == "false"
-            {
-                unexpected("reserved word")
-                    .map(|_| String::new())
-                    .message("reserved word")
-                    .right()
+    (unicode_id_start(), many::<Vec<_>, _>(unicode_id_continue())).map(|(start, rest)| {
+        let escaped = start.1 || rest.iter().any(|x| x.1);
+        let name = Some(start.0)
+            .into_iter()
+            .chain(rest.into_iter().map(|x| x.0))
+            .collect();
+        (name, escaped)
+    })
+}
+
+// Words like `public` are only reserved in strict mode.
+#[allow(dead_code)]
+fn identifier<I>(strict: bool) -> impl Parser<Input = I, Output = String>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    identifier_name().then(move |(id, escaped)| {
+        if is_reserved_word(&id, strict) {
+            let message = if FUTURE_RESERVED_WORDS.contains(&*id) {
+                "`enum` is reserved for future use"
+            } else if FUTURE_RESERVED_WORDS_STRICT.contains(&*id) {
+                "This word is reserved in strict mode"
+            } else if STRICT_MODE_RESTRICTED_WORDS.contains(&*id) {
+                "`let`, `static` and `yield` can not be names in strict mode"
+            } else if escaped {
+                "Keywords can not contain unicode escapes"
             } else {
-                value(id).left()
-            }
-        })
+                "reserved word"
+            };
+            unexpected("reserved word")
+                .map(|_| String::new())
+                .message(message)
+                .right()
+        } else {
+            value(id).left()
+        }
+    })
+}
+
+/// This returns true if the name can not be used as an identifier. Strict mode code
+/// reserves a few more words, eg. `public`, and does not allow `let`, `static` and `yield`.
+pub(crate) fn is_reserved_word(id: &str, strict: bool) -> bool {
+    KEYWORDS.contains(id)
+        || FUTURE_RESERVED_WORDS.contains(id)
+        || (strict && FUTURE_RESERVED_WORDS_STRICT.contains(id))
+        || (strict && STRICT_MODE_RESTRICTED_WORDS.contains(id))
+        || id == "null"
+        || id == "true"
+        || id == "false"
 }
 
 // https://www.ecma-international.org/ecma-262/9.0/index.html#sec-reserved-words
 lazy_static! {
-    static ref KEYWORDS: HashSet<&'static str> = {
+    pub(crate) static ref KEYWORDS: HashSet<&'static str> = {
         [
             "await",
             "break",
@@ -216,13 +348,12 @@ lazy_static! {
             "void",
             "while",
             "with",
-            "yield",
         ].iter()
             .cloned()
             .collect()
     };
     static ref FUTURE_RESERVED_WORDS: HashSet<&'static str> =
-        { ["enum"].iter().cloned().collect() };
+        ["enum"].iter().cloned().collect();
     static ref FUTURE_RESERVED_WORDS_STRICT: HashSet<&'static str> = {
         [
             "implements",
@@ -235,6 +366,12 @@ lazy_static! {
             .cloned()
             .collect()
     };
+    // These are not reserved words, but they can not be identifiers in strict mode code.
+    // `let` and `static` are contextual keywords, and `yield` is also reserved in
+    // generators, which are not supported yet.
+    // https://www.ecma-international.org/ecma-262/9.0/index.html#sec-identifiers-static-semantics-early-errors
+    static ref STRICT_MODE_RESTRICTED_WORDS: HashSet<&'static str> =
+        ["let", "static", "yield"].iter().cloned().collect();
 }
 
 #[cfg(test)]
@@ -243,37 +380,50 @@ mod lexical_tests {
 
     #[test]
     fn test_line_comment() {
-        assert_eq!(comment().parse("//\n"), Ok(((), "")));
-        assert_eq!(comment().parse("// hello\n"), Ok(((), "")));
+        assert_eq!(comment(false).parse("//\n"), Ok(((), "")));
+        assert_eq!(comment(false).parse("// hello\n"), Ok(((), "")));
     }
 
     #[test]
     fn test_block_comment() {
-        assert_eq!(comment().parse("/**/"), Ok(((), "")));
-        assert_eq!(comment().parse("/* * */"), Ok(((), "")));
-        assert_eq!(comment().parse("/** * **/"), Ok(((), "")));
-        assert_eq!(comment().parse("/* hello *\n\t */"), Ok(((), "")));
+        assert_eq!(comment(false).parse("/**/"), Ok(((), "")));
+        assert_eq!(comment(false).parse("/* * */"), Ok(((), "")));
+        assert_eq!(comment(false).parse("/** * **/"), Ok(((), "")));
+        assert_eq!(comment(false).parse("/* hello *\n\t */"), Ok(((), "")));
+    }
+
+    #[test]
+    fn test_html_comment() {
+        assert_eq!(comment(true).parse("<!-- hello\na"), Ok(((), "a")));
+        assert_eq!(comment(true).parse("\n  --> hello\na"), Ok(((), "a")));
+        assert_eq!(comment(true).parse("\r\n-->\n"), Ok(((), "")));
+        assert_eq!(comment(true).parse("<!-- hello"), Ok(((), "")));
+        // not at the start of a line
+        assert!(comment(true).parse("x -->\n").is_err());
+        // modules never have html comments
+        assert!(comment(false).parse("<!-- hello\n").is_err());
+        assert!(comment(false).parse("\n--> hello\n").is_err());
     }
 
     #[test]
     fn test_identifier() {
         // making sure that the unicode_escape_sequence satisifies things
         // eg. ZWNJ and ZWJ are not allowed as starts
-        assert!(identifier().parse(r"\u000a").is_err());
-        assert!(identifier().parse(r"\u200d").is_err());
-        assert!(identifier().parse(r"\u200c").is_err());
+        assert!(identifier(true).parse(r"\u000a").is_err());
+        assert!(identifier(true).parse(r"\u200d").is_err());
+        assert!(identifier(true).parse(r"\u200c").is_err());
         // testing $, _, unicode_escape_sequence as start
-        assert_eq!(identifier().parse(r"\u24"), Ok(("$".to_string(), "")));
-        assert_eq!(identifier().parse(r"_"), Ok(("_".to_string(), "")));
+        assert_eq!(identifier(true).parse(r"\u24"), Ok(("$".to_string(), "")));
+        assert_eq!(identifier(true).parse(r"_"), Ok(("_".to_string(), "")));
         // testing $, _, ZWNJ, ZWJ, unicode_escape_sequence as continue
-        assert_eq!(identifier().parse(r"a_"), Ok(("a_".to_string(), "")));
-        assert_eq!(identifier().parse(r"a$"), Ok(("a$".to_string(), "")));
+        assert_eq!(identifier(true).parse(r"a_"), Ok(("a_".to_string(), "")));
+        assert_eq!(identifier(true).parse(r"a$"), Ok(("a$".to_string(), "")));
         assert_eq!(
-            identifier().parse(r"_\u200d"),
+            identifier(true).parse(r"_\u200d"),
             Ok(("_\u{200d}".to_string(), ""))
         );
         assert_eq!(
-            identifier().parse(r"_\u200c"),
+            identifier(true).parse(r"_\u200c"),
             Ok(("_\u{200c}".to_string(), ""))
         );
     }
@@ -281,19 +431,66 @@ mod lexical_tests {
     #[test]
     fn test_identifier_reserved_word() {
         for &keyword in KEYWORDS.iter() {
-            assert!(identifier().parse(keyword).is_err());
+            assert!(identifier(true).parse(keyword).is_err());
         }
         for &keyword in FUTURE_RESERVED_WORDS.iter() {
-            assert!(identifier().parse(keyword).is_err());
+            assert!(identifier(true).parse(keyword).is_err());
         }
-        for &keyword in FUTURE_RESERVED_WORDS_STRICT.iter() {
-            assert!(identifier().parse(keyword).is_err());
+        for &keyword in FUTURE_RESERVED_WORDS_STRICT
+            .iter()
+            .chain(STRICT_MODE_RESTRICTED_WORDS.iter())
+        {
+            assert!(identifier(true).parse(keyword).is_err());
+            assert_eq!(identifier(false).parse(keyword), Ok((keyword.to_string(), "")));
         }
         // null literal
-        assert!(identifier().parse("null").is_err());
+        assert!(identifier(true).parse("null").is_err());
+        assert!(identifier(true).parse("nul\\u006c").is_err());
         // boolean literal
-        assert!(identifier().parse("true").is_err());
-        assert!(identifier().parse("false").is_err());
+        assert!(identifier(true).parse("true").is_err());
+        assert!(identifier(true).parse("false").is_err());
+    }
+
+    #[test]
+    fn test_reserved_word_messages() {
+        let message = |strict, source| {
+            let error = identifier(strict).easy_parse(State::new(source)).unwrap_err();
+            SyntaxError::from(error).message
+        };
+        assert!(message(false, "enum").contains("`enum` is reserved for future use"));
+        assert!(message(true, "public").contains("This word is reserved in strict mode"));
+        assert!(message(false, "if").contains("reserved word"));
+        assert_eq!(identifier(false).parse(r"st\u0061tic"), Ok(("static".to_string(), "")));
+        let restricted = "`let`, `static` and `yield` can not be names in strict mode";
+        assert!(message(true, r"st\u0061tic").contains(restricted));
+        // `var let` and `var yield` are fine in sloppy code
+        for word in &["let", "static", "yield"] {
+            assert_eq!(identifier(false).parse(*word), Ok((word.to_string(), "")));
+            assert!(message(true, word).contains(restricted));
+        }
+    }
+
+    #[test]
+    fn test_escaped_keyword() {
+        assert_eq!(identifier_name().parse("if"), Ok((("if".to_string(), false), "")));
+        assert_eq!(
+            identifier_name().parse(r"\u0069f"),
+            Ok((("if".to_string(), true), ""))
+        );
+        assert_eq!(
+            identifier_name().parse(r"a\u{62}"),
+            Ok((("ab".to_string(), true), ""))
+        );
+        for source in &[r"\u0069f", r"th\u0069s", r"\u{74}rue", r"v\u0061r"] {
+            let error = identifier(true).easy_parse(State::new(*source)).unwrap_err();
+            assert!(SyntaxError::from(error)
+                .message
+                .contains("Keywords can not contain unicode escapes"));
+        }
+        // escapes are fine in names that are not reserved
+        assert_eq!(identifier(true).parse(r"\u0069fs"), Ok(("ifs".to_string(), "")));
+        // and an escaped keyword is never the keyword itself
+        assert!(primary_expression(true).parse(r"\u0074his").is_err());
     }
 }
 
@@ -321,8 +518,9 @@ where
 }
 
 // https://www.ecma-international.org/ecma-262/9.0/index.html#sec-literals-numeric-literals
+// Legacy octal literals are only allowed in sloppy mode.
 #[allow(dead_code)]
-fn numeric_literal<I>() -> impl Parser<Input = I, Output = NumberLiteral>
+pub(crate) fn numeric_literal<I>(strict: bool) -> impl Parser<Input = I, Output = NumberLiteral>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
@@ -331,6 +529,7 @@ where
         try(binary_integer_literal()),
         try(octal_integer_literal()),
         try(hex_integer_literal()),
+        legacy_octal_like_integer_literal(strict),
         decimal_literal(),
     ))
 }
@@ -343,20 +542,38 @@ where
 {
     (
         optional(decimal_integer_literal()),
-        optional(
-            (token('.'), many::<String, _>(digit()))
-                .map(|(c, s): (char, String)| c.to_string() + &s),
-        ),
+        optional(fraction_part()),
         optional(exponent_part()),
     ).then(|tuple| match tuple {
             (None, None, None) => unexpected("empty").map(|_| String::new()).left(),
             (literal_opt, digits_opt, exponent_opt) => value(
-                literal_opt.unwrap_or_else(String::new)
-                    + &digits_opt.unwrap_or_else(String::new)
-                    + &exponent_opt.unwrap_or_else(String::new),
+                literal_opt.unwrap_or_default()
+                    + &digits_opt.unwrap_or_default()
+                    + &exponent_opt.unwrap_or_default(),
             ).right(),
         })
-        .map(|s| s.parse::<f64>().unwrap())
+        .then(|raw: String| match raw.replace('_', "").parse::<f64>() {
+            Ok(number) => value(NumberLiteral {
+                value: number,
+                kind: NumberKind::Decimal,
+                raw: Some(raw.into()),
+            }).left(),
+            // eg. `.` or `.e1` on their own
+            Err(_) => unexpected("number")
+                .map(|_| NumberLiteral::from(0f64))
+                .message("Expected digits in number")
+                .right(),
+        })
+}
+
+#[allow(dead_code)]
+fn fraction_part<I>() -> impl Parser<Input = I, Output = String>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    (token('.'), optional(separated_digits(10)))
+        .map(|(c, s): (char, Option<String>)| c.to_string() + &s.unwrap_or_default())
 }
 
 #[allow(dead_code)]
@@ -366,8 +583,11 @@ where
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
     choice((
-        string("0").skip(not_followed_by(digit())).map(String::from),
-        (one_of("123456789".chars()), many::<String, _>(digit()))
+        // a separator is not allowed after a leading zero, eg. `0_1`
+        string("0")
+            .skip(not_followed_by(digit().or(token('_'))))
+            .map(String::from),
+        (one_of("123456789".chars()), more_digits(10))
             .map(|(c, s): (char, String)| c.to_string() + &s),
     ))
 }
@@ -381,7 +601,7 @@ where
     (
         token('e').or(token('E')),
         optional(token('-').or(token('+'))),
-        many1::<String, _>(digit()),
+        separated_digits(10),
     ).map(
         |(e, sign_opt, digits): (char, Option<char>, String)| match sign_opt {
             Some(sign) => e.to_string() + &sign.to_string() + &digits,
@@ -399,8 +619,8 @@ where
     (
         token('0'),
         token('b').or(token('B')),
-        many1::<String, _>(one_of("01".chars())),
-    ).map(|(_, _, digits)| i64::from_str_radix(&digits, 2).unwrap() as f64)
+        separated_digits(2),
+    ).map(|(_, b, digits)| radix_literal(b, digits, NumberKind::Binary))
 }
 
 #[allow(dead_code)]
@@ -412,8 +632,8 @@ where
     (
         token('0'),
         token('o').or(token('O')),
-        many1::<String, _>(one_of("01234567".chars())),
-    ).map(|(_, _, digits)| i64::from_str_radix(&digits, 8).unwrap() as f64)
+        separated_digits(8),
+    ).map(|(_, o, digits)| radix_literal(o, digits, NumberKind::Octal))
 }
 
 #[allow(dead_code)]
@@ -425,22 +645,135 @@ where
     (
         token('0'),
         token('x').or(token('X')),
-        many1::<String, _>(hex_digit()),
-    ).map(|(_, _, digits)| i64::from_str_radix(&digits, 16).unwrap() as f64)
+        separated_digits(16),
+    ).map(|(_, x, digits)| radix_literal(x, digits, NumberKind::Hex))
+}
+
+// The literal for the digits after a prefix like `0x`, which are written as they are in
+// the raw text, so they can have separators.
+fn radix_literal(prefix: char, digits: String, kind: NumberKind) -> NumberLiteral {
+    NumberLiteral {
+        value: integer_value(&digits.replace('_', ""), kind.radix()),
+        kind,
+        raw: Some(format!("0{}{}", prefix, digits).into()),
+    }
+}
+
+// https://tc39.es/ecma262/#prod-NumericLiteralSeparator
+// Digits can be separated by single underscores, eg. `1_000`. The separators are kept in
+// the returned string, so they have to be removed before it is converted with
+// `integer_value` or `str::parse`.
+#[allow(dead_code)]
+fn separated_digits<I>(radix: u32) -> impl Parser<Input = I, Output = String>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    (radix_digit(radix), more_digits(radix)).map(|(c, s): (char, String)| c.to_string() + &s)
+}
+
+// The digits after the first one. A separator has to be followed by a digit, which rules
+// out separators at the end, eg. `1_`, and more than one in a row, eg. `1__0`.
+#[allow(dead_code)]
+fn more_digits<I>(radix: u32) -> impl Parser<Input = I, Output = String>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let separated = token('_')
+        .with(radix_digit(radix).message("Numeric separators are only allowed between digits"))
+        .map(|c: char| format!("_{}", c));
+    many(separated.or(radix_digit(radix).map(|c: char| c.to_string())))
+        .map(|digits: Vec<String>| digits.concat())
+}
+
+#[allow(dead_code)]
+fn radix_digit<I>(radix: u32) -> impl Parser<Input = I, Output = char>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    satisfy(move |c: char| c.is_digit(radix))
+}
+
+// https://www.ecma-international.org/ecma-262/9.0/index.html#sec-additional-syntax-numeric-literals
+// This is a LegacyOctalIntegerLiteral, eg. `0777`, or a NonOctalDecimalIntegerLiteral,
+// eg. `089`, which is decimal because it has an 8 or a 9 in it. Like any other decimal
+// integer, it can have a fraction and an exponent, eg. `08.5` or `09e1`.
+#[allow(dead_code)]
+fn legacy_octal_like_integer_literal<I>(
+    strict: bool,
+) -> impl Parser<Input = I, Output = NumberLiteral>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let leading_zero = try((token('0'), many1::<String, _>(digit())));
+    if strict {
+        return leading_zero
+            .then(|_| {
+                unexpected("leading zero")
+                    .map(|_| NumberLiteral::from(0f64))
+                    .message("Legacy octal literals are not allowed in strict mode")
+            })
+            .right();
+    }
+    leading_zero
+        .then(|(_, digits): (char, String)| {
+            if digits.contains(['8', '9']) {
+                (optional(fraction_part()), optional(exponent_part()))
+                    .map(move |(fraction, exponent)| {
+                        let raw = format!(
+                            "0{}{}{}",
+                            digits,
+                            fraction.unwrap_or_default(),
+                            exponent.unwrap_or_default()
+                        );
+                        NumberLiteral {
+                            // the digits have been validated, so this is always a number
+                            value: raw.replace('_', "").parse().unwrap(),
+                            kind: NumberKind::Decimal,
+                            raw: Some(raw.into()),
+                        }
+                    })
+                    .left()
+            } else {
+                value(NumberLiteral {
+                    value: integer_value(&digits, 8),
+                    kind: NumberKind::LegacyOctal,
+                    raw: Some(format!("0{}", digits).into()),
+                })
+                .right()
+            }
+        })
+        .left()
+}
+
+/// This converts digits that have already been validated into a number. Literals that
+/// are too large for a u64 can not be represented exactly anyway, so they are
+/// accumulated as an f64 instead of overflowing.
+pub(crate) fn integer_value(digits: &str, radix: u32) -> f64 {
+    match u64::from_str_radix(digits, radix) {
+        Ok(n) => n as f64,
+        Err(_) => digits
+            .chars()
+            .filter_map(|c| c.to_digit(radix))
+            .fold(0f64, |n, digit| n * f64::from(radix) + f64::from(digit)),
+    }
 }
 
 // https://www.ecma-international.org/ecma-262/9.0/index.html#sec-literals-string-literals
 #[allow(dead_code)]
-fn string_literal<I>() -> impl Parser<Input = I, Output = String>
+pub(crate) fn string_literal<I>(strict: bool) -> impl Parser<Input = I, Output = StringLiteral>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    try(double_quote_string()).or(single_quote_string())
+    try(double_quote_string(strict)).or(single_quote_string(strict))
 }
 
 #[allow(dead_code)]
-fn double_quote_string<I>() -> impl Parser<Input = I, Output = String>
+fn double_quote_string<I>(strict: bool) -> impl Parser<Input = I, Output = StringLiteral>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
@@ -448,24 +781,24 @@ where
     between(
         token('"'),
         token('"'),
-        many(double_quote_string_character()),
-    )
+        many::<Vec<_>, _>(double_quote_string_character(strict)),
+    ).map(|characters| quoted_string('"', characters))
 }
 
 #[allow(dead_code)]
-fn double_quote_string_character<I>() -> impl Parser<Input = I, Output = char>
+fn double_quote_string_character<I>(strict: bool) -> impl Parser<Input = I, Output = (u32, String)>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    // U+005C (REVERSE SOLIDUS), U+000D (CARRIAGE RETURN), U+2028 (LINE SEPARATOR), U+2029 (PARAGRAPH SEPARATOR), and U+000A (LINE FEED)
-    escape_sequence().map(|x| x.0).or(none_of(
-        "\u{005c}\u{000D}\u{2028}\u{2029}\u{000A}\"".chars(),
-    ))
+    // U+005C (REVERSE SOLIDUS), U+000D (CARRIAGE RETURN), and U+000A (LINE FEED)
+    // U+2028 (LINE SEPARATOR) and U+2029 (PARAGRAPH SEPARATOR) are allowed since ES2019
+    escape_sequence(strict)
+        .or(none_of("\u{005c}\u{000D}\u{000A}\"".chars()).map(|c: char| (u32::from(c), c.to_string())))
 }
 
 #[allow(dead_code)]
-fn single_quote_string<I>() -> impl Parser<Input = I, Output = String>
+fn single_quote_string<I>(strict: bool) -> impl Parser<Input = I, Output = StringLiteral>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
@@ -473,36 +806,56 @@ where
     between(
         token('\''),
         token('\''),
-        many(single_quote_string_character()),
-    )
+        many::<Vec<_>, _>(single_quote_string_character(strict)),
+    ).map(|characters| quoted_string('\'', characters))
 }
 
 #[allow(dead_code)]
-fn single_quote_string_character<I>() -> impl Parser<Input = I, Output = char>
+fn single_quote_string_character<I>(strict: bool) -> impl Parser<Input = I, Output = (u32, String)>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    // U+005C (REVERSE SOLIDUS), U+000D (CARRIAGE RETURN), U+2028 (LINE SEPARATOR), U+2029 (PARAGRAPH SEPARATOR), and U+000A (LINE FEED)
-    escape_sequence()
-        .map(|x| x.0)
-        .or(none_of("\u{005c}\u{000D}\u{2028}\u{2029}\u{000A}'".chars()))
+    // U+005C (REVERSE SOLIDUS), U+000D (CARRIAGE RETURN), and U+000A (LINE FEED)
+    // U+2028 (LINE SEPARATOR) and U+2029 (PARAGRAPH SEPARATOR) are allowed since ES2019
+    escape_sequence(strict)
+        .or(none_of("\u{005c}\u{000D}\u{000A}'".chars()).map(|c: char| (u32::from(c), c.to_string())))
+}
+
+// The characters are "cooked" and "raw" like the escape sequences, so the literal keeps its
+// source text, eg. to tell `'\07'` and `'\7'` apart.
+fn quoted_string(quote: char, characters: Vec<(u32, String)>) -> StringLiteral {
+    let mut raw = quote.to_string();
+    let mut code_points = Vec::with_capacity(characters.len());
+    for (cooked, text) in characters {
+        code_points.push(cooked);
+        raw.push_str(&text);
+    }
+    raw.push(quote);
+    StringLiteral {
+        value: JsString::from_code_points(code_points),
+        raw: Some(raw.into()),
+    }
 }
 
-// (char, String) is "cooked" and "raw"
+// (u32, String) is "cooked" and "raw"
 // this is for template elements, to be able to get access to the raw string
 // this makes things uglier, but oh well
+// The cooked value is a code point instead of a char, because `\uD800` is a lone surrogate.
+// Legacy octal escapes are only allowed in sloppy mode, and never in templates.
 #[allow(dead_code)]
-fn escape_sequence<I>() -> impl Parser<Input = I, Output = (char, String)>
+fn escape_sequence<I>(strict: bool) -> impl Parser<Input = I, Output = (u32, String)>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
+    let code_point = |(cooked, raw): (char, String)| (u32::from(cooked), raw);
     choice((
-        try(character_escape_sequence()),
-        try(non_escape_character_sequence()),
-        try(hex_escape_sequence()),
-        // TODO legacy octal escape sequence
+        try(character_escape_sequence()).map(code_point),
+        try(null_escape_sequence()).map(code_point),
+        try(non_escape_character_sequence()).map(code_point),
+        try(hex_escape_sequence()).map(code_point),
+        legacy_octal_escape_sequence(strict).map(code_point),
         unicode_escape_sequence(),
     ))
 }
@@ -529,6 +882,18 @@ where
         })
 }
 
+// `\0` is only an escape for the null character if it is not followed by a digit.
+#[allow(dead_code)]
+fn null_escape_sequence<I>() -> impl Parser<Input = I, Output = (char, String)>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    string("\\0")
+        .skip(not_followed_by(digit()))
+        .map(|raw| ('\u{0}', raw.to_string()))
+}
+
 #[allow(dead_code)]
 fn non_escape_character_sequence<I>() -> impl Parser<Input = I, Output = (char, String)>
 where
@@ -549,15 +914,50 @@ where
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
     (token('\\'), token('x'), count::<String, _>(2, hex_digit())).map(|(t, x, hex_digits)| {
-        let code_point = u32::from_str_radix(&hex_digits, 16).unwrap();
-        let cooked = ::std::char::from_u32(code_point).unwrap();
+        // two hex digits are always a valid char
+        let cooked = char::from(integer_value(&hex_digits, 16) as u8);
         (cooked, format!("{}{}{}", t, x, hex_digits))
     })
 }
 
+// https://www.ecma-international.org/ecma-262/9.0/index.html#prod-annexB-LegacyOctalEscapeSequence
+// This also handles the NonOctalDecimalEscapeSequences `\8` and `\9`, which are the digits
+// themselves.
+#[allow(dead_code)]
+fn legacy_octal_escape_sequence<I>(strict: bool) -> impl Parser<Input = I, Output = (char, String)>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    try((token('\\'), digit()))
+        .then(|(t, first)| {
+            // the value has to fit in a byte, so `\400` is `\40` followed by `0`
+            let rest = match first {
+                '0'..='3' => 2,
+                '4'..='7' => 1,
+                _ => 0,
+            };
+            count::<String, _>(rest, one_of("01234567".chars()))
+                .map(move |rest| (first, format!("{}{}{}", t, first, rest)))
+        })
+        .then(move |(first, raw)| {
+            if strict {
+                unexpected("octal escape")
+                    .map(|_| (' ', String::new()))
+                    .message("Octal escape sequences are not allowed in strict mode or templates")
+                    .right()
+            } else if first == '8' || first == '9' {
+                value((first, raw)).left()
+            } else {
+                let cooked = char::from(integer_value(&raw[1..], 8) as u8);
+                value((cooked, raw)).left()
+            }
+        })
+}
+
 // https://www.ecma-international.org/ecma-262/9.0/index.html#prod-UnicodeEscapeSequence
 #[allow(dead_code)]
-fn unicode_escape_sequence<I>() -> impl Parser<Input = I, Output = (char, String)>
+fn unicode_escape_sequence<I>() -> impl Parser<Input = I, Output = (u32, String)>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
@@ -572,28 +972,35 @@ where
             count::<String, _>(4, hex_digit()),
         )),
     ).then(|(t, u, digits_raw)| {
-        let digits_cooked = if &digits_raw[0..1] == "{" {
+        let digits_cooked = if digits_raw.starts_with('{') {
             &digits_raw[1..digits_raw.len() - 1]
         } else {
             &digits_raw[..]
         };
-        let code_point = u32::from_str_radix(digits_cooked, 16).unwrap();
-        if code_point > 0x0010_FFFF {
-            unexpected("code point")
-                .map(|_| (' ', String::new()))
-                .message("Code point too large")
-                .right()
-        } else {
-            let cooked = ::std::char::from_u32(code_point).unwrap();
-            let raw = format!("{}{}{}", t, u, digits_raw);
-            value((cooked, raw)).left()
+        // surrogates are allowed, strings and templates can hold them
+        match u32::from_str_radix(digits_cooked, 16) {
+            Ok(cooked) if cooked <= 0x0010_FFFF => {
+                let raw = format!("{}{}{}", t, u, digits_raw);
+                value((cooked, raw)).left()
+            }
+            result => {
+                let message = match result {
+                    Ok(_) => "Code point too large",
+                    // eg. `\\u{}`
+                    Err(_) => "Expected hex digits",
+                };
+                unexpected("code point")
+                    .map(|_| (0, String::new()))
+                    .message(message)
+                    .right()
+            }
         }
     })
 }
 
 // https://www.ecma-international.org/ecma-262/9.0/index.html#sec-literals-regular-expression-literals
 #[allow(dead_code)]
-fn regex_literal<I>() -> impl Parser<Input = I, Output = RegexLiteral>
+pub(crate) fn regex_literal<I>() -> impl Parser<Input = I, Output = RegexLiteral>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
@@ -672,16 +1079,16 @@ where
 
 // https://www.ecma-international.org/ecma-262/9.0/index.html#sec-template-literal-lexical-components
 #[allow(dead_code)]
-fn template<I>() -> impl Parser<Input = I, Output = TemplateElement>
+fn template<I>(cook: bool) -> impl Parser<Input = I, Output = TemplateElement>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    choice((try(no_substition_template()), template_head()))
+    choice((try(no_substition_template(cook)), template_head(cook)))
 }
 
 #[allow(dead_code)]
-fn no_substition_template<I>() -> impl Parser<Input = I, Output = TemplateElement>
+fn no_substition_template<I>(cook: bool) -> impl Parser<Input = I, Output = TemplateElement>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
@@ -690,15 +1097,11 @@ where
         token('`'),
         token('`'),
         many::<Vec<_>, _>(template_character()),
-    ).map(|pairs| {
-        let cooked = pairs.iter().cloned().map(|x| x.0).collect();
-        let raw = pairs.iter().cloned().map(|x| x.1).collect();
-        TemplateElement { cooked, raw }
-    })
+    ).map(move |pairs| template_element(&pairs, cook))
 }
 
 #[allow(dead_code)]
-fn template_head<I>() -> impl Parser<Input = I, Output = TemplateElement>
+fn template_head<I>(cook: bool) -> impl Parser<Input = I, Output = TemplateElement>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
@@ -707,38 +1110,34 @@ where
         token('`'),
         string("${"),
         many::<Vec<_>, _>(template_character()),
-    ).map(|pairs| {
-        let cooked = pairs.iter().cloned().map(|x| x.0).collect();
-        let raw = pairs.iter().cloned().map(|x| x.1).collect();
-        TemplateElement { cooked, raw }
-    })
+    ).map(move |pairs| template_element(&pairs, cook))
 }
 
 #[allow(dead_code)]
-fn template_character<I>() -> impl Parser<Input = I, Output = (char, String)>
+pub(crate) fn template_character<I>() -> impl Parser<Input = I, Output = (u32, String)>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
     choice((
-        try(token('$').skip(not_followed_by(token('{')))).map(|x: char| (x, x.to_string())),
-        try(escape_sequence()),
-        try(one_of("\r\n\u{2028}\u{2029}".chars())).map(|x: char| (x, x.to_string())),
-        none_of("`\\$".chars()).map(|x: char| (x, x.to_string())),
+        try(token('$').skip(not_followed_by(token('{')))).map(|x: char| (u32::from(x), x.to_string())),
+        try(escape_sequence(true)),
+        try(one_of("\r\n\u{2028}\u{2029}".chars())).map(|x: char| (u32::from(x), x.to_string())),
+        none_of("`\\$".chars()).map(|x: char| (u32::from(x), x.to_string())),
     ))
 }
 
 #[allow(dead_code)]
-fn template_substition_tail<I>() -> impl Parser<Input = I, Output = TemplateElement>
+fn template_substition_tail<I>(cook: bool) -> impl Parser<Input = I, Output = TemplateElement>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    choice((try(template_middle()), template_tail()))
+    choice((try(template_middle(cook)), template_tail(cook)))
 }
 
 #[allow(dead_code)]
-fn template_middle<I>() -> impl Parser<Input = I, Output = TemplateElement>
+fn template_middle<I>(cook: bool) -> impl Parser<Input = I, Output = TemplateElement>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
@@ -747,31 +1146,52 @@ where
         token('}'),
         string("${"),
         many::<Vec<_>, _>(template_character()),
-    ).map(|pairs| {
-        let cooked = pairs.iter().cloned().map(|x| x.0).collect();
-        let raw = pairs.iter().cloned().map(|x| x.1).collect();
-        TemplateElement { cooked, raw }
-    })
+    ).map(move |pairs| template_element(&pairs, cook))
 }
 
 #[allow(dead_code)]
-fn template_tail<I>() -> impl Parser<Input = I, Output = TemplateElement>
+fn template_tail<I>(cook: bool) -> impl Parser<Input = I, Output = TemplateElement>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
     token('}')
         .with(many::<Vec<_>, _>(template_character()))
-        .map(|pairs| {
-            let cooked = pairs.iter().cloned().map(|x| x.0).collect();
-            let raw = pairs.iter().cloned().map(|x| x.1).collect();
-            TemplateElement { cooked, raw }
-        })
+        .map(move |pairs| template_element(&pairs, cook))
+}
+
+// Cooking can be skipped for consumers that only need the raw text, eg. a printer. The
+// escape sequences are still checked, and the element can be cooked later with
+// `TemplateElement::cook`.
+fn template_element(pairs: &[(u32, String)], cook: bool) -> TemplateElement {
+    let cooked = if cook {
+        Some(JsString::from_code_points(pairs.iter().map(|x| x.0)))
+    } else {
+        None
+    };
+    let raw = pairs.iter().map(|x| &x.1[..]).collect();
+    TemplateElement { cooked, raw }
+}
+
+impl TemplateElement {
+    /// This evaluates the escape sequences in `raw`, for an element that was parsed
+    /// without cooking it. It returns `None` if `raw` is not valid template text.
+    pub fn cook(&self) -> Option<JsString> {
+        let (pairs, rest) = many::<Vec<_>, _>(template_character())
+            .parse(&self.raw[..])
+            .ok()?;
+        if rest.is_empty() {
+            Some(JsString::from_code_points(pairs.into_iter().map(|x| x.0)))
+        } else {
+            None
+        }
+    }
 }
 
 #[cfg(test)]
 mod literal_tests {
     use super::*;
+    use combine::error::StringStreamError;
 
     #[test]
     fn test_null_literal() {
@@ -784,97 +1204,231 @@ mod literal_tests {
         assert_eq!(boolean_literal().parse("false"), Ok((false, "")));
     }
 
+    fn parse_number(strict: bool, source: &str) -> Result<(f64, &str), StringStreamError> {
+        numeric_literal(strict)
+            .parse(source)
+            .map(|(n, rest)| (n.value, rest))
+    }
+
     #[test]
     fn test_number_literal() {
         // decimal
-        assert_eq!(numeric_literal().parse("0"), Ok((0f64, "")));
-        assert!(numeric_literal().parse("01").is_err());
-        assert!(numeric_literal().parse("01.").is_err());
-        assert_eq!(numeric_literal().parse("9"), Ok((9f64, "")));
-        assert_eq!(numeric_literal().parse("10"), Ok((10f64, "")));
-        assert_eq!(numeric_literal().parse("0.1"), Ok((0.1f64, "")));
-        assert_eq!(numeric_literal().parse(".1"), Ok((0.1f64, "")));
-        assert_eq!(numeric_literal().parse("1e1"), Ok((10f64, "")));
-        assert_eq!(numeric_literal().parse(".1e1"), Ok((1f64, "")));
-        assert_eq!(numeric_literal().parse("1.1e1"), Ok((11f64, "")));
+        assert_eq!(parse_number(true, "0"), Ok((0f64, "")));
+        assert!(numeric_literal(true).parse("01").is_err());
+        assert!(numeric_literal(true).parse("01.").is_err());
+        assert_eq!(parse_number(true, "9"), Ok((9f64, "")));
+        assert_eq!(parse_number(true, "10"), Ok((10f64, "")));
+        assert_eq!(parse_number(true, "0.1"), Ok((0.1f64, "")));
+        assert_eq!(parse_number(true, ".1"), Ok((0.1f64, "")));
+        assert_eq!(parse_number(true, "1e1"), Ok((10f64, "")));
+        assert_eq!(parse_number(true, ".1e1"), Ok((1f64, "")));
+        assert_eq!(parse_number(true, "1.1e1"), Ok((11f64, "")));
 
         // binary
-        assert_eq!(numeric_literal().parse("0b1010"), Ok((10f64, "")));
-        assert_eq!(numeric_literal().parse("0B1010"), Ok((10f64, "")));
+        assert_eq!(parse_number(true, "0b1010"), Ok((10f64, "")));
+        assert_eq!(parse_number(true, "0B1010"), Ok((10f64, "")));
         // octal
-        assert_eq!(numeric_literal().parse("0o123"), Ok((83f64, "")));
-        assert_eq!(numeric_literal().parse("0O123"), Ok((83f64, "")));
+        assert_eq!(parse_number(true, "0o123"), Ok((83f64, "")));
+        assert_eq!(parse_number(true, "0O123"), Ok((83f64, "")));
         // hex
         assert_eq!(
-            numeric_literal().parse("0xDEADBEEF"),
+            parse_number(true, "0xDEADBEEF"),
             Ok((3735928559f64, ""))
         );
         assert_eq!(
-            numeric_literal().parse("0XDEADBEEF"),
+            parse_number(true, "0XDEADBEEF"),
             Ok((3735928559f64, ""))
         );
+        // too large for a u64
+        assert_eq!(
+            parse_number(true, "0x10000000000000000"),
+            Ok((18446744073709551616f64, ""))
+        );
+        let binary = format!("0b1{}", "0".repeat(100));
+        assert_eq!(
+            parse_number(true, binary.as_str()),
+            Ok((2f64.powi(100), ""))
+        );
+
+        // no digits
+        assert!(numeric_literal(true).parse(".").is_err());
+        assert!(numeric_literal(true).parse(".e1").is_err());
+
+        // legacy octal, and decimal with a leading zero
+        assert_eq!(parse_number(false, "0777"), Ok((511f64, "")));
+        assert_eq!(parse_number(false, "089"), Ok((89f64, "")));
+        assert_eq!(parse_number(false, "08.5"), Ok((8.5f64, "")));
+        assert_eq!(parse_number(false, "09e1"), Ok((90f64, "")));
+        assert_eq!(parse_number(false, "089.5E-1"), Ok((8.95f64, "")));
+        // a legacy octal integer has no fraction
+        assert_eq!(parse_number(false, "07.5"), Ok((7f64, ".5")));
+        assert_eq!(parse_number(false, "0"), Ok((0f64, "")));
+        assert_eq!(parse_number(false, "0.5"), Ok((0.5f64, "")));
+        let error = numeric_literal(true)
+            .easy_parse(State::new("0777"))
+            .unwrap_err();
+        assert_eq!(
+            SyntaxError::from(error).message,
+            "Unexpected `leading zero`\nLegacy octal literals are not allowed in strict mode\n"
+        );
+    }
+
+    #[test]
+    fn test_numeric_separators() {
+        assert_eq!(parse_number(true, "1_000_000"), Ok((1e6, "")));
+        assert_eq!(parse_number(true, "1_0.2_5e1_0"), Ok((10.25e10, "")));
+        assert_eq!(parse_number(true, "0xFF_FF"), Ok((65535f64, "")));
+        assert_eq!(parse_number(true, "0b1_0"), Ok((2f64, "")));
+        assert_eq!(parse_number(true, "0o7_7"), Ok((63f64, "")));
+        for source in &["1_", "1__0", "0x_1", "0_1", "1e_1", "1_e1"] {
+            let result = numeric_literal(true).skip(eof()).parse(*source);
+            assert!(result.is_err(), "{}", source);
+        }
+        let error = numeric_literal(true)
+            .easy_parse(State::new("1__0"))
+            .unwrap_err();
+        assert!(SyntaxError::from(error)
+            .message
+            .contains("Numeric separators are only allowed between digits"));
+        // but only after a separator
+        let error = try_parse_expression("1a").unwrap_err();
+        assert!(!error.message.contains("Numeric separators"), "{}", error.message);
+        // legacy octal literals can not have separators
+        assert_eq!(parse_number(false, "07_7"), Ok((7f64, "_7")));
+    }
+
+    #[test]
+    fn test_number_raw() {
+        let raw = |strict, source| numeric_literal(strict).parse(source).unwrap().0.raw;
+        for source in &["1_000", "0XFF", "0b1_0", "0O17", ".5", "1.", "1E+3", "1_0.2_5e1_0"] {
+            assert_eq!(raw(true, *source).as_deref(), Some(*source));
+        }
+        assert_eq!(raw(false, "017").as_deref(), Some("017"));
+        assert_eq!(raw(false, "089").as_deref(), Some("089"));
+        assert_eq!(raw(false, "08.1_2e3").as_deref(), Some("08.1_2e3"));
+        // the raw text is only how the number was spelled
+        assert_eq!(
+            numeric_literal(true).parse("1_000").unwrap().0,
+            NumberLiteral::from(1000f64)
+        );
+    }
+
+    #[test]
+    fn test_number_kinds() {
+        let kind = |strict, source| numeric_literal(strict).parse(source).unwrap().0.kind;
+        assert_eq!(kind(true, "1.5e3"), NumberKind::Decimal);
+        assert_eq!(kind(true, "0b1"), NumberKind::Binary);
+        assert_eq!(kind(true, "0o7"), NumberKind::Octal);
+        assert_eq!(kind(true, "0XFF"), NumberKind::Hex);
+        assert_eq!(kind(false, "017"), NumberKind::LegacyOctal);
+        assert_eq!(kind(false, "019"), NumberKind::Decimal);
+        assert_eq!(NumberKind::Hex.radix(), 16);
     }
 
     #[test]
     fn test_string_literal() {
         // empty
-        assert_eq!(string_literal().parse(r#""""#), Ok((String::new(), "")));
-        assert_eq!(string_literal().parse("''"), Ok((String::new(), "")));
+        assert_eq!(string_literal(true).parse(r#""""#), Ok(("".into(), "")));
+        assert_eq!(string_literal(true).parse("''"), Ok(("".into(), "")));
         // not allowed chars
-        for not_allowed_char in "\u{005c}\u{000D}\u{2028}\u{2029}\u{000A}".chars() {
+        for not_allowed_char in "\u{005c}\u{000D}\u{000A}".chars() {
             let double_quote_slice: &str = &format!("\"{}\"", not_allowed_char);
             let single_quote_slice: &str = &format!("'{}'", not_allowed_char);
-            assert!(string_literal().parse(double_quote_slice).is_err());
-            assert!(string_literal().parse(single_quote_slice).is_err());
+            assert!(string_literal(true).parse(double_quote_slice).is_err());
+            assert!(string_literal(true).parse(single_quote_slice).is_err());
         }
+        // line and paragraph separators
+        assert_eq!(
+            string_literal(true).parse("'\u{2028}\u{2029}'"),
+            Ok(("\u{2028}\u{2029}".into(), ""))
+        );
+        assert_eq!(
+            string_literal(true).parse("\"\u{2028}\""),
+            Ok(("\u{2028}".into(), ""))
+        );
         // character escape sequences
         for escaped_character in r#"'"\bfnrtv"#.chars() {
             let double_quote_slice: &str = &format!("\"\\{}\"", escaped_character);
             let single_quote_slice: &str = &format!("'\\{}'", escaped_character);
-            assert!(string_literal().parse(double_quote_slice).is_ok());
-            assert!(string_literal().parse(single_quote_slice).is_ok());
+            assert!(string_literal(true).parse(double_quote_slice).is_ok());
+            assert!(string_literal(true).parse(single_quote_slice).is_ok());
         }
         // non character escape sequences
-        assert_eq!(string_literal().parse("\"\\a\""), Ok(("a".to_string(), "")));
-        assert_eq!(string_literal().parse("'\\a'"), Ok(("a".to_string(), "")));
+        assert_eq!(string_literal(true).parse("\"\\a\""), Ok(("a".into(), "")));
+        assert_eq!(string_literal(true).parse("'\\a'"), Ok(("a".into(), "")));
 
         // hex escape sequence
         assert_eq!(
-            string_literal().parse(r#""\x0A""#),
-            Ok(("\n".to_string(), ""))
+            string_literal(true).parse(r#""\x0A""#),
+            Ok(("\n".into(), ""))
         );
         assert_eq!(
-            string_literal().parse(r"'\x0a'"),
-            Ok(("\n".to_string(), ""))
+            string_literal(true).parse(r"'\x0a'"),
+            Ok(("\n".into(), ""))
         );
         // unicode escape sequence
         assert_eq!(
-            string_literal().parse(r#""\u2764""#),
-            Ok(("❤".to_string(), ""))
+            string_literal(true).parse(r#""\u2764""#),
+            Ok(("❤".into(), ""))
         );
         assert_eq!(
-            string_literal().parse(r"'\u2764'"),
-            Ok(("❤".to_string(), ""))
+            string_literal(true).parse(r"'\u2764'"),
+            Ok(("❤".into(), ""))
         );
         assert_eq!(
-            string_literal().parse(r#""\u{2764}""#),
-            Ok(("❤".to_string(), ""))
+            string_literal(true).parse(r#""\u{2764}""#),
+            Ok(("❤".into(), ""))
         );
         assert_eq!(
-            string_literal().parse(r"'\u{2764}'"),
-            Ok(("❤".to_string(), ""))
+            string_literal(true).parse(r"'\u{2764}'"),
+            Ok(("❤".into(), ""))
         );
-        assert!(string_literal().parse(r"'\u{110000}'").is_err());
+        assert!(string_literal(true).parse(r"'\u{110000}'").is_err());
+        assert!(string_literal(true).parse(r"'\u{}'").is_err());
+
+        // lone surrogates are kept, and surrogate pairs are combined
+        assert_eq!(
+            string_literal(true).parse(r"'\uD800'"),
+            Ok((JsString::from_utf16(&[0xD800]).into(), ""))
+        );
+        assert_eq!(
+            string_literal(true).parse(r"'a\u{DE00}\uD83D'"),
+            Ok((JsString::from_utf16(&[0x61, 0xDE00, 0xD83D]).into(), ""))
+        );
+        assert_eq!(
+            string_literal(true).parse(r#""\uD83D\uDE00""#),
+            Ok(("😀".into(), ""))
+        );
+
+        // null and legacy octal escape sequences
+        assert_eq!(string_literal(true).parse(r"'\0'"), Ok(("\0".into(), "")));
+        assert_eq!(
+            string_literal(false).parse(r"'\07\101\400\08\9'"),
+            Ok(("\u{7}A 0\u{0}89".into(), ""))
+        );
+        for octal in &[r"'\07'", r"'\08'", r"'\1'", r"'\9'"] {
+            assert!(string_literal(true).parse(*octal).is_err(), "{}", octal);
+        }
 
         // line continuation
         for line_continuation_char in "\r\n\u{2028}\u{2029}".chars() {
             let double_quote_slice: &str = &format!("\"\\{}\"", line_continuation_char);
             let single_quote_slice: &str = &format!("'\\{}'", line_continuation_char);
-            assert!(string_literal().parse(double_quote_slice).is_err());
-            assert!(string_literal().parse(single_quote_slice).is_err());
+            assert!(string_literal(true).parse(double_quote_slice).is_err());
+            assert!(string_literal(true).parse(single_quote_slice).is_err());
         }
     }
 
+    #[test]
+    fn test_string_raw() {
+        let raw = |source| string_literal(false).parse(source).unwrap().0.raw;
+        for source in &[r"'\07'", r"'\7'", r#""a\x61\u{61}""#, r"'\08'"] {
+            assert_eq!(raw(*source).as_deref(), Some(*source));
+        }
+        // the raw text is only how the string was spelled
+        assert_eq!(string_literal(false).parse(r"'\07'"), string_literal(false).parse(r"'\7'"));
+    }
+
     #[test]
     fn test_regex_literal() {
         // must be non empty
@@ -950,59 +1504,93 @@ mod literal_tests {
         assert!(regex_literal().skip(eof()).parse("/a/\\u1234").is_err());
     }
 
+    #[test]
+    fn test_template_octal_escapes() {
+        assert_eq!(
+            template(true).parse(r"`\0`"),
+            Ok((
+                TemplateElement {
+                    cooked: Some("\0".into()),
+                    raw: r"\0".to_string(),
+                },
+                ""
+            ))
+        );
+        assert!(template(true).parse(r"`\01`").is_err());
+        assert!(template(true).parse(r"`\8`").is_err());
+    }
+
+    #[test]
+    fn test_lazy_cooking() {
+        let (element, _) = template(false).parse(r"`a\n\u{41}${").unwrap();
+        assert_eq!(element.raw, r"a\n\u{41}");
+        assert_eq!(element.cooked, None);
+        assert_eq!(element.cook(), Some("a\nA".into()));
+        // escape sequences are still checked
+        assert!(template(false).parse(r"`\01`").is_err());
+        assert_eq!(build_ast!(templ_el {r"\u{}".to_string()}).cook(), None);
+        // and through the public entry point
+        let element = try_parse_template(r"`\x41`", false).unwrap();
+        assert_eq!(element.cooked, None);
+        assert_eq!(element.cook(), Some("A".into()));
+        let element = try_parse_template(r"`\x41`", true).unwrap();
+        assert_eq!(element.cooked, Some("A".into()));
+        assert!(try_parse_template(r"`\01`", false).is_err());
+    }
+
     #[test]
     fn test_template_elements() {
         // empty
         assert_eq!(
-            template().parse("``"),
+            template(true).parse("``"),
             Ok((build_ast!(templ_el {String::new()}), ""))
         );
 
         // no_substitution_template
         assert_eq!(
-            template().parse("`asd`"),
+            template(true).parse("`asd`"),
             Ok((build_ast!(templ_el {"asd".to_string()}), ""))
         );
 
         // template_head
         assert_eq!(
-            template().parse("`asd ${eval}`"),
+            template(true).parse("`asd ${eval}`"),
             Ok((build_ast!(templ_el {"asd ".to_string()}), "eval}`"))
         );
 
         // template_middle
         assert_eq!(
-            template_substition_tail().parse("} asd ${eval}`"),
+            template_substition_tail(true).parse("} asd ${eval}`"),
             Ok((build_ast!(templ_el {" asd ".to_string()}), "eval}`"))
         );
 
         // template_tail
         assert_eq!(
-            template_substition_tail().parse("} asd"),
+            template_substition_tail(true).parse("} asd"),
             Ok((build_ast!(templ_el {" asd".to_string()}), ""))
         );
 
         // $
         assert_eq!(
             template_character().parse("$123"),
-            Ok((('$', "$".to_string()), "123"))
+            Ok(((u32::from('$'), "$".to_string()), "123"))
         );
         // escape sequence
         assert_eq!(
             template_character().parse("\\n"),
-            Ok((('\n', "\\n".to_string()), ""))
+            Ok(((u32::from('\n'), "\\n".to_string()), ""))
         );
         assert_eq!(
             template_character().parse("\\x0A"),
-            Ok((('\n', "\\x0A".to_string()), ""))
+            Ok(((u32::from('\n'), "\\x0A".to_string()), ""))
         );
         assert_eq!(
             template_character().parse("\\u2764"),
-            Ok((('❤', "\\u2764".to_string()), ""))
+            Ok(((u32::from('❤'), "\\u2764".to_string()), ""))
         );
         assert_eq!(
             template_character().parse("\\u{2764}"),
-            Ok((('❤', "\\u{2764}".to_string()), ""))
+            Ok(((u32::from('❤'), "\\u{2764}".to_string()), ""))
         );
         // line continuation
         for line_continuation_char in "\r\n\u{2028}\u{2029}".chars() {
@@ -1010,25 +1598,36 @@ mod literal_tests {
             assert_eq!(
                 template_character().parse(slice),
                 Ok((
-                    (line_continuation_char, line_continuation_char.to_string()),
+                    (u32::from(line_continuation_char), line_continuation_char.to_string()),
                     ""
                 ))
             );
         }
+        // lone surrogate
+        assert_eq!(
+            template(true).parse(r"`\uD800`"),
+            Ok((
+                TemplateElement {
+                    cooked: Some(JsString::from_utf16(&[0xD800])),
+                    raw: r"\uD800".to_string(),
+                },
+                ""
+            ))
+        );
     }
 }
 
 // https://www.ecma-international.org/ecma-262/9.0/index.html#sec-ecmascript-language-expressions
 #[allow(dead_code)]
-fn primary_expression<I>() -> impl Parser<Input = I, Output = Expression>
+fn primary_expression<I>(strict: bool) -> impl Parser<Input = I, Output = Expression>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
     choice((
         try(this()),
-        try(identifier_reference()),
-        try(literal()),
+        try(identifier_reference(strict)),
+        try(literal(strict)),
         try(array_literal()),
         jsx_element(),
     ))
@@ -1044,16 +1643,16 @@ where
 }
 
 #[allow(dead_code)]
-fn identifier_reference<I>() -> impl Parser<Input = I, Output = Expression>
+fn identifier_reference<I>(strict: bool) -> impl Parser<Input = I, Output = Expression>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    identifier().map(Expression::IdReference)
+    identifier(strict).map(|id| Expression::IdReference(id.into()))
 }
 
 #[allow(dead_code)]
-fn literal<I>() -> impl Parser<Input = I, Output = Expression>
+fn literal<I>(strict: bool) -> impl Parser<Input = I, Output = Expression>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
@@ -1061,8 +1660,8 @@ where
     choice((
         try(null_literal()).map(|n| Expression::Literal(ExpressionLiteral::NullLiteral(n))),
         try(boolean_literal()).map(|n| Expression::Literal(ExpressionLiteral::BooleanLiteral(n))),
-        try(numeric_literal()).map(|n| Expression::Literal(ExpressionLiteral::NumberLiteral(n))),
-        try(string_literal()).map(|n| Expression::Literal(ExpressionLiteral::StringLiteral(n))),
+        try(numeric_literal(strict)).map(|n| Expression::Literal(ExpressionLiteral::NumberLiteral(n))),
+        try(string_literal(strict)).map(|n| Expression::Literal(ExpressionLiteral::StringLiteral(Box::new(n)))),
     ))
 }
 
@@ -1151,10 +1750,12 @@ where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    between(token('<'), string("/>"), identifier()).map(|name| Expression::JsxElement {
-        name,
-        attributes: Vec::new(),
-        children: Vec::new(),
+    between(token('<'), string("/>"), identifier(true)).map(|name| {
+        Expression::JsxElement(Box::new(JsxElement {
+            name: name.into(),
+            attributes: Vec::new(),
+            children: Vec::new(),
+        }))
     })
 }
 
@@ -1165,22 +1766,24 @@ where
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
     (
-        between(token('<'), string(">"), identifier()),
+        between(token('<'), string(">"), identifier(true)),
         skip_tokens(),
-        between(string("</"), token('>'), identifier()),
+        between(string("</"), token('>'), identifier(true)),
     ).then(|(opening_name, _, closing_name)| {
         if opening_name == closing_name {
-            value(Expression::JsxElement {
-                name: opening_name,
+            value(Expression::JsxElement(Box::new(JsxElement {
+                name: opening_name.into(),
                 attributes: Vec::new(),
                 children: Vec::new(),
-            }).left()
+            }))).left()
         } else {
             unexpected("closing element")
-                .map(|_| Expression::JsxElement {
-                    name: String::new(),
-                    attributes: Vec::new(),
-                    children: Vec::new(),
+                .map(|_| {
+                    Expression::JsxElement(Box::new(JsxElement {
+                        name: "".into(),
+                        attributes: Vec::new(),
+                        children: Vec::new(),
+                    }))
                 })
                 .message("closing name is not the same as opening name")
                 .right()
@@ -1195,7 +1798,7 @@ mod expression_test {
     #[test]
     fn test_this() {
         assert_eq!(
-            primary_expression().parse("this"),
+            primary_expression(true).parse("this"),
             Ok((build_ast!(this), ""))
         );
     }
@@ -1203,7 +1806,7 @@ mod expression_test {
     #[test]
     fn test_identifier_reference() {
         assert_eq!(
-            primary_expression().parse("abc123"),
+            primary_expression(true).parse("abc123"),
             Ok((build_ast!(id "abc123".to_string()), ""))
         );
     }
@@ -1211,23 +1814,23 @@ mod expression_test {
     #[test]
     fn test_literal() {
         assert_eq!(
-            primary_expression().parse("null"),
+            primary_expression(true).parse("null"),
             Ok((build_ast!(null), ""))
         );
         assert_eq!(
-            primary_expression().parse("true"),
+            primary_expression(true).parse("true"),
             Ok((build_ast!(true), ""))
         );
         assert_eq!(
-            primary_expression().parse("false"),
+            primary_expression(true).parse("false"),
             Ok((build_ast!(false), ""))
         );
         assert_eq!(
-            primary_expression().parse("123.e1"),
+            primary_expression(true).parse("123.e1"),
             Ok((build_ast!(num 1230f64), ""))
         );
         assert_eq!(
-            primary_expression().parse("'abc'"),
+            primary_expression(true).parse("'abc'"),
             Ok((build_ast!(str "abc".to_string()), ""))
         );
     }
@@ -1235,19 +1838,19 @@ mod expression_test {
     #[test]
     fn test_array_literal() {
         assert_eq!(
-            primary_expression().parse("[]"),
+            primary_expression(true).parse("[]"),
             Ok((build_ast!(array []), ""))
         );
         assert_eq!(
-            primary_expression().parse("[,,,,]"),
+            primary_expression(true).parse("[,,,,]"),
             Ok((build_ast!(array []), ""))
         );
         assert_eq!(
-            primary_expression().parse("[,,,,yield,,yield,,,]"),
+            primary_expression(true).parse("[,,,,yield,,yield,,,]"),
             Ok((build_ast!(array [ [yield], [yield] ]), ""))
         );
         assert_eq!(
-            primary_expression().parse("[,,,...yield,,,]"),
+            primary_expression(true).parse("[,,,...yield,,,]"),
             Ok((build_ast!(array [ [...[yield]] ]), ""))
         );
     }
@@ -1255,14 +1858,14 @@ mod expression_test {
     #[test]
     fn test_jsx() {
         assert_eq!(
-            primary_expression().parse("<div/>"),
+            primary_expression(true).parse("<div/>"),
             Ok((build_ast!(<div />), ""))
         );
         assert_eq!(
-            primary_expression().parse("<div>\n\n</div>"),
+            primary_expression(true).parse("<div>\n\n</div>"),
             Ok((build_ast!(<div />), ""))
         );
-        assert!(primary_expression().parse("<div>\n\n</v>").is_err());
+        assert!(primary_expression(true).parse("<div>\n\n</v>").is_err());
     }
 }
 
@@ -1272,23 +1875,456 @@ mod expression_test {
 
 // https://www.ecma-international.org/ecma-262/9.0/index.html#sec-ecmascript-language-scripts-and-modules
 #[allow(dead_code)]
-fn program<I>() -> impl Parser<Input = I, Output = Program>
+pub(crate) fn program<I>(source_type: SourceType) -> impl Parser<Input = I, Output = Program>
 where
     I: Stream<Item = char>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    eof().map(|_| Program {
-        source_type: SourceType::Module,
-        body: Vec::new(),
+    // everything that can be in a program is whitespace or a comment, so the span of
+    // each comment is found by adding up the lengths of everything before it
+    // `-->` is only a comment at the start of a line, so the whitespace and the comments
+    // keep track of whether the parser is at one
+    let at_line_start = Rc::new(Cell::new(true));
+    let whitespace = |at_line_start: Rc<Cell<bool>>| {
+        satisfy(|c| is_whitespace(c) || is_line_terminator(c)).map(move |c: char| {
+            if is_line_terminator(c) {
+                at_line_start.set(true);
+            }
+            (c.len_utf8(), None)
+        })
+    };
+    let comment = |at_line_start: Rc<Cell<bool>>| {
+        comment_text().map(move |(text, kind)| {
+            if kind == CommentKind::Block && text.contains(is_line_terminator) {
+                at_line_start.set(true);
+            }
+            let delimiters = if kind == CommentKind::Line { 2 } else { 4 };
+            (text.len() + delimiters, Some((text, kind)))
+        })
+    };
+    // a Script can also have HTML-like comments
+    let trivia = if source_type == SourceType::Script {
+        let close = at_line_start.clone();
+        let open = at_line_start.clone();
+        choice((
+            html_close_comment(move || close.get()).map(|text: String| {
+                (text.len() + 3, Some((text, CommentKind::HtmlClose)))
+            }),
+            whitespace(at_line_start.clone()),
+            try(comment(at_line_start.clone())),
+            html_open_comment().map(move |text: String| {
+                open.set(false);
+                (text.len() + 4, Some((text, CommentKind::HtmlOpen)))
+            }),
+        )).left()
+    } else {
+        choice((whitespace(at_line_start.clone()), comment(at_line_start))).right()
+    };
+    // statements are not parsed yet, so nothing else is expected
+    let program =
+        (optional(hashbang()), many::<Vec<_>, _>(trivia), eof()).expected("end of input");
+    program.map(move |(hashbang, trivia, _)| {
+        let mut offset = hashbang.as_ref().map_or(0, |text: &String| text.len() + 2);
+        let mut comments = Vec::new();
+        for (length, text) in trivia {
+            if let Some((text, kind)) = text {
+                let span = Span::new(offset as u32, (offset + length) as u32);
+                comments.push(Comment { text, span, kind });
+            }
+            offset += length;
+        }
+        Program {
+            source_type: source_type.clone(),
+            body: Vec::new(),
+            span: Span::new(0, offset as u32),
+            hashbang,
+            comments,
+        }
     })
 }
 
+/// SyntaxError is the error returned when the source text could not be parsed.
+/// The line and column are 1 based, and point at where the parser gave up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    /// The line where the error occurred.
+    pub line: i32,
+    /// The column where the error occurred.
+    pub column: i32,
+    /// A description of what was unexpected, and what was expected instead.
+    pub message: String,
+    /// The keyword that the word at the error is likely a misspelling of, eg. `function`
+    /// for `fucntion`.
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Parse error at line: {}, column: {}\n{}",
+            self.line, self.column, self.message
+        )?;
+        match self.suggestion {
+            Some(ref suggestion) => writeln!(f, "Did you mean `{}`?", suggestion),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Fail for SyntaxError {}
+
+// The range is a `&str` when the whole source text is parsed at once, and a `char` when
+// it is read from a stream.
+impl<R: fmt::Display + PartialEq> From<easy::Errors<char, R, SourcePosition>> for SyntaxError {
+    fn from(errors: easy::Errors<char, R, SourcePosition>) -> SyntaxError {
+        struct Messages<'a, R: 'a>(&'a [easy::Error<char, R>]);
+        impl<'a, R: fmt::Display + PartialEq> fmt::Display for Messages<'a, R> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                easy::Error::fmt_errors(self.0, f)
+            }
+        }
+        SyntaxError {
+            line: errors.position.line,
+            column: errors.position.column,
+            message: Messages(&errors.errors).to_string(),
+            suggestion: None,
+        }
+    }
+}
+
+/// Origin is where a piece of source text starts in a larger document, eg. the first
+/// character inside of a `<script>` element in an HTML file. The line and column are 1
+/// based, like the ones in a `SyntaxError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Origin {
+    /// The byte offset in the document.
+    pub offset: u32,
+    /// The line in the document.
+    pub line: i32,
+    /// The column in the document, counted in characters.
+    pub column: i32,
+}
+
+impl Origin {
+    /// This finds the line and column of a byte offset in the document.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the offset is not on a character boundary of the document.
+    pub fn in_document(document: &str, offset: u32) -> Origin {
+        let before = &document[..offset as usize];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Origin {
+            offset,
+            line: before.matches('\n').count() as i32 + 1,
+            column: before[line_start..].chars().count() as i32 + 1,
+        }
+    }
+}
+
 /// The main entry point to the parser. This function will return a fully constructed
-/// AST or an error message describing why it couldn't parse the input string.
+/// AST or an error message describing why it couldn't parse the input string. The source
+/// is parsed as a module, use `parse_as` to parse a script.
 pub fn parse(source: &str) -> Result<Program, Error> {
+    Ok(try_parse(source)?)
+}
+
+/// This is the same as `parse`, but the source is parsed as the given source type, eg. the
+/// one that `SourceType::from_path` picks for a file.
+pub fn parse_as(source: &str, source_type: SourceType) -> Result<Program, Error> {
+    Ok(try_parse_as(source, source_type)?)
+}
+
+/// This is the same as `parse`, but returns the `SyntaxError` directly.
+///
+/// This function never panics, whatever the input is. Malformed input, such as an
+/// unterminated string or an invalid escape sequence, is always reported as an error.
+/// This makes it the entry point to use for fuzzing, where any panic is a bug.
+pub fn try_parse(source: &str) -> Result<Program, SyntaxError> {
+    try_parse_as(source, SourceType::Module)
+}
+
+/// This is the same as `parse_as`, but returns the `SyntaxError` directly.
+pub fn try_parse_as(source: &str, source_type: SourceType) -> Result<Program, SyntaxError> {
     let stream = State::new(source);
-    let (ast, _) = program()
+    let (ast, _) = program(source_type)
         .easy_parse(stream)
-        .map_err(|e| failure::err_msg(e.to_string()))?;
+        .map_err(|errors| suggest_keyword(errors.into(), source, SourcePosition::new()))?;
     Ok(ast)
 }
+
+/// This parses source text that is a single expression, eg. a snippet in a template or a
+/// config file, instead of a whole program. It is parsed as if it was in a module, so in
+/// strict mode. Whitespace and comments around the expression are allowed, but anything
+/// else after it is a syntax error.
+pub fn parse_expression(source: &str) -> Result<Expression, Error> {
+    Ok(try_parse_expression(source)?)
+}
+
+/// This is the same as `parse_expression`, but returns the `SyntaxError` directly.
+pub fn try_parse_expression(source: &str) -> Result<Expression, SyntaxError> {
+    let trivia = || {
+        skip_many(
+            satisfy(|c| is_whitespace(c) || is_line_terminator(c))
+                .map(|_| ())
+                .or(try(comment_text()).map(|_| ())),
+        )
+    };
+    let mut parser = (trivia(), primary_expression(true), trivia(), eof())
+        .map(|(_, expression, _, _)| expression)
+        .expected("end of input");
+    let (expression, _) = parser
+        .easy_parse(State::new(source))
+        .map_err(|errors| suggest_keyword(errors.into(), source, SourcePosition::new()))?;
+    Ok(expression)
+}
+
+/// This parses source text that is a template literal without substitutions, eg.
+/// `` `a\n` ``. When `cook` is false, the escape sequences are checked but not evaluated,
+/// so the cooked value is left out for consumers that only need the raw text, eg. a
+/// printer. It can be computed later with `TemplateElement::cook`.
+pub fn parse_template(source: &str, cook: bool) -> Result<TemplateElement, Error> {
+    Ok(try_parse_template(source, cook)?)
+}
+
+/// This is the same as `parse_template`, but returns the `SyntaxError` directly.
+pub fn try_parse_template(source: &str, cook: bool) -> Result<TemplateElement, SyntaxError> {
+    let (element, _) = no_substition_template(cook)
+        .skip(eof())
+        .easy_parse(State::new(source))
+        .map_err(|errors| suggest_keyword(errors.into(), source, SourcePosition::new()))?;
+    Ok(element)
+}
+
+/// This parses source text that is embedded in a larger document, which starts at the
+/// origin. The spans in the program and the position of a syntax error are in the
+/// document, instead of in the embedded source text. It is a syntax error if the source
+/// text ends past the largest offset that a span can hold.
+pub fn try_parse_embedded(
+    source: &str,
+    source_type: SourceType,
+    origin: Origin,
+) -> Result<Program, SyntaxError> {
+    let position = SourcePosition {
+        line: origin.line,
+        column: origin.column,
+    };
+    let stream = State::with_positioner(source, position);
+    let (mut ast, _) = program(source_type)
+        .easy_parse(stream)
+        .map_err(|errors| suggest_keyword(errors.into(), source, position))?;
+    let shift = |span: Span| {
+        let start = span.start.checked_add(origin.offset);
+        let end = span.end.checked_add(origin.offset);
+        match (start, end) {
+            (Some(start), Some(end)) => Ok(Span::new(start, end)),
+            _ => Err(SyntaxError {
+                line: origin.line,
+                column: origin.column,
+                message: "The source text is too long for a span at this offset".to_string(),
+                suggestion: None,
+            }),
+        }
+    };
+    ast.span = shift(ast.span)?;
+    for comment in &mut ast.comments {
+        comment.span = shift(comment.span)?;
+    }
+    Ok(ast)
+}
+
+// If the parser gave up at a word that looks like a misspelled keyword, eg. `retrun`,
+// the keyword is suggested. The source text starts at the position `start`, which can
+// be in a larger document.
+fn suggest_keyword(mut error: SyntaxError, source: &str, start: SourcePosition) -> SyntaxError {
+    let mut position = start;
+    for (offset, c) in source.char_indices() {
+        if (position.line, position.column) == (error.line, error.column) {
+            let word: String = source[offset..]
+                .chars()
+                .take_while(|&c| satisfy_id_continue(c))
+                .collect();
+            error.suggestion = suggest::keyword(&word).map(str::to_string);
+            break;
+        }
+        position.update(&c);
+    }
+    error
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    #[test]
+    fn test_syntax_error() {
+        let error = parse("a").unwrap_err();
+        let syntax_error = error.downcast_ref::<SyntaxError>().unwrap();
+        assert_eq!((syntax_error.line, syntax_error.column), (1, 1));
+        assert_eq!(
+            error.to_string(),
+            "Parse error at line: 1, column: 1\nUnexpected `a`\nExpected `end of input`\n"
+        );
+    }
+
+    #[test]
+    fn test_keyword_suggestion() {
+        let error = try_parse("// a\n  fucntion f() {}").unwrap_err();
+        assert_eq!(error.suggestion, Some("function".to_string()));
+        assert_eq!(
+            error.to_string(),
+            "Parse error at line: 2, column: 3\nUnexpected `f`\nExpected `end of input`\n\
+             Did you mean `function`?\n"
+        );
+        assert_eq!(try_parse("function").unwrap_err().suggestion, None);
+        assert_eq!(try_parse("a").unwrap_err().suggestion, None);
+        let error = try_parse_expression("nul esle").unwrap_err();
+        assert_eq!(error.suggestion, Some("else".to_string()));
+        let origin = Origin {
+            offset: 10,
+            line: 3,
+            column: 5,
+        };
+        let error = try_parse_embedded("/**/retrun", SourceType::Script, origin).unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.suggestion),
+            (3, 9, Some("return".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let source = "\t\u{B}\u{C} \u{A0}\u{FEFF}\u{1680}\u{2000}\u{202F}\u{3000}\u{2028}\r\n";
+        assert!(try_parse_as(source, SourceType::Script).is_ok());
+        assert!(try_parse_expression(&format!("{}a{}", source, source)).is_ok());
+        // U+0085 is whitespace to `char::is_whitespace`, but not in ECMAScript
+        assert!(try_parse_as("\u{85}", SourceType::Script).is_err());
+        assert!(try_parse_expression("\u{85}a").is_err());
+    }
+
+    #[test]
+    fn test_program_metadata() {
+        let source = "#!/usr/bin/env node\n// é\n/* a\n b */ \u{FEFF}//";
+        let program = try_parse(source).unwrap();
+        assert_eq!(program.span, Span::new(0, source.len() as u32));
+        assert_eq!(program.hashbang, Some("/usr/bin/env node".to_string()));
+        let comments: Vec<_> = program
+            .comments
+            .iter()
+            .map(|comment| (comment.text.as_str(), &source[comment.span.range()]))
+            .collect();
+        assert_eq!(
+            comments,
+            vec![(" é", "// é"), (" a\n b ", "/* a\n b */"), ("", "//")]
+        );
+        let kinds: Vec<_> = program
+            .comments
+            .iter()
+            .map(|comment| (comment.kind, comment.has_line_terminator()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (CommentKind::Line, false),
+                (CommentKind::Block, true),
+                (CommentKind::Line, false),
+            ]
+        );
+        // the hashbang has to be at the very start
+        assert!(try_parse(" #!a").is_err());
+        assert!(try_parse("/* a").is_err());
+    }
+
+    #[test]
+    fn test_html_like_comments() {
+        let source = "<!-- a\n\t--> b\r\n<!--";
+        let program = try_parse_as(source, SourceType::Script).unwrap();
+        let comments: Vec<_> = program
+            .comments
+            .iter()
+            .map(|comment| (comment.kind, &source[comment.span.range()]))
+            .collect();
+        assert_eq!(
+            comments,
+            vec![
+                (CommentKind::HtmlOpen, "<!-- a"),
+                (CommentKind::HtmlClose, "--> b"),
+                (CommentKind::HtmlOpen, "<!--"),
+            ]
+        );
+        assert!(try_parse_as("<!-- hi\n", SourceType::Script).is_ok());
+        assert!(try_parse_as("\n--> x\n", SourceType::Script).is_ok());
+        // the start of the source text and a block comment over many lines start a line
+        for source in &["--> x", "/* */ --> x", "/*\n*/ --> x", "\n/* */ /**/ --> x"] {
+            let program = try_parse_as(source, SourceType::Script).unwrap_or_else(|_| {
+                panic!("{:?}", source);
+            });
+            assert_eq!(program.comments.last().unwrap().kind, CommentKind::HtmlClose);
+        }
+        // modules never have html comments
+        assert!(try_parse_as("<!-- hi\n", SourceType::Module).is_err());
+        assert!(try_parse_as("\n--> x\n", SourceType::Module).is_err());
+    }
+
+    #[test]
+    fn test_try_parse_embedded() {
+        let document = "<p>é</p>\n<script>// a\n  /* b */</script>";
+        let start = document.find("//").unwrap();
+        let end = document.find("</script>").unwrap();
+        let origin = Origin::in_document(document, start as u32);
+        assert_eq!(
+            origin,
+            Origin {
+                offset: start as u32,
+                line: 2,
+                column: 9,
+            }
+        );
+        let program =
+            try_parse_embedded(&document[start..end], SourceType::Script, origin).unwrap();
+        assert_eq!(&document[program.span.range()], "// a\n  /* b */");
+        assert_eq!(&document[program.comments[1].span.range()], "/* b */");
+
+        let error = try_parse_embedded("a", SourceType::Script, origin).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 9));
+        let error = try_parse_embedded("\n  a", SourceType::Script, origin).unwrap_err();
+        assert_eq!((error.line, error.column), (3, 3));
+
+        // the spans would overflow
+        let origin = Origin {
+            offset: u32::MAX - 2,
+            ..origin
+        };
+        let error = try_parse_embedded("/* a */", SourceType::Script, origin).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 9));
+        assert!(try_parse_embedded("", SourceType::Script, origin).is_ok());
+    }
+
+    #[test]
+    fn test_try_parse_expression() {
+        assert_eq!(
+            try_parse_expression(" /* a */ [yield]\n// b"),
+            Ok(build_ast!(array [ [yield] ]))
+        );
+        assert_eq!(parse_expression("'a'").unwrap(), build_ast!(str "a".to_string()));
+        // the whole source has to be a single expression
+        let error = try_parse_expression("a b").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 3));
+        assert!(try_parse_expression("").is_err());
+        // expressions are parsed in strict mode
+        assert!(try_parse_expression("public").is_err());
+    }
+
+    #[test]
+    fn test_try_parse() {
+        assert_eq!(try_parse("").unwrap().source_type, SourceType::Module);
+        assert_eq!(
+            try_parse_as("", SourceType::Script).unwrap().source_type,
+            SourceType::Script
+        );
+        for source in &["'", "\"\\u{}\"", "'\\uD800'", "0x", ".e1", "`${", "<a>", "\u{0}"] {
+            assert!(try_parse(source).is_err(), "{:?}", source);
+        }
+    }
+}
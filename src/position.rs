@@ -0,0 +1,195 @@
+//! This module converts between byte offsets and the line and character positions used
+//! by editors, eg.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::position::{Encoding, LineIndex, Position};
+//!
+//! let index = LineIndex::new("let a;\nf('😀', b);");
+//! let position = index.position(17, Encoding::Utf16);
+//! assert_eq!(position, Position { line: 1, character: 8 });
+//! assert_eq!(index.offset(position, Encoding::Utf16), 17);
+//! ```
+//!
+//! Positions follow the Language Server Protocol: lines and characters are 0 based, and
+//! lines end at `\n`, `\r\n` or `\r`. The JavaScript line terminators U+2028 and U+2029
+//! do not start a new line, because editors don't treat them as line breaks either.
+//! The width of a character depends on the `Encoding` that was negotiated with the
+//! client, which is UTF-16 unless the client says otherwise.
+
+/// Encoding is the unit that the character of a `Position` is counted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// Characters are counted in bytes, the same unit as a span.
+    Utf8,
+    /// Characters are counted in UTF-16 code units, so characters outside the basic
+    /// multilingual plane count as 2. This is the default in the Language Server Protocol.
+    Utf16,
+    /// Characters are counted in unicode code points, ie. Rust chars.
+    CodePoint,
+}
+
+impl Encoding {
+    fn width(self, c: char) -> u32 {
+        match self {
+            Encoding::Utf8 => c.len_utf8() as u32,
+            Encoding::Utf16 => c.len_utf16() as u32,
+            Encoding::CodePoint => 1,
+        }
+    }
+}
+
+/// Position is a 0 based line and character in the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub struct Position {
+    /// The line of the position.
+    pub line: u32,
+    /// The character of the position in the line, counted in some `Encoding`.
+    pub character: u32,
+}
+
+/// LineIndex remembers where each line of a source text starts, so byte offsets and
+/// positions can be converted without scanning the whole source every time.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<u32>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// This builds the index for the source text.
+    pub fn new(source: &'a str) -> LineIndex<'a> {
+        let mut line_starts = vec![0];
+        let bytes = source.as_bytes();
+        for (i, &byte) in bytes.iter().enumerate() {
+            let is_break = byte == b'\n' || (byte == b'\r' && bytes.get(i + 1) != Some(&b'\n'));
+            if is_break {
+                line_starts.push(i as u32 + 1);
+            }
+        }
+        LineIndex {
+            source,
+            line_starts,
+        }
+    }
+
+    /// The number of lines in the source text. An empty source has one line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// This converts a byte offset to a position. An offset past the end of the source
+    /// is treated as the end, and an offset inside of a character is treated as the
+    /// start of the character.
+    pub fn position(&self, offset: u32, encoding: Encoding) -> Position {
+        let offset = self.floor_char_boundary(offset);
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        let start = self.line_starts[line] as usize;
+        let character = self.source[start..offset as usize]
+            .chars()
+            .map(|c| encoding.width(c))
+            .sum();
+        Position {
+            line: line as u32,
+            character,
+        }
+    }
+
+    /// This converts a position to a byte offset. A character past the end of its line
+    /// is treated as the end of the line, and a line past the end of the source is
+    /// treated as the end of the source. A character in the middle of a character that
+    /// is wider than one unit is treated as the start of it.
+    pub fn offset(&self, position: Position, encoding: Encoding) -> u32 {
+        let line = position.line as usize;
+        if line >= self.line_starts.len() {
+            return self.source.len() as u32;
+        }
+        let start = self.line_starts[line] as usize;
+        let end = self.line_end(line);
+        let mut remaining = position.character;
+        for (i, c) in self.source[start..end].char_indices() {
+            let width = encoding.width(c);
+            if remaining < width {
+                return (start + i) as u32;
+            }
+            remaining -= width;
+        }
+        end as u32
+    }
+
+    /// The byte offset where a line ends, before its line terminator.
+    fn line_end(&self, line: usize) -> usize {
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.source.len(), |&start| start as usize);
+        let text = &self.source[self.line_starts[line] as usize..end];
+        end - (text.len() - text.trim_end_matches(['\n', '\r']).len())
+    }
+
+    fn floor_char_boundary(&self, offset: u32) -> u32 {
+        let mut offset = (offset as usize).min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset as u32
+    }
+}
+
+#[cfg(test)]
+mod position_tests {
+    use super::*;
+
+    fn position(line: u32, character: u32) -> Position {
+        Position { line, character }
+    }
+
+    #[test]
+    fn test_lines() {
+        let index = LineIndex::new("a\nb\r\nc\rd\u{2028}e");
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.position(0, Encoding::Utf8), position(0, 0));
+        assert_eq!(index.position(1, Encoding::Utf8), position(0, 1));
+        assert_eq!(index.position(2, Encoding::Utf8), position(1, 0));
+        assert_eq!(index.position(4, Encoding::Utf8), position(1, 2));
+        assert_eq!(index.position(5, Encoding::Utf8), position(2, 0));
+        assert_eq!(index.position(7, Encoding::Utf8), position(3, 0));
+        assert_eq!(index.position(11, Encoding::CodePoint), position(3, 2));
+        assert_eq!(index.position(100, Encoding::CodePoint), position(3, 3));
+        assert_eq!(LineIndex::new("").line_count(), 1);
+        assert_eq!(
+            LineIndex::new("\n").position(1, Encoding::Utf8),
+            position(1, 0)
+        );
+    }
+
+    #[test]
+    fn test_encodings() {
+        // é is 2 bytes, 1 unit in UTF-16, and 😀 is 4 bytes, 2 units in UTF-16
+        let index = LineIndex::new("x\né😀y");
+        let offset = 2 + 2 + 4;
+        assert_eq!(index.position(offset, Encoding::Utf8), position(1, 6));
+        assert_eq!(index.position(offset, Encoding::Utf16), position(1, 3));
+        assert_eq!(index.position(offset, Encoding::CodePoint), position(1, 2));
+        for &encoding in &[Encoding::Utf8, Encoding::Utf16, Encoding::CodePoint] {
+            for offset in &[0, 1, 2, 4, 8, 9] {
+                let position = index.position(*offset, encoding);
+                assert_eq!(index.offset(position, encoding), *offset);
+            }
+        }
+        // inside of a character
+        assert_eq!(index.position(6, Encoding::Utf16), position(1, 1));
+        assert_eq!(index.offset(position(1, 2), Encoding::Utf16), 4);
+    }
+
+    #[test]
+    fn test_clamping() {
+        let index = LineIndex::new("ab\r\ncd");
+        assert_eq!(index.offset(position(0, 10), Encoding::Utf16), 2);
+        assert_eq!(index.offset(position(1, 10), Encoding::Utf16), 6);
+        assert_eq!(index.offset(position(5, 0), Encoding::Utf16), 6);
+    }
+}
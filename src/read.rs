@@ -0,0 +1,168 @@
+//! This module parses source text that is read from an `io::Read`, eg. a large bundle on
+//! disk or a response from the network, without reading all of it into memory first.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::ast::SourceType;
+//! use ecmascript::parse_reader;
+//!
+//! let source: &[u8] = b"#!/usr/bin/env node\n// hello";
+//! let program = parse_reader(source, SourceType::Script).unwrap();
+//! assert_eq!(program.comments[0].text, " hello");
+//! ```
+//!
+//! The parser only keeps the last `LOOKAHEAD` characters. When the source text is valid,
+//! it never backtracks over more than a few characters, eg. the `-->` of an HTML-like
+//! comment. It can backtrack further while it is failing, eg. over a block comment that
+//! is never closed. That is still a syntax error, but its position and message can be
+//! wrong.
+
+use ast::{Program, SourceType};
+use combine::easy;
+use combine::stream::buffered::BufferedStream;
+use combine::stream::state::{SourcePosition, State};
+use combine::stream::IteratorStream;
+use combine::Parser;
+use failure::Error;
+use parser::{program, SyntaxError};
+use std::io::{self, BufReader, Bytes, Read};
+use std::str;
+
+/// The number of characters that the parser can backtrack over.
+pub const LOOKAHEAD: usize = 64 * 1024;
+
+/// This parses the source text from the reader, which has to be UTF-8. Reading errors and
+/// invalid UTF-8 are returned as an `io::Error`, and everything else is a `SyntaxError`.
+pub fn parse_reader<R: Read>(reader: R, source_type: SourceType) -> Result<Program, Error> {
+    let mut error = None;
+    let result = {
+        let chars = Chars {
+            bytes: BufReader::new(reader).bytes(),
+            error: &mut error,
+        };
+        let state = State::with_positioner(IteratorStream::new(chars), SourcePosition::new());
+        let stream = BufferedStream::new(easy::Stream(state), LOOKAHEAD);
+        program(source_type)
+            .parse(stream)
+            .map(|(program, _)| program)
+    };
+    // the source text ends at the first error, so it is reported instead of whatever the
+    // parser made of the rest
+    if let Some(error) = error {
+        return Err(error.into());
+    }
+    result.map_err(|errors| SyntaxError::from(errors).into())
+}
+
+/// Chars decodes UTF-8 one character at a time. It stops at the first error, and keeps
+/// it so it can be returned after parsing.
+struct Chars<'a, R> {
+    bytes: Bytes<R>,
+    error: &'a mut Option<io::Error>,
+}
+
+impl<'a, R: Read> Chars<'a, R> {
+    fn decode(&mut self) -> io::Result<Option<char>> {
+        let first = match self.bytes.next() {
+            Some(byte) => byte?,
+            None => return Ok(None),
+        };
+        let width = match first {
+            0x00..=0x7F => 1,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => return Err(invalid_utf8()),
+        };
+        let mut buffer = [first, 0, 0, 0];
+        for byte in &mut buffer[1..width] {
+            *byte = match self.bytes.next() {
+                Some(byte) => byte?,
+                None => return Err(invalid_utf8()),
+            };
+        }
+        match str::from_utf8(&buffer[..width]) {
+            Ok(s) => Ok(s.chars().next()),
+            Err(_) => Err(invalid_utf8()),
+        }
+    }
+}
+
+impl<'a, R: Read> Iterator for Chars<'a, R> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.decode() {
+            Ok(c) => c,
+            Err(error) => {
+                *self.error = Some(error);
+                None
+            }
+        }
+    }
+}
+
+// the same error that `fs::read_to_string` returns
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
+#[cfg(test)]
+mod read_tests {
+    use super::*;
+    use ast::CommentKind;
+    use parser::try_parse_as;
+
+    /// This returns the bytes one at a time, so every character is split across reads.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&byte, rest)) if !buffer.is_empty() => {
+                    buffer[0] = byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        let source = "#!node\n/* é\u{2028}😀 */ // a\n";
+        let program = parse_reader(Trickle(source.as_bytes()), SourceType::Module).unwrap();
+        assert_eq!(program, try_parse_as(source, SourceType::Module).unwrap());
+
+        // a line that could start with `-->` is not backtracked over
+        let source = format!("\n{}", " ".repeat(LOOKAHEAD + 1000));
+        let program = parse_reader(source.as_bytes(), SourceType::Script).unwrap();
+        assert_eq!(program, try_parse_as(&source, SourceType::Script).unwrap());
+        let source = format!("\n{}--> a", " ".repeat(LOOKAHEAD + 1000));
+        let program = parse_reader(source.as_bytes(), SourceType::Script).unwrap();
+        assert_eq!(program.comments[0].kind, CommentKind::HtmlClose);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = parse_reader("\n  a".as_bytes(), SourceType::Script).unwrap_err();
+        let syntax_error = error.downcast_ref::<SyntaxError>().unwrap();
+        assert_eq!(
+            syntax_error,
+            &try_parse_as("\n  a", SourceType::Script).unwrap_err()
+        );
+
+        let error = parse_reader(&b"// \xFF"[..], SourceType::Script).unwrap_err();
+        let io_error = error.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(io_error.kind(), io::ErrorKind::InvalidData);
+        // a character that is cut off at the end is invalid as well
+        assert!(parse_reader(&b"// \xC3"[..], SourceType::Script).is_err());
+    }
+}
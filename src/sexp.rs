@@ -0,0 +1,844 @@
+//! This module contains a compact S-expression format for syntax trees, and a parser for
+//! it. This is handy for golden file tests, where the derived `Debug` output is too
+//! verbose to read or diff, eg.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::sexp;
+//!
+//! let source = r#"(call (member (id console) (id log)) (str "hi"))"#;
+//! let expression = sexp::parse_expression(source).unwrap();
+//! assert_eq!(expression.to_sexp(), source);
+//! ```
+//!
+//! Every tree can be written and read back without losing anything. The forms are:
+//!
+//! - `this`, `super`, `new.target`, `null`, `true` and `false`
+//! - `(id name)`, `(num 1.5)`, `(str "text")` and `(regex "pattern" "flags")`, where a
+//!   number that was not written in decimal has its kind, eg. `(num 255.0 hex)`
+//! - `(array elements...)` and `(spread argument)`
+//! - `(object properties...)`, where a property is `(init key value)`, `(get key value)`
+//!   or `(set key value)`
+//! - `(function (name id) (params ids...) async generator)`, where the name and the
+//!   flags are optional
+//! - `(template elements...)`, where the strings are `(quasi "cooked" "raw")`, or
+//!   `(quasi "raw")` if the template was parsed without cooking it
+//! - `(member lhs rhs)` and `(computed-member lhs rhs)`
+//! - `(new callee arguments...)`, `(call callee arguments...)` and
+//!   `(tagged-template tag quasi)`
+//! - `(prefix ++ argument)`, `(postfix -- argument)`, `(unary typeof argument)`,
+//!   `(binary + lhs rhs)` and `(assign += lhs rhs)`
+//! - `(conditional test alternate consequent)` and `(comma expressions...)`
+//! - `(yield argument)` and `(yield* argument)`, where the argument is optional
+//! - `(jsx name (attributes ...) children...)`, where an attribute is
+//!   `(attr name value)` or `(spread-attr expression)`, and `(jsx-fragment children...)`
+//! - `(program script)` and `(program module)`
+//!
+//! The span, hashbang and comments of a program are not part of the tree, so they are
+//! not written. Neither is the raw text of a number or a string, eg. `1_000` or `'\x61'`,
+//! which is only how the literal was spelled.
+
+use ast::*;
+use combine::error::ParseError;
+use combine::parser::char::{spaces, string};
+use combine::parser::choice::choice;
+use combine::parser::error::unexpected;
+use combine::parser::item::{any, none_of, position, satisfy, token, value};
+use combine::parser::repeat::{many, many1};
+use combine::parser::sequence::between;
+use combine::stream::state::{SourcePosition, State};
+use combine::{eof, Parser, Stream};
+use parser::SyntaxError;
+use span::Span;
+use std::fmt::Write;
+
+/// Sexp is a parsed S-expression, before it is turned into a syntax tree.
+struct Sexp {
+    position: SourcePosition,
+    kind: SexpKind,
+}
+
+enum SexpKind {
+    Symbol(String),
+    Str(JsString),
+    List(Vec<Sexp>),
+}
+
+parser! {
+    fn sexp[I]()(I) -> Sexp
+    where [
+        I: Stream<Item = char, Position = SourcePosition>,
+        I::Error: ParseError<I::Item, I::Range, I::Position>,
+    ]
+    {
+        (
+            position(),
+            choice((
+                between(token('('), token(')'), spaces().with(many(sexp().skip(spaces()))))
+                    .map(SexpKind::List),
+                sexp_string().map(SexpKind::Str),
+                many1(satisfy(|c: char| !c.is_whitespace() && !"()\"".contains(c)))
+                    .map(SexpKind::Symbol),
+            )),
+        )
+            .map(|(position, kind)| Sexp { position, kind })
+    }
+}
+
+// the string is read as code points, since `\u{d800}` is allowed for lone surrogates
+fn sexp_string<I>() -> impl Parser<Input = I, Output = JsString>
+where
+    I: Stream<Item = char>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    between(
+        token('"'),
+        token('"'),
+        many::<Vec<_>, _>(choice((
+            token('\\').with(choice((
+                token('n').map(|_| u32::from('\n')),
+                token('r').map(|_| u32::from('\r')),
+                token('t').map(|_| u32::from('\t')),
+                between(
+                    string("u{"),
+                    token('}'),
+                    many1::<String, _>(satisfy(|c: char| c.is_ascii_hexdigit())),
+                )
+                .then(|digits| match u32::from_str_radix(&digits, 16) {
+                    Ok(code_point) if code_point <= 0x0010_FFFF => value(code_point).left(),
+                    _ => unexpected("escape")
+                        .map(|_| 0)
+                        .message("Invalid code point")
+                        .right(),
+                }),
+                any().map(u32::from),
+            ))),
+            none_of("\\\"".chars()).map(u32::from),
+        ))),
+    )
+    .map(JsString::from_code_points)
+}
+
+/// This parses an expression that was written by `Expression::to_sexp`.
+pub fn parse_expression(source: &str) -> Result<Expression, SyntaxError> {
+    expression(&read(source)?)
+}
+
+/// This parses a program that was written by `Program::to_sexp`.
+pub fn parse_program(source: &str) -> Result<Program, SyntaxError> {
+    let sexp = read(source)?;
+    let (head, args) = list(&sexp)?;
+    match (head, args) {
+        ("program", [ref source_type]) => Ok(Program {
+            source_type: match symbol(source_type)? {
+                "script" => SourceType::Script,
+                "module" => SourceType::Module,
+                _ => return Err(error(source_type, "Expected `script` or `module`")),
+            },
+            // there are no statements to read yet
+            body: Vec::new(),
+            span: Span::default(),
+            hashbang: None,
+            comments: Vec::new(),
+        }),
+        _ => Err(error(
+            &sexp,
+            "Expected `(program script)` or `(program module)`",
+        )),
+    }
+}
+
+fn read(source: &str) -> Result<Sexp, SyntaxError> {
+    let (sexp, _) = spaces()
+        .with(sexp())
+        .skip(spaces())
+        .skip(eof())
+        .easy_parse(State::new(source))?;
+    Ok(sexp)
+}
+
+fn error(sexp: &Sexp, message: &str) -> SyntaxError {
+    SyntaxError {
+        line: sexp.position.line,
+        column: sexp.position.column,
+        message: message.to_string(),
+        suggestion: None,
+    }
+}
+
+fn symbol(sexp: &Sexp) -> Result<&str, SyntaxError> {
+    match sexp.kind {
+        SexpKind::Symbol(ref symbol) => Ok(symbol),
+        _ => Err(error(sexp, "Expected a symbol")),
+    }
+}
+
+fn string_value(sexp: &Sexp) -> Result<String, SyntaxError> {
+    match js_string_value(sexp)?.as_str() {
+        Some(s) => Ok(s.to_string()),
+        None => Err(error(sexp, "Lone surrogates are only allowed in strings")),
+    }
+}
+
+fn js_string_value(sexp: &Sexp) -> Result<&JsString, SyntaxError> {
+    match sexp.kind {
+        SexpKind::Str(ref s) => Ok(s),
+        _ => Err(error(sexp, "Expected a string")),
+    }
+}
+
+fn number(sexp: &Sexp) -> Result<f64, SyntaxError> {
+    symbol(sexp)?
+        .parse()
+        .map_err(|_| error(sexp, "Expected a number"))
+}
+
+/// This splits a list into the symbol at the head, and the rest of the list.
+fn list(sexp: &Sexp) -> Result<(&str, &[Sexp]), SyntaxError> {
+    match sexp.kind {
+        SexpKind::List(ref items) if !items.is_empty() => Ok((symbol(&items[0])?, &items[1..])),
+        _ => Err(error(sexp, "Expected a list")),
+    }
+}
+
+fn expressions(sexps: &[Sexp]) -> Result<Vec<Expression>, SyntaxError> {
+    sexps.iter().map(expression).collect()
+}
+
+fn boxed(sexp: &Sexp) -> Result<Box<Expression>, SyntaxError> {
+    expression(sexp).map(Box::new)
+}
+
+fn operator<T: Clone>(sexp: &Sexp, operators: &[(T, &str)]) -> Result<T, SyntaxError> {
+    let symbol = symbol(sexp)?;
+    operators
+        .iter()
+        .find(|&&(_, token)| token == symbol)
+        .map(|(operator, _)| operator.clone())
+        .ok_or_else(|| error(sexp, "Unknown operator"))
+}
+
+fn expression(sexp: &Sexp) -> Result<Expression, SyntaxError> {
+    if let SexpKind::Symbol(ref symbol) = sexp.kind {
+        return match symbol.as_str() {
+            "this" => Ok(Expression::This),
+            "super" => Ok(Expression::Super),
+            "new.target" => Ok(Expression::MetaProperty),
+            "null" => Ok(Expression::Literal(ExpressionLiteral::NullLiteral(
+                NullLiteral,
+            ))),
+            "true" => Ok(Expression::Literal(ExpressionLiteral::BooleanLiteral(true))),
+            "false" => Ok(Expression::Literal(ExpressionLiteral::BooleanLiteral(
+                false,
+            ))),
+            _ => Err(error(sexp, "Unknown expression")),
+        };
+    }
+
+    let (head, args) = list(sexp)?;
+    let expression = match (head, args) {
+        ("id", [ref id]) => Expression::IdReference(symbol(id)?.into()),
+        ("num", [ref n]) => Expression::Literal(ExpressionLiteral::NumberLiteral(number(n)?.into())),
+        ("num", [ref n, ref kind]) => {
+            let kind = symbol(kind)?;
+            let kind = match NUMBER_KINDS.iter().find(|&&(_, name)| name == kind) {
+                Some(&(kind, _)) => kind,
+                None => return Err(error(sexp, "Unknown number kind")),
+            };
+            Expression::Literal(ExpressionLiteral::NumberLiteral(NumberLiteral {
+                value: number(n)?,
+                kind,
+                raw: None,
+            }))
+        }
+        ("str", [ref s]) => Expression::Literal(ExpressionLiteral::StringLiteral(Box::new(
+            js_string_value(s)?.clone().into(),
+        ))),
+        ("regex", [ref pattern, ref flags]) => Expression::RegexLiteral(Box::new(RegexLiteral {
+            pattern: string_value(pattern)?,
+            flags: string_value(flags)?,
+        })),
+        ("array", elements) => Expression::ArrayLiteral(expressions(elements)?),
+        ("object", properties) => Expression::ObjectLiteral(
+            properties
+                .iter()
+                .map(|property| match list(property)? {
+                    (kind, [ref key, ref value]) => Ok(Property {
+                        key: expression(key)?,
+                        value: expression(value)?,
+                        kind: match kind {
+                            "init" => PropertyKind::Init,
+                            "get" => PropertyKind::Get,
+                            "set" => PropertyKind::Set,
+                            _ => return Err(error(property, "Unknown property kind")),
+                        },
+                    }),
+                    _ => Err(error(property, "Expected `(kind key value)`")),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        ("function", args) => {
+            let mut function = Function {
+                id: None,
+                params: Vec::new(),
+                body: Vec::new(),
+                async: false,
+                generator: false,
+            };
+            for arg in args {
+                if let SexpKind::Symbol(ref flag) = arg.kind {
+                    match flag.as_str() {
+                        "async" => function.async = true,
+                        "generator" => function.generator = true,
+                        _ => return Err(error(arg, "Expected `async` or `generator`")),
+                    }
+                    continue;
+                }
+                match list(arg)? {
+                    ("name", [ref id]) => function.id = Some(symbol(id)?.into()),
+                    ("params", params) => {
+                        function.params = params
+                            .iter()
+                            .map(|param| symbol(param).map(Id::from))
+                            .collect::<Result<_, _>>()?
+                    }
+                    _ => return Err(error(arg, "Expected `(name id)` or `(params ...)`")),
+                }
+            }
+            Expression::Function(Box::new(function))
+        }
+        ("template", elements) => Expression::TemplateLiteral(
+            elements
+                .iter()
+                .map(|element| match list(element) {
+                    Ok(("quasi", [ref cooked, ref raw])) => {
+                        Ok(TemplateLiteralElement::TemplateElement(TemplateElement {
+                            cooked: Some(js_string_value(cooked)?.clone()),
+                            raw: string_value(raw)?,
+                        }))
+                    }
+                    Ok(("quasi", [ref raw])) => {
+                        Ok(TemplateLiteralElement::TemplateElement(TemplateElement {
+                            cooked: None,
+                            raw: string_value(raw)?,
+                        }))
+                    }
+                    _ => expression(element).map(TemplateLiteralElement::Expression),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        ("spread", [ref argument]) => Expression::Spread(boxed(argument)?),
+        ("member", [ref lhs, ref rhs]) => Expression::Member {
+            lhs: boxed(lhs)?,
+            rhs: boxed(rhs)?,
+            computed: false,
+        },
+        ("computed-member", [ref lhs, ref rhs]) => Expression::Member {
+            lhs: boxed(lhs)?,
+            rhs: boxed(rhs)?,
+            computed: true,
+        },
+        ("new", args) if !args.is_empty() => Expression::New(Box::new(New {
+            callee: expression(&args[0])?,
+            arguments: expressions(&args[1..])?,
+        })),
+        ("call", args) if !args.is_empty() => Expression::Call(Box::new(Call {
+            callee: expression(&args[0])?,
+            arguments: expressions(&args[1..])?,
+        })),
+        ("tagged-template", [ref tag, ref quasi]) => Expression::TaggedTemplate {
+            tag: boxed(tag)?,
+            quasi: boxed(quasi)?,
+        },
+        (fix, [ref op, ref argument]) if fix == "prefix" || fix == "postfix" => {
+            Expression::Update {
+                operator: operator(op, UPDATE_OPERATORS)?,
+                argument: boxed(argument)?,
+                prefix: fix == "prefix",
+            }
+        }
+        ("unary", [ref op, ref argument]) => Expression::Unary {
+            operator: operator(op, UNARY_OPERATORS)?,
+            argument: boxed(argument)?,
+        },
+        ("binary", [ref op, ref lhs, ref rhs]) => Expression::Binary {
+            operator: operator(op, BINARY_OPERATORS)?,
+            lhs: boxed(lhs)?,
+            rhs: boxed(rhs)?,
+        },
+        ("conditional", [ref test, ref alternate, ref consequent]) => Expression::Conditional {
+            test: boxed(test)?,
+            alternate: boxed(alternate)?,
+            consequent: boxed(consequent)?,
+        },
+        ("assign", [ref op, ref lhs, ref rhs]) => Expression::Assignment {
+            operator: operator(op, ASSIGNMENT_OPERATORS)?,
+            lhs: boxed(lhs)?,
+            rhs: boxed(rhs)?,
+        },
+        (keyword, args) if (keyword == "yield" || keyword == "yield*") && args.len() <= 1 => {
+            Expression::Yield {
+                argument: match args.first() {
+                    Some(argument) => Some(boxed(argument)?),
+                    None => None,
+                },
+                delegate: keyword == "yield*",
+            }
+        }
+        ("comma", args) => Expression::Comma(expressions(args)?),
+        ("jsx", args) if args.len() >= 2 => {
+            let attributes = match list(&args[1])? {
+                ("attributes", attributes) => attributes
+                    .iter()
+                    .map(|attribute| match list(attribute)? {
+                        ("attr", [ref name]) => Ok(JsxAttribute::JsxAttribute {
+                            name: symbol(name)?.into(),
+                            value: None,
+                        }),
+                        ("attr", [ref name, ref value]) => Ok(JsxAttribute::JsxAttribute {
+                            name: symbol(name)?.into(),
+                            value: Some(expression(value)?),
+                        }),
+                        ("spread-attr", [ref expression]) => Ok(JsxAttribute::JsxSpreadAttribute {
+                            expression: self::expression(expression)?,
+                        }),
+                        _ => Err(error(
+                            attribute,
+                            "Expected `(attr ...)` or `(spread-attr ...)`",
+                        )),
+                    })
+                    .collect::<Result<_, _>>()?,
+                _ => return Err(error(&args[1], "Expected `(attributes ...)`")),
+            };
+            Expression::JsxElement(Box::new(JsxElement {
+                name: symbol(&args[0])?.into(),
+                attributes,
+                children: expressions(&args[2..])?,
+            }))
+        }
+        ("jsx-fragment", children) => Expression::JsxFragment(expressions(children)?),
+        _ => return Err(error(sexp, "Unknown expression")),
+    };
+    Ok(expression)
+}
+
+fn write_string(out: &mut String, s: &str) {
+    write_code_points(out, s.chars().map(u32::from));
+}
+
+fn write_js_string(out: &mut String, s: &JsString) {
+    write_code_points(out, s.code_points());
+}
+
+fn write_code_points<I: Iterator<Item = u32>>(out: &mut String, code_points: I) {
+    out.push('"');
+    for code_point in code_points {
+        let c = match ::std::char::from_u32(code_point) {
+            Some(c) => c,
+            // a lone surrogate
+            None => {
+                let _ = write!(out, "\\u{{{:x}}}", code_point);
+                continue;
+            }
+        };
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            // line and paragraph separators are escaped too, so a string always stays on
+            // one line
+            c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+                let _ = write!(out, "\\u{{{:x}}}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_operator<T: PartialEq>(out: &mut String, operator: &T, operators: &[(T, &str)]) {
+    if let Some(&(_, token)) = operators.iter().find(|&(op, _)| op == operator) {
+        out.push_str(token);
+    }
+}
+
+fn write_list<'a, I>(out: &mut String, head: &str, expressions: I)
+where
+    I: IntoIterator<Item = &'a Expression>,
+{
+    out.push('(');
+    out.push_str(head);
+    for expression in expressions {
+        out.push(' ');
+        write_expression(out, expression);
+    }
+    out.push(')');
+}
+
+fn write_expression(out: &mut String, expression: &Expression) {
+    match *expression {
+        Expression::This => out.push_str("this"),
+        Expression::IdReference(ref id) => {
+            let _ = write!(out, "(id {})", id);
+        }
+        Expression::Literal(ref literal) => match *literal {
+            ExpressionLiteral::NullLiteral(_) => out.push_str("null"),
+            ExpressionLiteral::BooleanLiteral(b) => out.push_str(if b { "true" } else { "false" }),
+            ExpressionLiteral::NumberLiteral(ref n) => {
+                let _ = write!(out, "(num {:?}", n.value);
+                if let Some(&(_, name)) = NUMBER_KINDS.iter().find(|&&(kind, _)| kind == n.kind) {
+                    let _ = write!(out, " {}", name);
+                }
+                out.push(')');
+            }
+            ExpressionLiteral::StringLiteral(ref s) => {
+                out.push_str("(str ");
+                write_js_string(out, &s.value);
+                out.push(')');
+            }
+        },
+        Expression::ArrayLiteral(ref elements) => write_list(out, "array", elements),
+        Expression::ObjectLiteral(ref properties) => {
+            out.push_str("(object");
+            for property in properties {
+                out.push_str(match property.kind {
+                    PropertyKind::Init => " (init ",
+                    PropertyKind::Get => " (get ",
+                    PropertyKind::Set => " (set ",
+                });
+                write_expression(out, &property.key);
+                out.push(' ');
+                write_expression(out, &property.value);
+                out.push(')');
+            }
+            out.push(')');
+        }
+        Expression::Function(ref function) => {
+            out.push_str("(function");
+            if let Some(ref id) = function.id {
+                let _ = write!(out, " (name {})", id);
+            }
+            out.push_str(" (params");
+            for param in &function.params {
+                out.push(' ');
+                out.push_str(param);
+            }
+            out.push(')');
+            if function.async {
+                out.push_str(" async");
+            }
+            if function.generator {
+                out.push_str(" generator");
+            }
+            out.push(')');
+        }
+        Expression::RegexLiteral(ref regex) => {
+            out.push_str("(regex ");
+            write_string(out, &regex.pattern);
+            out.push(' ');
+            write_string(out, &regex.flags);
+            out.push(')');
+        }
+        Expression::TemplateLiteral(ref elements) => {
+            out.push_str("(template");
+            for element in elements {
+                out.push(' ');
+                match *element {
+                    TemplateLiteralElement::TemplateElement(ref element) => {
+                        out.push_str("(quasi ");
+                        if let Some(ref cooked) = element.cooked {
+                            write_js_string(out, cooked);
+                            out.push(' ');
+                        }
+                        write_string(out, &element.raw);
+                        out.push(')');
+                    }
+                    TemplateLiteralElement::Expression(ref expression) => {
+                        write_expression(out, expression)
+                    }
+                }
+            }
+            out.push(')');
+        }
+        Expression::Spread(ref argument) => write_list(out, "spread", Some(&**argument)),
+        Expression::Member {
+            ref lhs,
+            ref rhs,
+            computed,
+        } => write_list(
+            out,
+            if computed {
+                "computed-member"
+            } else {
+                "member"
+            },
+            vec![&**lhs, &**rhs],
+        ),
+        Expression::Super => out.push_str("super"),
+        Expression::MetaProperty => out.push_str("new.target"),
+        Expression::New(ref new) => write_list(
+            out,
+            "new",
+            Some(&new.callee).into_iter().chain(&new.arguments),
+        ),
+        Expression::Call(ref call) => write_list(
+            out,
+            "call",
+            Some(&call.callee).into_iter().chain(&call.arguments),
+        ),
+        Expression::TaggedTemplate { ref tag, ref quasi } => {
+            write_list(out, "tagged-template", vec![&**tag, &**quasi])
+        }
+        Expression::Update {
+            ref operator,
+            ref argument,
+            prefix,
+        } => {
+            out.push_str(if prefix { "(prefix " } else { "(postfix " });
+            write_operator(out, operator, UPDATE_OPERATORS);
+            out.push(' ');
+            write_expression(out, argument);
+            out.push(')');
+        }
+        Expression::Unary {
+            ref operator,
+            ref argument,
+        } => {
+            out.push_str("(unary ");
+            write_operator(out, operator, UNARY_OPERATORS);
+            out.push(' ');
+            write_expression(out, argument);
+            out.push(')');
+        }
+        Expression::Binary {
+            ref operator,
+            ref lhs,
+            ref rhs,
+        } => {
+            out.push_str("(binary ");
+            write_operator(out, operator, BINARY_OPERATORS);
+            out.push(' ');
+            write_expression(out, lhs);
+            out.push(' ');
+            write_expression(out, rhs);
+            out.push(')');
+        }
+        Expression::Conditional {
+            ref test,
+            ref alternate,
+            ref consequent,
+        } => write_list(
+            out,
+            "conditional",
+            vec![&**test, &**alternate, &**consequent],
+        ),
+        Expression::Assignment {
+            ref operator,
+            ref lhs,
+            ref rhs,
+        } => {
+            out.push_str("(assign ");
+            write_operator(out, operator, ASSIGNMENT_OPERATORS);
+            out.push(' ');
+            write_expression(out, lhs);
+            out.push(' ');
+            write_expression(out, rhs);
+            out.push(')');
+        }
+        Expression::Yield {
+            ref argument,
+            delegate,
+        } => write_list(
+            out,
+            if delegate { "yield*" } else { "yield" },
+            argument.as_ref().map(|argument| &**argument),
+        ),
+        Expression::Comma(ref expressions) => write_list(out, "comma", expressions),
+        Expression::JsxElement(ref element) => {
+            let _ = write!(out, "(jsx {} (attributes", element.name);
+            for attribute in &element.attributes {
+                match *attribute {
+                    JsxAttribute::JsxSpreadAttribute { ref expression } => {
+                        out.push(' ');
+                        write_list(out, "spread-attr", Some(expression));
+                    }
+                    JsxAttribute::JsxAttribute {
+                        ref name,
+                        ref value,
+                    } => {
+                        let _ = write!(out, " (attr {}", name);
+                        if let Some(ref value) = *value {
+                            out.push(' ');
+                            write_expression(out, value);
+                        }
+                        out.push(')');
+                    }
+                }
+            }
+            out.push(')');
+            for child in &element.children {
+                out.push(' ');
+                write_expression(out, child);
+            }
+            out.push(')');
+        }
+        Expression::JsxFragment(ref children) => write_list(out, "jsx-fragment", children),
+    }
+}
+
+impl Program {
+    /// This writes the program as an S-expression, which can be read back with
+    /// `sexp::parse_program`.
+    pub fn to_sexp(&self) -> String {
+        match self.source_type {
+            SourceType::Script => "(program script)".to_string(),
+            SourceType::Module => "(program module)".to_string(),
+        }
+    }
+}
+
+impl Expression {
+    /// This writes the expression as an S-expression on a single line, which can be read
+    /// back with `sexp::parse_expression`.
+    pub fn to_sexp(&self) -> String {
+        let mut out = String::new();
+        write_expression(&mut out, self);
+        out
+    }
+}
+
+const UPDATE_OPERATORS: &[(UpdateOperator, &str)] = &[
+    (UpdateOperator::Increment, "++"),
+    (UpdateOperator::Decrement, "--"),
+];
+
+const UNARY_OPERATORS: &[(UnaryOperator, &str)] = &[
+    (UnaryOperator::Minus, "-"),
+    (UnaryOperator::Plus, "+"),
+    (UnaryOperator::Not, "!"),
+    (UnaryOperator::BitwiseNot, "~"),
+    (UnaryOperator::Typeof, "typeof"),
+    (UnaryOperator::Void, "void"),
+    (UnaryOperator::Delete, "delete"),
+];
+
+const BINARY_OPERATORS: &[(BinaryOperator, &str)] = &[
+    (BinaryOperator::EqEq, "=="),
+    (BinaryOperator::NotEq, "!="),
+    (BinaryOperator::EqEqEq, "==="),
+    (BinaryOperator::NotEqEq, "!=="),
+    (BinaryOperator::Lt, "<"),
+    (BinaryOperator::Lte, "<="),
+    (BinaryOperator::Gt, ">"),
+    (BinaryOperator::Gte, ">="),
+    (BinaryOperator::Shl, "<<"),
+    (BinaryOperator::Shr, ">>"),
+    (BinaryOperator::UnsignedShr, ">>>"),
+    (BinaryOperator::Plus, "+"),
+    (BinaryOperator::Minus, "-"),
+    (BinaryOperator::Multiply, "*"),
+    (BinaryOperator::Divide, "/"),
+    (BinaryOperator::Mod, "%"),
+    (BinaryOperator::BitwiseOr, "|"),
+    (BinaryOperator::Or, "||"),
+    (BinaryOperator::BitwiseXor, "^"),
+    (BinaryOperator::BitwiseAnd, "&"),
+    (BinaryOperator::And, "&&"),
+    (BinaryOperator::In, "in"),
+    (BinaryOperator::InstanceOf, "instanceof"),
+    (BinaryOperator::Exponentiation, "**"),
+];
+
+// decimal numbers are written without a kind
+const NUMBER_KINDS: &[(NumberKind, &str)] = &[
+    (NumberKind::Binary, "binary"),
+    (NumberKind::Octal, "octal"),
+    (NumberKind::Hex, "hex"),
+    (NumberKind::LegacyOctal, "legacy-octal"),
+];
+
+const ASSIGNMENT_OPERATORS: &[(AssignmentOperator, &str)] = &[
+    (AssignmentOperator::Eq, "="),
+    (AssignmentOperator::PlusEq, "+="),
+    (AssignmentOperator::MinusEq, "-="),
+    (AssignmentOperator::MultiplyEq, "*="),
+    (AssignmentOperator::DivideEq, "/="),
+    (AssignmentOperator::ModEq, "%="),
+    (AssignmentOperator::ShlEq, "<<="),
+    (AssignmentOperator::ShrEq, ">>="),
+    (AssignmentOperator::UnsignedShrEq, ">>>="),
+    (AssignmentOperator::BitwiseOrEq, "|="),
+    (AssignmentOperator::BitwiseXorEq, "^="),
+    (AssignmentOperator::BitwiseAndEq, "&="),
+];
+
+#[cfg(test)]
+mod sexp_tests {
+    use super::*;
+
+    fn round_trip(source: &str) {
+        let expression = parse_expression(source).unwrap();
+        assert_eq!(expression.to_sexp(), source);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(r#"(call (member (id console) (id log)) (str "hi"))"#);
+        round_trip("(array this super new.target null true false (num 1.5) (num -0.0))");
+        round_trip(r#"(object (init (id a) (num 1.0)) (get (str "b\n\"") (function (params))))"#);
+        round_trip("(function (name f) (params a b) async generator)");
+        round_trip(r#"(template (quasi "a\n" "a\\n") (id b) (quasi "" ""))"#);
+        round_trip(r#"(regex "a[b]" "gi")"#);
+        round_trip("(array (num 255.0 hex) (num 2.0 binary) (num 8.0 octal) (num 15.0 legacy-octal))");
+        round_trip(r#"(template (quasi "\u{d800}" "\\uD800") (str "a\u{dc00}"))"#);
+        round_trip("(computed-member (spread (id a)) (new (id B)))");
+        round_trip("(tagged-template (id tag) (template (quasi \"\" \"\")))");
+        round_trip(r#"(template (quasi "\\u{41}"))"#);
+        round_trip("(comma (prefix ++ (id a)) (postfix -- (id b)) (unary typeof (id c)))");
+        round_trip("(binary >>> (id a) (assign &= (id b) (id c)))");
+        round_trip("(conditional (yield) (yield* (id a)) (yield (id b)))");
+        round_trip("(jsx div (attributes (attr a) (attr b (str \"c\")) (spread-attr (id d))) (jsx-fragment))");
+    }
+
+    #[test]
+    fn test_whitespace() {
+        assert_eq!(
+            parse_expression("  ( call\n\t(id f)  (num 1) )\n").unwrap(),
+            build_ast!(call [id "f"] [[num 1.0]])
+        );
+        assert_eq!(
+            parse_expression(r#"(str "\u{1F600}\u{7}\u{2028}")"#)
+                .unwrap()
+                .to_sexp(),
+            "(str \"\u{1F600}\\u{7}\\u{2028}\")"
+        );
+    }
+
+    #[test]
+    fn test_program() {
+        let program = parse_program("(program script)").unwrap();
+        assert_eq!(program.source_type, SourceType::Script);
+        assert_eq!(program.to_sexp(), "(program script)");
+        assert!(parse_program("(program)").is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = parse_expression("(array\n  (id a b))").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+        assert_eq!(error.message, "Unknown expression");
+
+        let error = parse_expression("(binary ?? (id a) (id b))").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 9));
+        assert_eq!(error.message, "Unknown operator");
+
+        assert!(parse_expression("(id a").is_err());
+        assert!(parse_expression("(num x)").is_err());
+        assert!(parse_expression("(str \"\\u{110000}\")").is_err());
+        // lone surrogates are only allowed where ECMAScript allows them
+        let error = parse_expression("(regex \"\\u{D800}\" \"\")").unwrap_err();
+        assert_eq!(error.message, "Lone surrogates are only allowed in strings");
+    }
+}
@@ -0,0 +1,193 @@
+//! This module keeps track of the source text of many files, so a span can say which
+//! file it is from, eg.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::source_map::SourceMap;
+//! use ecmascript::span::Span;
+//!
+//! let mut map = SourceMap::new();
+//! map.add("a.js", "let a = 1;");
+//! let b = map.add("b.js", "let b;\nfoo(bar);");
+//! assert_eq!(map.text(b.span(Span::new(11, 14))), "bar");
+//! assert_eq!(
+//!     map.report(b.span(Span::new(11, 14)), "bar is not defined"),
+//!     "b.js:2:5: bar is not defined\nfoo(bar);\n    ^^^\n"
+//! );
+//! ```
+
+use position::{Encoding, LineIndex};
+use span::{source_lines, source_text, Span, Spanned};
+use std::path::{Path, PathBuf};
+
+/// FileId identifies a file that was added to a `SourceMap`. It is only meaningful for the
+/// source map that created it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(u32);
+
+impl FileId {
+    /// This attaches the file to a span of its source text.
+    pub fn span(self, span: Span) -> FileSpan {
+        FileSpan { file: self, span }
+    }
+}
+
+/// FileSpan is a span of the source text of a particular file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileSpan {
+    /// The file that the span is in.
+    pub file: FileId,
+    /// The span in the source text of the file.
+    pub span: Span,
+}
+
+impl Spanned for FileSpan {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// SourceFile is a file that was added to a `SourceMap`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    /// The path of the file, as it should be shown in diagnostics.
+    pub path: PathBuf,
+    /// The source text of the file.
+    pub source: String,
+}
+
+/// SourceMap owns the source text of every file in a project.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    /// This creates an empty source map.
+    pub fn new() -> SourceMap {
+        SourceMap::default()
+    }
+
+    /// This adds a file and returns its id. Adding the same path twice adds a second
+    /// file, eg. for a newer version of the source text.
+    pub fn add<P: Into<PathBuf>, S: Into<String>>(&mut self, path: P, source: S) -> FileId {
+        self.files.push(SourceFile {
+            path: path.into(),
+            source: source.into(),
+        });
+        FileId(self.files.len() as u32 - 1)
+    }
+
+    /// This returns the file with the id.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the id is from a different source map.
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0 as usize]
+    }
+
+    /// The path of the file with the id.
+    pub fn path(&self, id: FileId) -> &Path {
+        &self.file(id).path
+    }
+
+    /// The source text of the file with the id.
+    pub fn source(&self, id: FileId) -> &str {
+        &self.file(id).source
+    }
+
+    /// This returns the most recently added file with the path, if there is one.
+    pub fn find<P: AsRef<Path>>(&self, path: P) -> Option<FileId> {
+        self.files
+            .iter()
+            .rposition(|file| file.path == path.as_ref())
+            .map(|i| FileId(i as u32))
+    }
+
+    /// This iterates over the ids of every file, in the order they were added.
+    pub fn ids(&self) -> impl Iterator<Item = FileId> {
+        (0..self.files.len() as u32).map(FileId)
+    }
+
+    /// The source text that the span covers.
+    pub fn text(&self, span: FileSpan) -> &str {
+        source_text(&span, self.source(span.file))
+    }
+
+    /// The path, line and column of the start of the span, eg. `src/a.js:2:5`. The line
+    /// and column are 1 based, and the column is counted in characters.
+    pub fn location(&self, span: FileSpan) -> String {
+        let index = LineIndex::new(self.source(span.file));
+        let position = index.position(span.span.start, Encoding::CodePoint);
+        format!(
+            "{}:{}:{}",
+            self.path(span.file).display(),
+            position.line + 1,
+            position.character + 1
+        )
+    }
+
+    /// This formats a message about the span for a terminal. The first line has the
+    /// location and the message, and it is followed by the source line with the start of
+    /// the span underlined.
+    pub fn report(&self, span: FileSpan, message: &str) -> String {
+        let source = self.source(span.file);
+        let line = source_lines(&Span::empty(span.span.start), source);
+        let index = LineIndex::new(source);
+        let start = index.position(span.span.start, Encoding::CodePoint);
+        let end = index.position(span.span.end, Encoding::CodePoint);
+        let width = if end.line == start.line {
+            end.character - start.character
+        } else {
+            line.chars().count() as u32 - start.character
+        };
+        format!(
+            "{}: {}\n{}\n{}{}\n",
+            self.location(span),
+            message,
+            line,
+            " ".repeat(start.character as usize),
+            "^".repeat(width.max(1) as usize)
+        )
+    }
+}
+
+#[cfg(test)]
+mod source_map_tests {
+    use super::*;
+
+    #[test]
+    fn test_files() {
+        let mut map = SourceMap::new();
+        let a = map.add("a.js", "a");
+        let b = map.add("b.js", "b");
+        let newer = map.add("a.js", "aa");
+        assert_ne!(a, newer);
+        assert_eq!(map.find("a.js"), Some(newer));
+        assert_eq!(map.find("b.js"), Some(b));
+        assert_eq!(map.find("c.js"), None);
+        assert_eq!(map.ids().collect::<Vec<_>>(), vec![a, b, newer]);
+        assert_eq!(map.source(a), "a");
+        assert_eq!(map.path(b), Path::new("b.js"));
+    }
+
+    #[test]
+    fn test_report() {
+        let mut map = SourceMap::new();
+        let file = map.add("src/x.js", "let é = 1;\nif (a) {\n  b;\n}");
+        assert_eq!(map.location(file.span(Span::new(4, 6))), "src/x.js:1:5");
+        assert_eq!(
+            map.report(file.span(Span::new(9, 10)), "unexpected number"),
+            "src/x.js:1:9: unexpected number\nlet é = 1;\n        ^\n"
+        );
+        assert_eq!(
+            map.report(file.span(Span::new(19, 27)), "empty"),
+            "src/x.js:2:8: empty\nif (a) {\n       ^\n"
+        );
+        assert_eq!(
+            map.report(file.span(Span::new(12, 12)), "here"),
+            "src/x.js:2:1: here\nif (a) {\n^\n"
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! This module decides whether a file is a script or a module from its path, following
+//! the rules that Node.js uses, eg.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::ast::SourceType;
+//!
+//! assert_eq!(SourceType::from_path("index.mjs"), SourceType::Module);
+//! assert_eq!(SourceType::from_path("index.cjs"), SourceType::Script);
+//! ```
+//!
+//! - `.mjs` files are modules and `.cjs` files are scripts.
+//! - `.js` files are modules if the nearest `package.json` above them has
+//!   `"type": "module"`, and scripts otherwise.
+//! - Any other file is a script.
+//!
+//! [Reference](https://nodejs.org/api/packages.html#determining-module-system)
+
+use ast::*;
+use json::{parse_json, Dialect};
+use std::fs;
+use std::path::Path;
+
+impl SourceType {
+    /// This picks the source type for the file at the path. A `package.json` that can
+    /// not be read or parsed is treated as if it had no `"type"` field.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> SourceType {
+        let path = path.as_ref();
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("mjs") => SourceType::Module,
+            Some("js") => match package_type(path).as_deref() {
+                Some("module") => SourceType::Module,
+                _ => SourceType::Script,
+            },
+            _ => SourceType::Script,
+        }
+    }
+}
+
+/// The `"type"` field of the nearest `package.json` above the path. The search stops at
+/// the first `package.json`, even if it has no `"type"` field or can not be read.
+fn package_type(path: &Path) -> Option<String> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let package = path
+        .ancestors()
+        .skip(1)
+        .map(|directory| directory.join("package.json"))
+        .find(|package| package.is_file())?;
+    let source = fs::read_to_string(package).ok()?;
+    let properties = match parse_json(&source, Dialect::Json).ok()? {
+        Expression::ObjectLiteral(properties) => properties,
+        _ => return None,
+    };
+    fn string(expression: &Expression) -> Option<&str> {
+        match *expression {
+            Expression::Literal(ExpressionLiteral::StringLiteral(ref s)) => s.value.as_str(),
+            _ => None,
+        }
+    }
+    // like `JSON.parse`, the last of the fields with the same name wins
+    let property = properties
+        .iter()
+        .rev()
+        .find(|property| string(&property.key) == Some("type"))?;
+    string(&property.value).map(str::to_string)
+}
+
+#[cfg(test)]
+mod source_type_tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        let root =
+            ::std::env::temp_dir().join(format!("ecma-source-type-{}", ::std::process::id()));
+        let esm = root.join("esm");
+        let nested = esm.join("lib").join("cjs");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(
+            esm.join("package.json"),
+            r#"{ "name": "a", "version": "1.0.0", "files": ["x", {"y": 1.5e3}], "private": true, "type": "module" }"#,
+        )
+        .unwrap();
+        fs::write(nested.join("package.json"), r#"{"name": "b"}"#).unwrap();
+
+        assert_eq!(SourceType::from_path(esm.join("a.js")), SourceType::Module);
+        assert_eq!(
+            SourceType::from_path(esm.join("lib").join("a.js")),
+            SourceType::Module
+        );
+        assert_eq!(SourceType::from_path(esm.join("a.cjs")), SourceType::Script);
+        assert_eq!(SourceType::from_path(esm.join("a.jsx")), SourceType::Script);
+        // the nearest package.json wins, even without a type
+        assert_eq!(
+            SourceType::from_path(nested.join("a.js")),
+            SourceType::Script
+        );
+        assert_eq!(
+            SourceType::from_path(nested.join("a.mjs")),
+            SourceType::Module
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_package_type() {
+        let root =
+            ::std::env::temp_dir().join(format!("ecma-package-type-{}", ::std::process::id()));
+        let package = root.join("a");
+        let nested = package.join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(
+            package.join("package.json"),
+            r#"{"type": "commonjs", "type": "mod\u0075le"}"#,
+        )
+        .unwrap();
+        // a directory named package.json is not a package
+        fs::create_dir_all(nested.join("package.json")).unwrap();
+        assert_eq!(
+            package_type(&nested.join("a.js")),
+            Some("module".to_string())
+        );
+        // a package.json that does not parse has no type
+        fs::write(package.join("package.json"), r#"{"type": "module""#).unwrap();
+        assert_eq!(package_type(&nested.join("a.js")), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+//! This module contains the Span type, which represents a range of the source text.
+//!
+//! Spans are byte offsets into the source, stored as u32 values to keep them small.
+//! This limits sources to 4GiB, which is far more than any real program needs.
+//!
+//! `source_text` and `source_lines` slice the original source with the span of anything
+//! that implements `Spanned`.
+
+use parser::is_line_terminator;
+use std::ops::Range;
+
+/// Span is a half open range of byte offsets, `start..end`, into the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub struct Span {
+    /// The offset of the first byte in the span.
+    pub start: u32,
+    /// The offset one past the last byte in the span.
+    pub end: u32,
+}
+
+impl Span {
+    /// This creates a span from `start` to `end`. If `end` is before `start`, they are
+    /// swapped so the span is always valid.
+    pub fn new(start: u32, end: u32) -> Span {
+        if end < start {
+            Span {
+                start: end,
+                end: start,
+            }
+        } else {
+            Span { start, end }
+        }
+    }
+
+    /// This creates an empty span at an offset, eg. for an insertion point.
+    pub fn empty(offset: u32) -> Span {
+        Span {
+            start: offset,
+            end: offset,
+        }
+    }
+
+    /// The length of the span, in bytes.
+    pub fn len(&self) -> u32 {
+        self.end - self.start
+    }
+
+    /// This is true if the span does not cover any bytes.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// This is true if the offset is inside the span.
+    pub fn contains(&self, offset: u32) -> bool {
+        self.start <= offset && offset < self.end
+    }
+
+    /// This is true if the two spans share at least one byte.
+    pub fn overlaps(&self, other: Span) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
+    /// This returns the smallest span that covers both spans.
+    pub fn to(&self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    /// This returns the span as a range, so it can be used to slice the source text.
+    pub fn range(&self) -> Range<usize> {
+        self.start as usize..self.end as usize
+    }
+
+    /// This returns a part of the span, with offsets relative to the start of the span.
+    /// The result is clamped so it never reaches outside of the span.
+    pub fn relative(&self, range: Range<u32>) -> Span {
+        let start = self.start.saturating_add(range.start).min(self.end);
+        let end = self.start.saturating_add(range.end).min(self.end);
+        Span::new(start, end)
+    }
+}
+
+impl From<Range<u32>> for Span {
+    fn from(range: Range<u32>) -> Span {
+        Span::new(range.start, range.end)
+    }
+}
+
+/// Spanned is implemented by anything that knows which part of the source text it
+/// came from.
+pub trait Spanned {
+    /// The span of the source text that this was parsed from.
+    fn span(&self) -> Span;
+}
+
+impl Spanned for Span {
+    fn span(&self) -> Span {
+        *self
+    }
+}
+
+/// This returns the exact source text of a node, eg. to quote the offending code in an
+/// error message. If the span does not fit the source, because it came from a different
+/// source text, an empty string is returned.
+pub fn source_text<'a, T: Spanned + ?Sized>(node: &T, source: &'a str) -> &'a str {
+    source.get(node.span().range()).unwrap_or("")
+}
+
+/// This returns the whole lines that a node is on, without the final line terminator.
+/// This is useful to show the node in context, eg. with a `^^^` underline.
+pub fn source_lines<'a, T: Spanned + ?Sized>(node: &T, source: &'a str) -> &'a str {
+    let range = node.span().range();
+    if source.get(range.clone()).is_none() {
+        return "";
+    }
+    let start = source[..range.start]
+        .rfind(is_line_terminator)
+        .map_or(0, |i| {
+            i + source[i..].chars().next().map_or(1, char::len_utf8)
+        });
+    // a span that ends with a line terminator does not include the next line
+    let text = source[range.clone()].trim_end_matches(is_line_terminator);
+    let end_search = range.start + text.len();
+    let end = source[end_search..]
+        .find(is_line_terminator)
+        .map_or(source.len(), |i| end_search + i);
+    &source[start..end]
+}
+
+#[cfg(test)]
+mod span_tests {
+    use super::*;
+    use std::mem::size_of;
+
+    #[test]
+    fn test_size() {
+        assert_eq!(size_of::<Span>(), 8);
+        assert_eq!(size_of::<Option<Span>>(), 12);
+    }
+
+    #[test]
+    fn test_span() {
+        let span = Span::new(5, 2);
+        assert_eq!(span, Span::from(2..5));
+        assert_eq!(span.len(), 3);
+        assert!(span.contains(2));
+        assert!(!span.contains(5));
+        assert!(Span::empty(3).is_empty());
+        assert!(span.overlaps(Span::new(4, 8)));
+        assert!(!span.overlaps(Span::new(5, 8)));
+        assert_eq!(span.to(Span::new(7, 9)), Span::new(2, 9));
+        assert_eq!(&"abcdef"[span.range()], "cde");
+    }
+
+    #[test]
+    fn test_source_text() {
+        let source = "let a = 1;\nfoo(bar, baz);\r\nqux";
+        let span = Span::new(11, 25);
+        assert_eq!(source_text(&span, source), "foo(bar, baz);");
+        assert_eq!(source_text(&span.relative(4..7), source), "bar");
+        assert_eq!(source_text(&span.relative(9..100), source), "baz);");
+        assert_eq!(source_text(&Span::new(30, 40), source), "");
+
+        assert_eq!(source_lines(&span.relative(4..7), source), "foo(bar, baz);");
+        assert_eq!(
+            source_lines(&Span::new(8, 15), source),
+            "let a = 1;\nfoo(bar, baz);"
+        );
+        assert_eq!(source_lines(&Span::new(11, 27), source), "foo(bar, baz);");
+        assert_eq!(source_lines(&Span::new(28, 28), source), "qux");
+        assert_eq!(source_lines(&Span::new(0, 0), source), "let a = 1;");
+    }
+}
@@ -0,0 +1,338 @@
+//! This module contains an API to measure the size of a syntax tree, eg.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! let program = ecmascript::parse("").unwrap();
+//! let stats = program.stats();
+//! assert_eq!(stats.total_nodes(), 0);
+//! ```
+//!
+//! This can be used to profile memory, or to reject pathological inputs (eg. very deeply
+//! nested expressions) before running an expensive pass over the tree.
+
+use ast::*;
+use std::collections::BTreeMap;
+use std::mem::size_of;
+
+/// Stats is a summary of the nodes in a syntax tree.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    /// The number of nodes of each kind, keyed by the name of the variant or struct,
+    /// eg. `"Binary"` or `"Property"`.
+    pub node_counts: BTreeMap<&'static str, usize>,
+    /// The depth of the deepest node. A tree with only a root node has a depth of 1.
+    pub max_depth: usize,
+    /// An estimate of the bytes allocated on the heap by the tree, using the capacity of
+    /// vectors and strings, and the length of boxed strings and string values, which have
+    /// no spare capacity that can be seen. Identifiers are interned and shared, so they
+    /// are not counted.
+    pub heap_bytes: usize,
+}
+
+impl Stats {
+    /// The total number of nodes in the tree.
+    pub fn total_nodes(&self) -> usize {
+        self.node_counts.values().sum()
+    }
+
+    fn node(&mut self, kind: &'static str, depth: usize) {
+        *self.node_counts.entry(kind).or_insert(0) += 1;
+        self.max_depth = self.max_depth.max(depth);
+    }
+
+    fn vec<T>(&mut self, vec: &Vec<T>) {
+        self.heap_bytes += vec.capacity() * size_of::<T>();
+    }
+
+    fn boxed<T>(&mut self, _: &T) {
+        self.heap_bytes += size_of::<T>();
+    }
+
+    fn string(&mut self, string: &String) {
+        self.heap_bytes += string.capacity();
+    }
+
+    fn boxed_str(&mut self, string: &str) {
+        self.heap_bytes += string.len();
+    }
+
+    // a string with lone surrogates is stored as UTF-16 code units
+    fn js_string(&mut self, string: &JsString) {
+        match string.as_str() {
+            Some(s) => self.boxed_str(s),
+            None => self.heap_bytes += string.len_utf16() * 2,
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression, depth: usize) {
+        match *expression {
+            Expression::This => self.node("This", depth),
+            Expression::IdReference(_) => self.node("IdReference", depth),
+            Expression::Literal(ref literal) => {
+                self.node("Literal", depth);
+                match *literal {
+                    ExpressionLiteral::NumberLiteral(ref n) => {
+                        if let Some(ref raw) = n.raw {
+                            self.boxed_str(raw);
+                        }
+                    }
+                    ExpressionLiteral::StringLiteral(ref s) => {
+                        self.boxed(&**s);
+                        self.js_string(&s.value);
+                        if let Some(ref raw) = s.raw {
+                            self.boxed_str(raw);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Expression::ArrayLiteral(ref elements) => {
+                self.node("ArrayLiteral", depth);
+                self.expressions(elements, depth + 1);
+            }
+            Expression::ObjectLiteral(ref properties) => {
+                self.node("ObjectLiteral", depth);
+                self.vec(properties);
+                for property in properties {
+                    self.node("Property", depth + 1);
+                    self.expression(&property.key, depth + 2);
+                    self.expression(&property.value, depth + 2);
+                }
+            }
+            Expression::Function(ref function) => {
+                self.node("Function", depth);
+                self.boxed(&**function);
+                self.vec(&function.params);
+                self.vec(&function.body);
+                for statement in &function.body {
+                    self.statement(statement, depth + 1);
+                }
+            }
+            Expression::RegexLiteral(ref regex) => {
+                self.node("RegexLiteral", depth);
+                self.boxed(&**regex);
+                self.string(&regex.pattern);
+                self.string(&regex.flags);
+            }
+            Expression::TemplateLiteral(ref elements) => {
+                self.node("TemplateLiteral", depth);
+                self.vec(elements);
+                for element in elements {
+                    match *element {
+                        TemplateLiteralElement::TemplateElement(ref element) => {
+                            self.node("TemplateElement", depth + 1);
+                            // an element that was not cooked has no cooked value allocated
+                            if let Some(ref cooked) = element.cooked {
+                                self.js_string(cooked);
+                            }
+                            self.string(&element.raw);
+                        }
+                        TemplateLiteralElement::Expression(ref expression) => {
+                            self.expression(expression, depth + 1)
+                        }
+                    }
+                }
+            }
+            Expression::Spread(ref argument) => {
+                self.node("Spread", depth);
+                self.child(argument, depth);
+            }
+            Expression::Member {
+                ref lhs, ref rhs, ..
+            } => {
+                self.node("Member", depth);
+                self.child(lhs, depth);
+                self.child(rhs, depth);
+            }
+            Expression::Super => self.node("Super", depth),
+            Expression::MetaProperty => self.node("MetaProperty", depth),
+            Expression::New(ref new) => {
+                self.node("New", depth);
+                self.boxed(&**new);
+                self.expression(&new.callee, depth + 1);
+                self.expressions(&new.arguments, depth + 1);
+            }
+            Expression::Call(ref call) => {
+                self.node("Call", depth);
+                self.boxed(&**call);
+                self.expression(&call.callee, depth + 1);
+                self.expressions(&call.arguments, depth + 1);
+            }
+            Expression::TaggedTemplate { ref tag, ref quasi } => {
+                self.node("TaggedTemplate", depth);
+                self.child(tag, depth);
+                self.child(quasi, depth);
+            }
+            Expression::Update { ref argument, .. } => {
+                self.node("Update", depth);
+                self.child(argument, depth);
+            }
+            Expression::Unary { ref argument, .. } => {
+                self.node("Unary", depth);
+                self.child(argument, depth);
+            }
+            Expression::Binary {
+                ref lhs, ref rhs, ..
+            } => {
+                self.node("Binary", depth);
+                self.child(lhs, depth);
+                self.child(rhs, depth);
+            }
+            Expression::Conditional {
+                ref test,
+                ref alternate,
+                ref consequent,
+            } => {
+                self.node("Conditional", depth);
+                self.child(test, depth);
+                self.child(alternate, depth);
+                self.child(consequent, depth);
+            }
+            Expression::Assignment {
+                ref lhs, ref rhs, ..
+            } => {
+                self.node("Assignment", depth);
+                self.child(lhs, depth);
+                self.child(rhs, depth);
+            }
+            Expression::Yield { ref argument, .. } => {
+                self.node("Yield", depth);
+                if let Some(ref argument) = *argument {
+                    self.child(argument, depth);
+                }
+            }
+            Expression::Comma(ref expressions) => {
+                self.node("Comma", depth);
+                self.expressions(expressions, depth + 1);
+            }
+            Expression::JsxElement(ref element) => {
+                self.node("JsxElement", depth);
+                self.boxed(&**element);
+                self.vec(&element.attributes);
+                for attribute in &element.attributes {
+                    self.node("JsxAttribute", depth + 1);
+                    match *attribute {
+                        JsxAttribute::JsxSpreadAttribute { ref expression } => {
+                            self.expression(expression, depth + 2)
+                        }
+                        JsxAttribute::JsxAttribute {
+                            value: Some(ref value),
+                            ..
+                        } => self.expression(value, depth + 2),
+                        JsxAttribute::JsxAttribute { value: None, .. } => {}
+                    }
+                }
+                self.expressions(&element.children, depth + 1);
+            }
+            Expression::JsxFragment(ref children) => {
+                self.node("JsxFragment", depth);
+                self.expressions(children, depth + 1);
+            }
+        }
+    }
+
+    /// This counts a boxed child expression, including the box itself.
+    fn child(&mut self, child: &Expression, depth: usize) {
+        self.boxed(child);
+        self.expression(child, depth + 1);
+    }
+
+    fn expressions(&mut self, expressions: &Vec<Expression>, depth: usize) {
+        self.vec(expressions);
+        for expression in expressions {
+            self.expression(expression, depth);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement, _depth: usize) {
+        match *statement {}
+    }
+}
+
+impl Program {
+    /// This counts the nodes in the program. The program itself is not counted, but the
+    /// heap bytes include its hashbang and comments.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        stats.vec(&self.body);
+        for statement in &self.body {
+            stats.statement(statement, 1);
+        }
+        if let Some(ref hashbang) = self.hashbang {
+            stats.string(hashbang);
+        }
+        stats.vec(&self.comments);
+        for comment in &self.comments {
+            stats.string(&comment.text);
+        }
+        stats
+    }
+}
+
+impl Expression {
+    /// This counts the nodes in the expression, including the expression itself.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        stats.expression(self, 1);
+        stats
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use super::*;
+    use span::Span;
+
+    #[test]
+    fn test_expression_stats() {
+        let object = Expression::ObjectLiteral(vec![build_ast!([id "a"]: [null])]);
+        let call = Expression::Call(Box::new(Call {
+            callee: build_ast!(id "f"),
+            arguments: vec![build_ast!(str "abc".to_string()), object],
+        }));
+        let expression = Expression::ArrayLiteral(vec![build_ast!(num 1.0), call]);
+        let stats = expression.stats();
+        assert_eq!(
+            stats.node_counts.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("ArrayLiteral", 1),
+                ("Call", 1),
+                ("IdReference", 2),
+                ("Literal", 3),
+                ("ObjectLiteral", 1),
+                ("Property", 1),
+            ]
+        );
+        // array > call > object > property > key
+        assert_eq!(stats.max_depth, 5);
+        assert!(stats.heap_bytes >= size_of::<Call>() + 3 + 2 * size_of::<Expression>());
+    }
+
+    #[test]
+    fn test_program_stats() {
+        let program = Program {
+            source_type: SourceType::Script,
+            body: Vec::new(),
+            span: Span::default(),
+            hashbang: None,
+            comments: Vec::new(),
+        };
+        assert_eq!(program.stats(), Stats::default());
+
+        let program = ::parse("#!/usr/bin/env node\n// one\n/* two */").unwrap();
+        assert_eq!(program.comments.len(), 2);
+        let stats = program.stats();
+        assert_eq!(stats.total_nodes(), 0);
+        let texts = program.hashbang.as_ref().unwrap().capacity()
+            + program
+                .comments
+                .iter()
+                .map(|comment| comment.text.capacity())
+                .sum::<usize>();
+        assert!(texts >= "/usr/bin/env node".len() + " one".len() + " two ".len());
+        assert_eq!(
+            stats.heap_bytes,
+            program.comments.capacity() * size_of::<Comment>() + texts
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! This module picks "did you mean" suggestions for misspelled names, eg.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::suggest::closest;
+//!
+//! let names = ["length", "push", "slice"];
+//! assert_eq!(closest("lenght", names.iter().cloned()), Some("length"));
+//! assert_eq!(closest("map", names.iter().cloned()), None);
+//! ```
+//!
+//! Names are compared by their edit distance, where a swap of two characters next to
+//! each other counts as a single edit. A name is only suggested if it is at most one edit
+//! away for every three characters of the misspelling, so short words like `a` never get
+//! a suggestion.
+//!
+//! The parser uses this to suggest a keyword when it gives up at a word that looks like
+//! a misspelled one, eg. `fucntion`. The suggestion is kept in `SyntaxError::suggestion`.
+
+use parser::KEYWORDS;
+
+/// This counts the insertions, deletions, substitutions and swaps of neighbouring
+/// characters that it takes to turn one word into the other.
+/// [Reference](https://en.wikipedia.org/wiki/Damerau%E2%80%93Levenshtein_distance#Optimal_string_alignment_distance)
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // each row has the distances from a prefix of a to every prefix of b, and only the
+    // rows of the two prefixes before the current one are kept
+    let mut before: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            row[j] = (previous[j] + 1)
+                .min(row[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+        }
+        before = previous;
+        previous = row;
+    }
+    previous[b.len()]
+}
+
+/// This picks the candidate that is the closest to the word, if any is close enough to be
+/// a likely misspelling. Candidates that are the same as the word are never picked, and
+/// ties go to the candidate that comes first.
+pub fn closest<'a, I>(word: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let limit = word.chars().count() / 3;
+    let mut best: Option<(usize, &'a str)> = None;
+    for candidate in candidates {
+        let distance = edit_distance(word, candidate);
+        if distance == 0 || distance > limit {
+            continue;
+        }
+        if best.is_none_or(|(best, _)| distance < best) {
+            best = Some((distance, candidate));
+        }
+    }
+    best.map(|(_, candidate)| candidate)
+}
+
+/// This picks the keyword that the word is likely a misspelling of, eg. `return` for
+/// `retrun`. Words that are keywords themselves get no suggestion.
+pub fn keyword(word: &str) -> Option<&'static str> {
+    let mut keywords: Vec<&'static str> = KEYWORDS.iter().cloned().collect();
+    // `let` and `yield` are only kept from being names in strict mode, but are still worth
+    // suggesting
+    keywords.extend(&["let", "yield", "null", "true", "false"]);
+    if keywords.contains(&word) {
+        return None;
+    }
+    // the set has no order, so the keywords are sorted to break ties the same way every
+    // time
+    keywords.sort();
+    closest(word, keywords)
+}
+
+#[cfg(test)]
+mod suggest_tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("fucntion", "function"), 1);
+        assert_eq!(edit_distance("ca", "abc"), 3);
+        assert_eq!(edit_distance("é", "e"), 1);
+    }
+
+    #[test]
+    fn test_keyword() {
+        assert_eq!(keyword("fucntion"), Some("function"));
+        assert_eq!(keyword("retrun"), Some("return"));
+        assert_eq!(keyword("esle"), Some("else"));
+        assert_eq!(keyword("nul"), Some("null"));
+        assert_eq!(keyword("function"), None);
+        assert_eq!(keyword("fn"), None);
+        assert_eq!(keyword("value"), None);
+    }
+}
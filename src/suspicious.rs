@@ -0,0 +1,250 @@
+//! This module finds characters that can make source code look different from what it
+//! actually does, like in the "trojan source" attack (CVE-2021-42574), eg.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::edit::apply_edits;
+//! use ecmascript::suspicious::suspicious_characters;
+//!
+//! // the first letter is a cyrillic а
+//! let source = "let \u{430}dmin = true;";
+//! let found = suspicious_characters(source);
+//! assert_eq!(found[0].to_string(), "U+0430 looks like 'a'");
+//! let fixes: Vec<_> = found.into_iter().map(|s| s.fix).collect();
+//! assert_eq!(apply_edits(source, &fixes).unwrap(), "let \\u0430dmin = true;");
+//! ```
+//!
+//! Two kinds of characters are reported:
+//!
+//! * Bidirectional control characters anywhere in the source, since they reorder how the
+//!   text is displayed. Inside of a comment or a string they can hide code from a reader.
+//! * Confusable characters in a word, if they look like an ASCII letter and the word either
+//!   has ASCII letters as well, or is only made of confusable characters.
+//!
+//! This works on the source text, so it also checks comments and strings, and it works on
+//! source that does not parse. Each result has a fix that replaces the character with a
+//! unicode escape, which means the same thing in strings, templates and identifiers.
+
+use edit::TextEdit;
+use parser::satisfy_id_continue;
+use span::{Span, Spanned};
+use std::fmt;
+
+/// Suspicious is a character that should be looked at by a person.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suspicious {
+    /// The span of the character.
+    pub span: Span,
+    /// The character itself.
+    pub character: char,
+    /// Why the character is suspicious.
+    pub kind: SuspiciousKind,
+    /// An edit that replaces the character with its unicode escape.
+    pub fix: TextEdit,
+}
+
+/// SuspiciousKind is the reason that a character was reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuspiciousKind {
+    /// The character changes the direction of the text around it.
+    Bidi,
+    /// The character looks like the ASCII letter.
+    Confusable(char),
+}
+
+impl Spanned for Suspicious {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for Suspicious {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            SuspiciousKind::Bidi => write!(
+                f,
+                "U+{:04X} is a bidirectional control character",
+                self.character as u32
+            ),
+            SuspiciousKind::Confusable(ascii) => {
+                write!(f, "U+{:04X} looks like '{}'", self.character as u32, ascii)
+            }
+        }
+    }
+}
+
+/// This returns every suspicious character in the source text, in order.
+pub fn suspicious_characters(source: &str) -> Vec<Suspicious> {
+    let mut found = Vec::new();
+    let mut word = Vec::new();
+    for (offset, c) in source.char_indices() {
+        if satisfy_id_continue(c) {
+            word.push((offset, c));
+            continue;
+        }
+        check_word(&word, &mut found);
+        word.clear();
+        if is_bidi(c) {
+            found.push(suspicious(offset, c, SuspiciousKind::Bidi));
+        }
+    }
+    check_word(&word, &mut found);
+    found
+}
+
+fn check_word(word: &[(usize, char)], found: &mut Vec<Suspicious>) {
+    let has_ascii = word.iter().any(|&(_, c)| c.is_ascii_alphabetic());
+    let all_confusable = word
+        .iter()
+        .all(|&(_, c)| c.is_ascii() || confusable(c).is_some());
+    if !has_ascii && !all_confusable {
+        return;
+    }
+    for &(offset, c) in word {
+        if let Some(ascii) = confusable(c) {
+            found.push(suspicious(offset, c, SuspiciousKind::Confusable(ascii)));
+        }
+    }
+}
+
+fn suspicious(offset: usize, c: char, kind: SuspiciousKind) -> Suspicious {
+    let span = Span::new(offset as u32, (offset + c.len_utf8()) as u32);
+    Suspicious {
+        span,
+        character: c,
+        kind,
+        fix: TextEdit::new(span, escape(c)),
+    }
+}
+
+/// This escapes a character, using the short form when it fits in 4 hex digits.
+fn escape(c: char) -> String {
+    if (c as u32) <= 0xFFFF {
+        format!("\\u{:04X}", c as u32)
+    } else {
+        format!("\\u{{{:X}}}", c as u32)
+    }
+}
+
+// https://www.unicode.org/reports/tr9/#Explicit_Directional_Embeddings
+fn is_bidi(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// The ASCII letter that a character is most easily mistaken for. This is a small part of
+/// the unicode confusables table, with the cyrillic and greek letters that are
+/// indistinguishable from latin letters in most fonts.
+fn confusable(c: char) -> Option<char> {
+    CONFUSABLES
+        .iter()
+        .find(|&&(confusable, _)| confusable == c)
+        .map(|&(_, ascii)| ascii)
+}
+
+const CONFUSABLES: &[(char, char)] = &[
+    // cyrillic
+    ('\u{0430}', 'a'),
+    ('\u{0441}', 'c'),
+    ('\u{0501}', 'd'),
+    ('\u{0435}', 'e'),
+    ('\u{04BB}', 'h'),
+    ('\u{0456}', 'i'),
+    ('\u{0458}', 'j'),
+    ('\u{043E}', 'o'),
+    ('\u{0440}', 'p'),
+    ('\u{051B}', 'q'),
+    ('\u{0455}', 's'),
+    ('\u{051D}', 'w'),
+    ('\u{0445}', 'x'),
+    ('\u{0443}', 'y'),
+    ('\u{0410}', 'A'),
+    ('\u{0412}', 'B'),
+    ('\u{0421}', 'C'),
+    ('\u{0415}', 'E'),
+    ('\u{041D}', 'H'),
+    ('\u{0406}', 'I'),
+    ('\u{0408}', 'J'),
+    ('\u{041A}', 'K'),
+    ('\u{041C}', 'M'),
+    ('\u{041E}', 'O'),
+    ('\u{0420}', 'P'),
+    ('\u{0405}', 'S'),
+    ('\u{0422}', 'T'),
+    ('\u{0425}', 'X'),
+    ('\u{04AE}', 'Y'),
+    // greek
+    ('\u{03BF}', 'o'),
+    ('\u{03BD}', 'v'),
+    ('\u{0391}', 'A'),
+    ('\u{0392}', 'B'),
+    ('\u{0395}', 'E'),
+    ('\u{0396}', 'Z'),
+    ('\u{0397}', 'H'),
+    ('\u{0399}', 'I'),
+    ('\u{039A}', 'K'),
+    ('\u{039C}', 'M'),
+    ('\u{039D}', 'N'),
+    ('\u{039F}', 'O'),
+    ('\u{03A1}', 'P'),
+    ('\u{03A4}', 'T'),
+    ('\u{03A5}', 'Y'),
+    ('\u{03A7}', 'X'),
+];
+
+#[cfg(test)]
+mod suspicious_tests {
+    use super::*;
+
+    #[test]
+    fn test_bidi() {
+        // the "early return" example, the comment hides the return from a reader
+        let source = "/*\u{202E} } \u{2066}if (isAdmin)\u{2069} \u{2066} begin admins only */";
+        let found = suspicious_characters(source);
+        assert_eq!(
+            found
+                .iter()
+                .map(|s| (s.span.start, s.character))
+                .collect::<Vec<_>>(),
+            vec![
+                (2, '\u{202E}'),
+                (8, '\u{2066}'),
+                (23, '\u{2069}'),
+                (27, '\u{2066}'),
+            ]
+        );
+        assert!(found.iter().all(|s| s.kind == SuspiciousKind::Bidi));
+        assert_eq!(found[0].fix.replacement, "\\u202E");
+        assert_eq!(
+            found[0].to_string(),
+            "U+202E is a bidirectional control character"
+        );
+    }
+
+    #[test]
+    fn test_confusables() {
+        let found = suspicious_characters("\u{0435}val(x); p\u{0430}y; \u{0440}\u{0430}\u{0443}");
+        assert_eq!(
+            found.iter().map(|s| s.kind).collect::<Vec<_>>(),
+            vec![
+                SuspiciousKind::Confusable('e'),
+                SuspiciousKind::Confusable('a'),
+                SuspiciousKind::Confusable('p'),
+                SuspiciousKind::Confusable('a'),
+                SuspiciousKind::Confusable('y'),
+            ]
+        );
+        assert_eq!(found[0].span, Span::new(0, 2));
+        // real words in other scripts are left alone
+        assert!(suspicious_characters("let привет = 'Καλημέρα';").is_empty());
+        assert!(suspicious_characters("let admin = true;").is_empty());
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape('\u{0430}'), "\\u0430");
+        assert_eq!(escape('\u{1D5BA}'), "\\u{1D5BA}");
+    }
+}
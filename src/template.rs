@@ -0,0 +1,464 @@
+//! This module contains templates, which are expressions with placeholders in them. A
+//! template can be matched against expressions to find code with the same shape, and
+//! filled in with the expressions that its placeholders matched to write new code, eg.
+//!
+//! ```
+//! # extern crate ecmascript;
+//! use ecmascript::sexp;
+//! use ecmascript::template::Template;
+//!
+//! // `require($MOD)`
+//! let pattern = Template::new(sexp::parse_expression("(call (id require) (id $MOD))").unwrap());
+//! // `import($MOD)`
+//! let replacement = Template::new(sexp::parse_expression("(call (id import) (id $MOD))").unwrap());
+//!
+//! let code = sexp::parse_expression(r#"(array (call (id require) (str "fs")))"#).unwrap();
+//! let bindings = pattern.find_all(&code)[0].bindings.clone();
+//! assert_eq!(bindings["$MOD"].to_sexp(), r#"(str "fs")"#);
+//! assert_eq!(
+//!     pattern.rewrite(&code, &replacement).unwrap().to_sexp(),
+//!     r#"(array (call (id import) (str "fs")))"#
+//! );
+//! ```
+//!
+//! A placeholder is an identifier reference that is a `$` followed by capital letters,
+//! digits and underscores, eg. `$MOD` or `$KEY_2`. It matches any expression, and when it
+//! is used more than once in a pattern every use has to match the same expression. `$_`
+//! matches any expression without binding it. Other names that start with a `$`, like
+//! `$` or `$el`, are ordinary identifiers.
+//!
+//! Patterns can be parsed with `parse_expression`, or written in the S-expression form of
+//! the `sexp` module for expressions that the parser can not parse yet.
+
+use ast::*;
+use failure::Fail;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Bindings are the expressions that the placeholders of a template matched, keyed by
+/// the name of the placeholder, eg. `$MOD`.
+pub type Bindings = BTreeMap<String, Expression>;
+
+/// Template is a pattern that can be matched and filled in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pattern: Expression,
+}
+
+/// Match is an expression that a template matched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match<'a> {
+    /// The expression that matched.
+    pub expression: &'a Expression,
+    /// What each placeholder of the template matched.
+    pub bindings: Bindings,
+}
+
+/// UnboundPlaceholder is returned when a template is filled in, but one of its
+/// placeholders has no binding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnboundPlaceholder(pub String);
+
+impl fmt::Display for UnboundPlaceholder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The placeholder `{}` has no binding", self.0)
+    }
+}
+
+impl Fail for UnboundPlaceholder {}
+
+impl Template {
+    /// This creates a template from the pattern.
+    pub fn new(pattern: Expression) -> Template {
+        Template { pattern }
+    }
+
+    /// The pattern, with its placeholders.
+    pub fn pattern(&self) -> &Expression {
+        &self.pattern
+    }
+
+    /// The names of the placeholders in the pattern, in the order that they are first
+    /// used. `$_` is not included, since it never binds anything.
+    pub fn placeholders(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        collect_placeholders(&self.pattern, &mut names);
+        names
+    }
+
+    /// This matches the whole expression against the pattern, and returns what each
+    /// placeholder matched if it does.
+    pub fn matches(&self, expression: &Expression) -> Option<Bindings> {
+        let mut bindings = Bindings::new();
+        if match_expression(&self.pattern, expression, &mut bindings) {
+            Some(bindings)
+        } else {
+            None
+        }
+    }
+
+    /// This finds every expression in the tree that matches, including the root, in
+    /// source order. Matches can be nested inside of each other, eg. `$A + $B` matches
+    /// `a + b + c` twice.
+    pub fn find_all<'a>(&self, root: &'a Expression) -> Vec<Match<'a>> {
+        let mut found = Vec::new();
+        self.find(root, &mut found);
+        found
+    }
+
+    fn find<'a>(&self, expression: &'a Expression, found: &mut Vec<Match<'a>>) {
+        if let Some(bindings) = self.matches(expression) {
+            found.push(Match {
+                expression,
+                bindings,
+            });
+        }
+        for child in children(expression) {
+            self.find(child, found);
+        }
+    }
+
+    /// This fills in the placeholders of the pattern with their bindings. Bindings for
+    /// placeholders that the pattern does not use are ignored.
+    pub fn instantiate(&self, bindings: &Bindings) -> Result<Expression, UnboundPlaceholder> {
+        instantiate(&self.pattern, bindings)
+    }
+
+    /// This replaces every expression in the tree that matches with the replacement,
+    /// filled in with what the match bound. Expressions inside of a match are not
+    /// matched again, so the outermost match wins.
+    pub fn rewrite(
+        &self,
+        root: &Expression,
+        replacement: &Template,
+    ) -> Result<Expression, UnboundPlaceholder> {
+        match self.matches(root) {
+            Some(bindings) => replacement.instantiate(&bindings),
+            None => map_children(root, &mut |child| self.rewrite(child, replacement)),
+        }
+    }
+}
+
+/// This is true if the name is a placeholder, eg. `$MOD`.
+pub fn is_placeholder(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next() == Some('$')
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_uppercase() || c == '_')
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn placeholder(expression: &Expression) -> Option<&str> {
+    match *expression {
+        Expression::IdReference(ref id) if is_placeholder(id) => Some(id.as_str()),
+        _ => None,
+    }
+}
+
+fn collect_placeholders<'a>(expression: &'a Expression, names: &mut Vec<&'a str>) {
+    match placeholder(expression) {
+        Some("$_") => {}
+        Some(name) => {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        None => {
+            for child in children(expression) {
+                collect_placeholders(child, names);
+            }
+        }
+    }
+}
+
+// Two expressions have the same shape if they are equal once their children are taken
+// out, so only the children have to be compared one by one.
+fn match_expression(pattern: &Expression, target: &Expression, bindings: &mut Bindings) -> bool {
+    match placeholder(pattern) {
+        Some("$_") => true,
+        Some(name) => match bindings.get(name) {
+            Some(bound) => bound == target,
+            None => {
+                bindings.insert(name.to_string(), target.clone());
+                true
+            }
+        },
+        None => {
+            shape(pattern) == shape(target)
+                && children(pattern)
+                    .into_iter()
+                    .zip(children(target))
+                    .all(|(pattern, target)| match_expression(pattern, target, bindings))
+        }
+    }
+}
+
+fn instantiate(
+    pattern: &Expression,
+    bindings: &Bindings,
+) -> Result<Expression, UnboundPlaceholder> {
+    match placeholder(pattern) {
+        Some(name) => bindings
+            .get(name)
+            .cloned()
+            .ok_or_else(|| UnboundPlaceholder(name.to_string())),
+        None => map_children(pattern, &mut |child| instantiate(child, bindings)),
+    }
+}
+
+fn shape(expression: &Expression) -> Expression {
+    let result: Result<_, ()> = map_children(expression, &mut |_| Ok(Expression::This));
+    result.unwrap()
+}
+
+fn children(expression: &Expression) -> Vec<&Expression> {
+    let mut children = Vec::new();
+    let _: Result<_, ()> = map_children(expression, &mut |child| {
+        children.push(child);
+        Ok(Expression::This)
+    });
+    children
+}
+
+/// This copies the expression, with each of its direct children replaced by what `f`
+/// returns for it. The children are visited in source order.
+fn map_children<'a, E, F>(expression: &'a Expression, f: &mut F) -> Result<Expression, E>
+where
+    F: FnMut(&'a Expression) -> Result<Expression, E>,
+{
+    let mut boxed = |expression: &'a Expression| f(expression).map(Box::new);
+    Ok(match *expression {
+        Expression::This
+        | Expression::IdReference(_)
+        | Expression::Literal(_)
+        | Expression::Function(_)
+        | Expression::RegexLiteral(_)
+        | Expression::Super
+        | Expression::MetaProperty => expression.clone(),
+        Expression::ArrayLiteral(ref elements) => {
+            Expression::ArrayLiteral(map_all(elements, &mut boxed)?)
+        }
+        Expression::ObjectLiteral(ref properties) => {
+            let mut mapped = Vec::new();
+            for property in properties {
+                mapped.push(Property {
+                    key: *boxed(&property.key)?,
+                    value: *boxed(&property.value)?,
+                    kind: property.kind.clone(),
+                });
+            }
+            Expression::ObjectLiteral(mapped)
+        }
+        Expression::TemplateLiteral(ref elements) => {
+            let mut mapped = Vec::new();
+            for element in elements {
+                mapped.push(match *element {
+                    TemplateLiteralElement::TemplateElement(ref element) => {
+                        TemplateLiteralElement::TemplateElement(element.clone())
+                    }
+                    TemplateLiteralElement::Expression(ref expression) => {
+                        TemplateLiteralElement::Expression(*boxed(expression)?)
+                    }
+                });
+            }
+            Expression::TemplateLiteral(mapped)
+        }
+        Expression::Spread(ref argument) => Expression::Spread(boxed(argument)?),
+        Expression::Member {
+            ref lhs,
+            ref rhs,
+            computed,
+        } => Expression::Member {
+            lhs: boxed(lhs)?,
+            rhs: boxed(rhs)?,
+            computed,
+        },
+        Expression::New(ref new) => Expression::New(Box::new(New {
+            callee: *boxed(&new.callee)?,
+            arguments: map_all(&new.arguments, &mut boxed)?,
+        })),
+        Expression::Call(ref call) => Expression::Call(Box::new(Call {
+            callee: *boxed(&call.callee)?,
+            arguments: map_all(&call.arguments, &mut boxed)?,
+        })),
+        Expression::TaggedTemplate { ref tag, ref quasi } => Expression::TaggedTemplate {
+            tag: boxed(tag)?,
+            quasi: boxed(quasi)?,
+        },
+        Expression::Update {
+            ref operator,
+            ref argument,
+            prefix,
+        } => Expression::Update {
+            operator: operator.clone(),
+            argument: boxed(argument)?,
+            prefix,
+        },
+        Expression::Unary {
+            ref operator,
+            ref argument,
+        } => Expression::Unary {
+            operator: operator.clone(),
+            argument: boxed(argument)?,
+        },
+        Expression::Binary {
+            ref operator,
+            ref lhs,
+            ref rhs,
+        } => Expression::Binary {
+            operator: operator.clone(),
+            lhs: boxed(lhs)?,
+            rhs: boxed(rhs)?,
+        },
+        Expression::Conditional {
+            ref test,
+            ref alternate,
+            ref consequent,
+        } => Expression::Conditional {
+            test: boxed(test)?,
+            alternate: boxed(alternate)?,
+            consequent: boxed(consequent)?,
+        },
+        Expression::Assignment {
+            ref operator,
+            ref lhs,
+            ref rhs,
+        } => Expression::Assignment {
+            operator: operator.clone(),
+            lhs: boxed(lhs)?,
+            rhs: boxed(rhs)?,
+        },
+        Expression::Yield {
+            ref argument,
+            delegate,
+        } => Expression::Yield {
+            argument: match *argument {
+                Some(ref argument) => Some(boxed(argument)?),
+                None => None,
+            },
+            delegate,
+        },
+        Expression::Comma(ref expressions) => Expression::Comma(map_all(expressions, &mut boxed)?),
+        Expression::JsxElement(ref element) => {
+            let mut attributes = Vec::new();
+            for attribute in &element.attributes {
+                attributes.push(match *attribute {
+                    JsxAttribute::JsxSpreadAttribute { ref expression } => {
+                        JsxAttribute::JsxSpreadAttribute {
+                            expression: *boxed(expression)?,
+                        }
+                    }
+                    JsxAttribute::JsxAttribute {
+                        ref name,
+                        ref value,
+                    } => JsxAttribute::JsxAttribute {
+                        name: name.clone(),
+                        value: match *value {
+                            Some(ref value) => Some(*boxed(value)?),
+                            None => None,
+                        },
+                    },
+                });
+            }
+            Expression::JsxElement(Box::new(JsxElement {
+                name: element.name.clone(),
+                attributes,
+                children: map_all(&element.children, &mut boxed)?,
+            }))
+        }
+        Expression::JsxFragment(ref children) => {
+            Expression::JsxFragment(map_all(children, &mut boxed)?)
+        }
+    })
+}
+
+fn map_all<'a, E, F>(expressions: &'a [Expression], f: &mut F) -> Result<Vec<Expression>, E>
+where
+    F: FnMut(&'a Expression) -> Result<Box<Expression>, E>,
+{
+    expressions.iter().map(|e| f(e).map(|e| *e)).collect()
+}
+
+#[cfg(test)]
+mod template_tests {
+    use super::*;
+    use sexp::parse_expression;
+
+    fn template(source: &str) -> Template {
+        Template::new(parse_expression(source).unwrap())
+    }
+
+    #[test]
+    fn test_matches() {
+        // `$OBJ.hasOwnProperty($KEY)`
+        let pattern = template("(call (member (id $OBJ) (id hasOwnProperty)) (id $KEY))");
+        assert_eq!(pattern.placeholders(), vec!["$OBJ", "$KEY"]);
+        let code =
+            parse_expression(r#"(call (member (id a) (id hasOwnProperty)) (str "b"))"#).unwrap();
+        let bindings = pattern.matches(&code).unwrap();
+        assert_eq!(bindings["$OBJ"], parse_expression("(id a)").unwrap());
+        assert_eq!(bindings["$KEY"], parse_expression(r#"(str "b")"#).unwrap());
+        // the property name is not a placeholder, and neither is `$`
+        let other = parse_expression("(call (member (id a) (id has)) (id b))").unwrap();
+        assert_eq!(pattern.matches(&other), None);
+        assert_eq!(template("(id $)").matches(&other), None);
+
+        // a placeholder that is used twice has to match the same expression both times
+        let same = template("(binary === (id $A) (id $A))");
+        assert!(same
+            .matches(&parse_expression("(binary === (id a) (id a))").unwrap())
+            .is_some());
+        assert!(same
+            .matches(&parse_expression("(binary === (id a) (id b))").unwrap())
+            .is_none());
+        let any = template("(binary === (id $_) (id $_))");
+        assert!(any
+            .matches(&parse_expression("(binary === (id a) (num 1.0))").unwrap())
+            .is_some());
+        assert_eq!(any.placeholders(), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_find_all() {
+        let pattern = template("(binary + (id $A) (id $B))");
+        let code = parse_expression("(array (binary + (binary + (id a) (id b)) (id c)))").unwrap();
+        let found: Vec<_> = pattern
+            .find_all(&code)
+            .iter()
+            .map(|found| found.expression.to_sexp())
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                "(binary + (binary + (id a) (id b)) (id c))",
+                "(binary + (id a) (id b))",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_instantiate() {
+        let pattern = template("(call (id require) (id $MOD))");
+        let replacement = template("(member (call (id import) (id $MOD)) (id default))");
+        let code = parse_expression(
+            r#"(object (init (id a) (call (id require) (call (id require) (str "b")))))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            pattern.rewrite(&code, &replacement).unwrap().to_sexp(),
+            r#"(object (init (id a) (member (call (id import) (call (id require) (str "b"))) (id default))))"#
+        );
+        assert_eq!(
+            template("(id $OTHER)").instantiate(&Bindings::new()),
+            Err(UnboundPlaceholder("$OTHER".to_string()))
+        );
+        assert_eq!(
+            pattern
+                .rewrite(&code, &template("(id $OTHER)"))
+                .unwrap_err()
+                .to_string(),
+            "The placeholder `$OTHER` has no binding"
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! This module runs the parser tests of [test262](https://github.com/tc39/test262), the
+//! conformance suite for ECMAScript, eg.
+//!
+//! ```no_run
+//! # extern crate ecmascript;
+//! use ecmascript::test262::run_directory;
+//!
+//! let summary = run_directory("test262/test/language").unwrap();
+//! println!("{}", summary);
+//! for failure in &summary.failures {
+//!     println!("{}: {}", failure.path.display(), failure.reason);
+//! }
+//! ```
+//!
+//! Each test has its metadata in a `/*--- ---*/` comment, which says how it has to be
+//! run. The flags are followed as described in
+//! [INTERPRETING.md](https://github.com/tc39/test262/blob/main/INTERPRETING.md):
+//!
+//! - `module` tests are parsed as a module, once.
+//! - `raw` tests are parsed as a script exactly as they are, once.
+//! - `onlyStrict` tests are parsed as a script with `"use strict";` in front of them, and
+//!   `noStrict` tests without it.
+//! - Every other test is parsed both ways.
+//!
+//! A test with a `negative` phase of `parse` or `early` passes if every way of parsing
+//! it fails, and any other test passes if every way of parsing it succeeds. The parser
+//! does not have a separate early error pass, so both phases are expected to fail to
+//! parse. Fixtures that other tests import are skipped.
+//!
+//! This module is only built with the `test262` feature.
+
+use ast::SourceType;
+use parser::try_parse_as;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Metadata is the part of the frontmatter of a test that decides how it is run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// The flags, eg. `module` or `onlyStrict`.
+    pub flags: Vec<String>,
+    /// The features that the test uses, eg. `BigInt`.
+    pub features: Vec<String>,
+    /// The phase that the test is expected to fail in, if it is a negative test.
+    pub negative_phase: Option<String>,
+    /// The type of the error that a negative test expects, eg. `SyntaxError`.
+    pub negative_type: Option<String>,
+}
+
+impl Metadata {
+    /// This reads the metadata from the frontmatter of a test. It returns `None` if the
+    /// source does not have any frontmatter, eg. for a file of the harness.
+    ///
+    /// Only the subset of YAML that test262 uses for these fields is understood, ie.
+    /// lists written as `[a, b]` or as `- a` lines, and the nested fields of `negative`.
+    pub fn parse(source: &str) -> Option<Metadata> {
+        let start = source.find("/*---")? + 5;
+        let end = start + source[start..].find("---*/")?;
+        let mut metadata = Metadata::default();
+        let mut field = String::new();
+        for line in source[start..end].lines() {
+            let indented = line.starts_with(' ') || line.starts_with('\t');
+            let line = line.trim();
+            if let Some(item) = line.strip_prefix("- ") {
+                metadata.push(&field, item.trim());
+            } else if let Some(colon) = line.find(':') {
+                let (key, value) = (line[..colon].trim(), line[colon + 1..].trim());
+                if indented && field == "negative" {
+                    match key {
+                        "phase" => metadata.negative_phase = Some(value.to_string()),
+                        "type" => metadata.negative_type = Some(value.to_string()),
+                        _ => {}
+                    }
+                    continue;
+                }
+                field = key.to_string();
+                if value.starts_with('[') && value.ends_with(']') {
+                    let items = value[1..value.len() - 1].split(',');
+                    for item in items.map(str::trim).filter(|item| !item.is_empty()) {
+                        metadata.push(&field, item);
+                    }
+                }
+            }
+        }
+        Some(metadata)
+    }
+
+    fn push(&mut self, field: &str, item: &str) {
+        match field {
+            "flags" => self.flags.push(item.to_string()),
+            "features" => self.features.push(item.to_string()),
+            _ => {}
+        }
+    }
+
+    /// This is true if the test has the flag.
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// This is true if the test expects the source text to not parse.
+    pub fn expects_syntax_error(&self) -> bool {
+        matches!(
+            self.negative_phase.as_deref(),
+            Some("parse") | Some("early")
+        )
+    }
+
+    /// The ways that the test has to be parsed, as the source type and the text to put in
+    /// front of the source.
+    pub fn modes(&self) -> Vec<(SourceType, &'static str)> {
+        const STRICT: &str = "\"use strict\";\n";
+        if self.has_flag("module") {
+            vec![(SourceType::Module, "")]
+        } else if self.has_flag("raw") || self.has_flag("noStrict") {
+            vec![(SourceType::Script, "")]
+        } else if self.has_flag("onlyStrict") {
+            vec![(SourceType::Script, STRICT)]
+        } else {
+            vec![(SourceType::Script, ""), (SourceType::Script, STRICT)]
+        }
+    }
+}
+
+/// Outcome is the result of running a single test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The parser did what the test expected.
+    Pass,
+    /// The parser did not do what the test expected, with the reason why.
+    Fail(String),
+    /// The test was not run, eg. because it is a fixture.
+    Skip,
+}
+
+/// This runs a single test, with the source text of the test file.
+pub fn run_test(source: &str) -> Outcome {
+    let metadata = match Metadata::parse(source) {
+        Some(metadata) => metadata,
+        None => return Outcome::Skip,
+    };
+    for (source_type, prefix) in metadata.modes() {
+        let mode = if prefix.is_empty() {
+            ""
+        } else {
+            " in strict mode"
+        };
+        let text = format!("{}{}", prefix, source);
+        match (
+            try_parse_as(&text, source_type),
+            metadata.expects_syntax_error(),
+        ) {
+            (Ok(_), true) => {
+                return Outcome::Fail(format!("expected a syntax error{}", mode));
+            }
+            (Err(error), false) => {
+                let message = error.message.trim_end().replace('\n', ", ");
+                return Outcome::Fail(format!(
+                    "unexpected syntax error{} at {}:{}: {}",
+                    mode, error.line, error.column, message
+                ));
+            }
+            _ => {}
+        }
+    }
+    Outcome::Pass
+}
+
+/// Failure is a test that did not pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// The path of the test file.
+    pub path: PathBuf,
+    /// Why the test failed.
+    pub reason: String,
+}
+
+/// Summary is the result of running many tests.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// The number of tests that passed.
+    pub passed: usize,
+    /// The number of tests that were skipped.
+    pub skipped: usize,
+    /// The tests that failed, sorted by their path.
+    pub failures: Vec<Failure>,
+}
+
+impl Summary {
+    /// The number of tests that were run.
+    pub fn total(&self) -> usize {
+        self.passed + self.failures.len()
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let percent = if self.total() == 0 {
+            0.0
+        } else {
+            self.passed as f64 * 100.0 / self.total() as f64
+        };
+        write!(
+            f,
+            "{} of {} tests passed ({:.2}%), {} skipped",
+            self.passed,
+            self.total(),
+            percent,
+            self.skipped
+        )
+    }
+}
+
+/// This runs every `.js` file in the directory and the directories inside of it. Files
+/// that end in `_FIXTURE.js` are only imported by other tests, so they are skipped.
+pub fn run_directory<P: AsRef<Path>>(directory: P) -> io::Result<Summary> {
+    let mut paths = Vec::new();
+    find_tests(directory.as_ref(), &mut paths)?;
+    paths.sort();
+
+    let mut summary = Summary::default();
+    for path in paths {
+        if path.to_string_lossy().ends_with("_FIXTURE.js") {
+            summary.skipped += 1;
+            continue;
+        }
+        match run_test(&fs::read_to_string(&path)?) {
+            Outcome::Pass => summary.passed += 1,
+            Outcome::Skip => summary.skipped += 1,
+            Outcome::Fail(reason) => summary.failures.push(Failure { path, reason }),
+        }
+    }
+    Ok(summary)
+}
+
+fn find_tests(directory: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_tests(&path, paths)?;
+        } else if path.extension().is_some_and(|extension| extension == "js") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test262_tests {
+    use super::*;
+
+    const NEGATIVE: &str = "// Copyright
+/*---
+description: >
+  an unterminated comment
+negative:
+  phase: parse
+  type: SyntaxError
+flags: [onlyStrict]
+features:
+  - hashbang
+  - BigInt
+---*/
+
+$DONOTEVALUATE();
+/*";
+
+    #[test]
+    fn test_metadata() {
+        let metadata = Metadata::parse(NEGATIVE).unwrap();
+        assert_eq!(
+            metadata,
+            Metadata {
+                flags: vec!["onlyStrict".to_string()],
+                features: vec!["hashbang".to_string(), "BigInt".to_string()],
+                negative_phase: Some("parse".to_string()),
+                negative_type: Some("SyntaxError".to_string()),
+            }
+        );
+        assert!(metadata.expects_syntax_error());
+        assert_eq!(
+            metadata.modes(),
+            vec![(SourceType::Script, "\"use strict\";\n")]
+        );
+        assert_eq!(Metadata::parse("function $DONE() {}"), None);
+        let module = Metadata::parse("/*---\nflags: [module, async]\n---*/").unwrap();
+        assert_eq!(module.modes(), vec![(SourceType::Module, "")]);
+    }
+
+    #[test]
+    fn test_run_test() {
+        // the strict mode directive is not parsed yet, so only raw tests can pass
+        assert_eq!(
+            run_test("/*---\nflags: [raw]\n---*/\n// nothing"),
+            Outcome::Pass
+        );
+        assert_eq!(
+            run_test("/*---\nflags: [raw]\nnegative:\n  phase: parse\n---*/ /*"),
+            Outcome::Pass
+        );
+        assert_eq!(
+            run_test("/*---\nflags: [raw]\n---*/ a"),
+            Outcome::Fail(
+                "unexpected syntax error at 3:7: Unexpected `a`, Expected `end of input`"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            run_test("/*---\nflags: [raw]\nnegative:\n  phase: early\n---*/"),
+            Outcome::Fail("expected a syntax error".to_string())
+        );
+        assert_eq!(run_test("// a harness file"), Outcome::Skip);
+    }
+
+    #[test]
+    fn test_run_directory() {
+        let root = ::std::env::temp_dir().join(format!("ecma-test262-{}", ::std::process::id()));
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("pass.js"), "/*---\nflags: [raw]\n---*/").unwrap();
+        fs::write(root.join("nested").join("fail.js"), "/*---\n---*/ a").unwrap();
+        fs::write(root.join("nested").join("a_FIXTURE.js"), "").unwrap();
+        fs::write(root.join("README.md"), "").unwrap();
+
+        let summary = run_directory(&root).unwrap();
+        assert_eq!((summary.passed, summary.skipped), (1, 1));
+        assert_eq!(summary.failures.len(), 1);
+        assert_eq!(
+            summary.failures[0].path,
+            root.join("nested").join("fail.js")
+        );
+        assert_eq!(
+            summary.to_string(),
+            "1 of 2 tests passed (50.00%), 1 skipped"
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}